@@ -7,10 +7,15 @@ export class AuthService extends BaseService {
 		super(fetch);
 	}
 
-	async handleOauthCallback(provider: string, state: string, code: string): Promise<void> {
+	/**
+	 * Returns the path to navigate to after the login, if any.
+	 */
+	async handleOauthCallback(provider: string, state: string, code: string): Promise<string | null> {
 		const response = await this.fetch(`${PUBLIC_API_URL}/auth/${provider}/callback?state=${state}&code=${code}`);
 		if (!response.ok) {
 			throw new Error(`failed to handle oauth callback: ${response.statusText}`)
 		}
+		const body: { return_to: string | null } = await response.json();
+		return body.return_to;
 	}
 }
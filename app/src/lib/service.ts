@@ -16,7 +16,8 @@ export class BaseService {
 			});
 
 			if (response.status === 401) {
-				goto('/login');
+				const returnTo = window.location.pathname + window.location.search;
+				goto(`/login?return_to=${encodeURIComponent(returnTo)}`);
 			}
 
 			return response;
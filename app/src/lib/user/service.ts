@@ -24,6 +24,19 @@ export class UserService extends BaseService {
 		return data.user ?? undefined;
 	}
 
+	async getActiveSessionsCount(): Promise<number> {
+		const response = await this.fetch(`${PUBLIC_API_URL}/user/me/sessions/count`, {
+			headers: {
+				'Content-Type': 'application/json',
+			},
+		});
+		if (!response.ok) {
+			throw new HttpError(`failed to get active sessions count: ${response.statusText}`, response.status);
+		}
+		const data: { count: number } = await response.json();
+		return data.count;
+	}
+
 	async logoutUser(): Promise<void> {
 		const response = await this.fetch(`${PUBLIC_API_URL}/logout`, {
 			method: "POST",
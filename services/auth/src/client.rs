@@ -3,36 +3,74 @@ use crate::GRPC_PORT;
 use crate::SERVICE_NAME;
 use crate::proto::CreateSessionReq;
 use crate::proto::CreateSessionResp;
+use crate::proto::DeleteAllSessionsReq;
+use crate::proto::DeleteAllSessionsResp;
 use crate::proto::DeleteSessionReq;
 use crate::proto::DeleteSessionResp;
+use crate::proto::GetActiveSessionsCountReq;
+use crate::proto::GetActiveSessionsCountResp;
 use crate::proto::GetOauthAccountReq;
 use crate::proto::GetOauthAccountResp;
 use crate::proto::HandleOauthCallbackReq;
 use crate::proto::HandleOauthCallbackResp;
 use crate::proto::LinkOauthAccountReq;
 use crate::proto::LinkOauthAccountResp;
+use crate::proto::ListSessionsReq;
+use crate::proto::ListSessionsResp;
+use crate::proto::PingReq;
+use crate::proto::PingResp;
+use crate::proto::RefreshOauthTokenReq;
+use crate::proto::RefreshOauthTokenResp;
 use crate::proto::StartOauthLoginReq;
 use crate::proto::StartOauthLoginResp;
 use crate::proto::ValidateSessionReq;
 use crate::proto::ValidateSessionResp;
 use crate::proto::auth_service_client::AuthServiceClient;
-use setup::{middleware::tracing::TracingServiceClient, patched_host};
-use std::{error::Error, str::FromStr as _};
-use tonic::transport::{Channel, Endpoint};
+use setup::endpoint::{
+    ConnectRetry, DEFAULT_REQUEST_TIMEOUT, connect_with_retry, deadline_exceeded, service_endpoint,
+};
+use setup::middleware::tracing::TracingServiceClient;
+use std::error::Error;
+use std::time::Duration;
+use tonic::transport::Channel;
 use tonic::{Request, Response, Status, async_trait};
 
 #[derive(Clone)]
 pub struct AuthClient(AuthServiceClient<TracingServiceClient<Channel>>);
 
 impl AuthClient {
+    /// Creates a client with the [`DEFAULT_REQUEST_TIMEOUT`].
     pub async fn new() -> Result<Self, Box<dyn Error>> {
-        let host = patched_host(String::from(SERVICE_NAME));
-        let endpoint = Endpoint::from_str(&format!("http://{host}:{GRPC_PORT}"))?;
-        let channel = endpoint.connect().await?;
-        let client = TracingServiceClient::new(channel);
-        let client = AuthServiceClient::new(client);
+        Self::new_with_timeout(DEFAULT_REQUEST_TIMEOUT).await
+    }
+
+    /// Creates a client whose requests fail with `DeadlineExceeded`
+    /// if they take longer than `timeout`.
+    ///
+    /// Connecting is retried as configured by [`ConnectRetry::from_env`].
+    pub async fn new_with_timeout(timeout: Duration) -> Result<Self, Box<dyn Error>> {
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(timeout);
+        let channel = connect_with_retry(&endpoint, ConnectRetry::from_env()?).await?;
+
+        Ok(Self::from_channel(channel))
+    }
+
+    /// Creates a client that connects on its first request.
+    ///
+    /// Unlike [`Self::new`], this does not wait for the service to be up,
+    /// so callers can start in any order and recover once it becomes
+    /// reachable. The tradeoff is that a misconfigured or unreachable
+    /// service is only noticed when the first request fails.
+    pub fn lazy() -> Result<Self, Box<dyn Error>> {
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(DEFAULT_REQUEST_TIMEOUT);
+        let channel = endpoint.connect_lazy();
 
-        Ok(Self(client))
+        Ok(Self::from_channel(channel))
+    }
+
+    fn from_channel(channel: Channel) -> Self {
+        let client = TracingServiceClient::new(channel);
+        Self(AuthServiceClient::new(client))
     }
 }
 
@@ -46,109 +84,379 @@ pub trait IAuthClient: Send + Sync + 'static {
     async fn handle_oauth_callback(&self, req: Request<HandleOauthCallbackReq>) -> Result<Response<HandleOauthCallbackResp>, Status>;
     async fn link_oauth_account(&self, req: Request<LinkOauthAccountReq>) -> Result<Response<LinkOauthAccountResp>, Status>;
     async fn get_oauth_account(&self, req: Request<GetOauthAccountReq>) -> Result<Response<GetOauthAccountResp>, Status>;
+    async fn refresh_oauth_token(&self, req: Request<RefreshOauthTokenReq>) -> Result<Response<RefreshOauthTokenResp>, Status>;
+    async fn get_active_sessions_count(&self, req: Request<GetActiveSessionsCountReq>) -> Result<Response<GetActiveSessionsCountResp>, Status>;
+    async fn list_sessions(&self, req: Request<ListSessionsReq>) -> Result<Response<ListSessionsResp>, Status>;
+    async fn delete_all_sessions(&self, req: Request<DeleteAllSessionsReq>) -> Result<Response<DeleteAllSessionsResp>, Status>;
+    async fn ping(&self, req: Request<PingReq>) -> Result<Response<PingResp>, Status>;
 }
 
 #[rustfmt::skip]
 #[async_trait]
 impl IAuthClient for AuthClient {
     async fn create_session(&self, req: Request<CreateSessionReq>) -> Result<Response<CreateSessionResp>, Status> {
-        self.0.clone().create_session(req).await
+        self.0.clone().create_session(req).await.map_err(deadline_exceeded)
     }
     async fn validate_session(&self, req: Request<ValidateSessionReq>) -> Result<Response<ValidateSessionResp>, Status> {
-        self.0.clone().validate_session(req).await
+        self.0.clone().validate_session(req).await.map_err(deadline_exceeded)
     }
     async fn delete_session(&self, req: Request<DeleteSessionReq>) -> Result<Response<DeleteSessionResp>, Status> {
-        self.0.clone().delete_session(req).await
+        self.0.clone().delete_session(req).await.map_err(deadline_exceeded)
     }
     async fn start_oauth_login(&self, req: Request<StartOauthLoginReq>) -> Result<Response<StartOauthLoginResp>, Status> {
-        self.0.clone().start_oauth_login(req).await
+        self.0.clone().start_oauth_login(req).await.map_err(deadline_exceeded)
     }
     async fn handle_oauth_callback(&self, req: Request<HandleOauthCallbackReq>) -> Result<Response<HandleOauthCallbackResp>, Status> {
-        self.0.clone().handle_oauth_callback(req).await
+        self.0.clone().handle_oauth_callback(req).await.map_err(deadline_exceeded)
     }
     async fn link_oauth_account(&self, req: Request<LinkOauthAccountReq>) -> Result<Response<LinkOauthAccountResp>, Status> {
-        self.0.clone().link_oauth_account(req).await
+        self.0.clone().link_oauth_account(req).await.map_err(deadline_exceeded)
     }
     async fn get_oauth_account(&self, req: Request<GetOauthAccountReq>) -> Result<Response<GetOauthAccountResp>, Status> {
-        self.0.clone().get_oauth_account(req).await
+        self.0.clone().get_oauth_account(req).await.map_err(deadline_exceeded)
+    }
+    async fn refresh_oauth_token(&self, req: Request<RefreshOauthTokenReq>) -> Result<Response<RefreshOauthTokenResp>, Status> {
+        self.0.clone().refresh_oauth_token(req).await.map_err(deadline_exceeded)
+    }
+    async fn get_active_sessions_count(&self, req: Request<GetActiveSessionsCountReq>) -> Result<Response<GetActiveSessionsCountResp>, Status> {
+        self.0.clone().get_active_sessions_count(req).await.map_err(deadline_exceeded)
+    }
+    async fn list_sessions(&self, req: Request<ListSessionsReq>) -> Result<Response<ListSessionsResp>, Status> {
+        self.0.clone().list_sessions(req).await.map_err(deadline_exceeded)
+    }
+    async fn delete_all_sessions(&self, req: Request<DeleteAllSessionsReq>) -> Result<Response<DeleteAllSessionsResp>, Status> {
+        self.0.clone().delete_all_sessions(req).await.map_err(deadline_exceeded)
+    }
+    async fn ping(&self, req: Request<PingReq>) -> Result<Response<PingResp>, Status> {
+        self.0.clone().ping(req).await.map_err(deadline_exceeded)
     }
 }
 
 #[cfg(feature = "testutils")]
 pub mod testutils {
     use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tokio::sync::Mutex;
     use tonic::{Request, Response, Status};
 
     #[rustfmt::skip]
     pub struct MockAuthClient {
         pub create_session_req: Mutex<Option<CreateSessionReq>>,
-        pub create_session_resp: Mutex<Option<Result<CreateSessionResp, Status>>>,
+        pub create_session_resp: Mutex<VecDeque<Result<CreateSessionResp, Status>>>,
+        create_session_call_count: AtomicUsize,
         pub validate_session_req: Mutex<Option<ValidateSessionReq>>,
-        pub validate_session_resp: Mutex<Option<Result<ValidateSessionResp, Status>>>,
+        pub validate_session_resp: Mutex<VecDeque<Result<ValidateSessionResp, Status>>>,
+        validate_session_call_count: AtomicUsize,
         pub delete_session_req: Mutex<Option<DeleteSessionReq>>,
-        pub delete_session_resp: Mutex<Option<Result<DeleteSessionResp, Status>>>,
+        pub delete_session_resp: Mutex<VecDeque<Result<DeleteSessionResp, Status>>>,
+        delete_session_call_count: AtomicUsize,
         pub start_oauth_login_req: Mutex<Option<StartOauthLoginReq>>,
-        pub start_oauth_login_resp: Mutex<Option<Result<StartOauthLoginResp, Status>>>,
+        pub start_oauth_login_resp: Mutex<VecDeque<Result<StartOauthLoginResp, Status>>>,
+        start_oauth_login_call_count: AtomicUsize,
         pub handle_oauth_callback_req: Mutex<Option<HandleOauthCallbackReq>>,
-        pub handle_oauth_callback_resp: Mutex<Option<Result<HandleOauthCallbackResp, Status>>>,
+        pub handle_oauth_callback_resp: Mutex<VecDeque<Result<HandleOauthCallbackResp, Status>>>,
+        handle_oauth_callback_call_count: AtomicUsize,
         pub link_oauth_account_req: Mutex<Option<LinkOauthAccountReq>>,
-        pub link_oauth_account_resp: Mutex<Option<Result<LinkOauthAccountResp, Status>>>,
+        pub link_oauth_account_resp: Mutex<VecDeque<Result<LinkOauthAccountResp, Status>>>,
+        link_oauth_account_call_count: AtomicUsize,
         pub get_oauth_account_req: Mutex<Option<GetOauthAccountReq>>,
-        pub get_oauth_account_resp: Mutex<Option<Result<GetOauthAccountResp, Status>>>,
+        pub get_oauth_account_resp: Mutex<VecDeque<Result<GetOauthAccountResp, Status>>>,
+        get_oauth_account_call_count: AtomicUsize,
+        pub refresh_oauth_token_req: Mutex<Option<RefreshOauthTokenReq>>,
+        pub refresh_oauth_token_resp: Mutex<VecDeque<Result<RefreshOauthTokenResp, Status>>>,
+        refresh_oauth_token_call_count: AtomicUsize,
+        pub get_active_sessions_count_req: Mutex<Option<GetActiveSessionsCountReq>>,
+        pub get_active_sessions_count_resp: Mutex<VecDeque<Result<GetActiveSessionsCountResp, Status>>>,
+        get_active_sessions_count_call_count: AtomicUsize,
+        pub list_sessions_req: Mutex<Option<ListSessionsReq>>,
+        pub list_sessions_resp: Mutex<VecDeque<Result<ListSessionsResp, Status>>>,
+        list_sessions_call_count: AtomicUsize,
+        pub delete_all_sessions_req: Mutex<Option<DeleteAllSessionsReq>>,
+        pub delete_all_sessions_resp: Mutex<VecDeque<Result<DeleteAllSessionsResp, Status>>>,
+        delete_all_sessions_call_count: AtomicUsize,
+        pub ping_req: Mutex<Option<PingReq>>,
+        pub ping_resp: Mutex<VecDeque<Result<PingResp, Status>>>,
+        ping_call_count: AtomicUsize,
     }
 
     impl Default for MockAuthClient {
         fn default() -> Self {
             Self {
                 create_session_req: Mutex::new(None),
-                create_session_resp: Mutex::new(None),
+                create_session_resp: Mutex::new(VecDeque::new()),
+                create_session_call_count: AtomicUsize::new(0),
                 validate_session_req: Mutex::new(None),
-                validate_session_resp: Mutex::new(None),
+                validate_session_resp: Mutex::new(VecDeque::new()),
+                validate_session_call_count: AtomicUsize::new(0),
                 delete_session_req: Mutex::new(None),
-                delete_session_resp: Mutex::new(None),
+                delete_session_resp: Mutex::new(VecDeque::new()),
+                delete_session_call_count: AtomicUsize::new(0),
                 start_oauth_login_req: Mutex::new(None),
-                start_oauth_login_resp: Mutex::new(None),
+                start_oauth_login_resp: Mutex::new(VecDeque::new()),
+                start_oauth_login_call_count: AtomicUsize::new(0),
                 handle_oauth_callback_req: Mutex::new(None),
-                handle_oauth_callback_resp: Mutex::new(None),
+                handle_oauth_callback_resp: Mutex::new(VecDeque::new()),
+                handle_oauth_callback_call_count: AtomicUsize::new(0),
                 link_oauth_account_req: Mutex::new(None),
-                link_oauth_account_resp: Mutex::new(None),
+                link_oauth_account_resp: Mutex::new(VecDeque::new()),
+                link_oauth_account_call_count: AtomicUsize::new(0),
                 get_oauth_account_req: Mutex::new(None),
-                get_oauth_account_resp: Mutex::new(None),
+                get_oauth_account_resp: Mutex::new(VecDeque::new()),
+                get_oauth_account_call_count: AtomicUsize::new(0),
+                refresh_oauth_token_req: Mutex::new(None),
+                refresh_oauth_token_resp: Mutex::new(VecDeque::new()),
+                refresh_oauth_token_call_count: AtomicUsize::new(0),
+                get_active_sessions_count_req: Mutex::new(None),
+                get_active_sessions_count_resp: Mutex::new(VecDeque::new()),
+                get_active_sessions_count_call_count: AtomicUsize::new(0),
+                list_sessions_req: Mutex::new(None),
+                list_sessions_resp: Mutex::new(VecDeque::new()),
+                list_sessions_call_count: AtomicUsize::new(0),
+                delete_all_sessions_req: Mutex::new(None),
+                delete_all_sessions_resp: Mutex::new(VecDeque::new()),
+                delete_all_sessions_call_count: AtomicUsize::new(0),
+                ping_req: Mutex::new(None),
+                ping_resp: Mutex::new(VecDeque::new()),
+                ping_call_count: AtomicUsize::new(0),
             }
         }
     }
 
+    #[rustfmt::skip]
+    impl MockAuthClient {
+        /// Queues the result of the next unanswered `create_session` call.
+        pub fn push_create_session_resp(&mut self, resp: Result<CreateSessionResp, Status>) -> &mut Self {
+            self.create_session_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `create_session` was called.
+        pub fn create_session_calls(&self) -> usize {
+            self.create_session_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `validate_session` call.
+        pub fn push_validate_session_resp(&mut self, resp: Result<ValidateSessionResp, Status>) -> &mut Self {
+            self.validate_session_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `validate_session` was called.
+        pub fn validate_session_calls(&self) -> usize {
+            self.validate_session_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `delete_session` call.
+        pub fn push_delete_session_resp(&mut self, resp: Result<DeleteSessionResp, Status>) -> &mut Self {
+            self.delete_session_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `delete_session` was called.
+        pub fn delete_session_calls(&self) -> usize {
+            self.delete_session_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `start_oauth_login` call.
+        pub fn push_start_oauth_login_resp(&mut self, resp: Result<StartOauthLoginResp, Status>) -> &mut Self {
+            self.start_oauth_login_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `start_oauth_login` was called.
+        pub fn start_oauth_login_calls(&self) -> usize {
+            self.start_oauth_login_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `handle_oauth_callback` call.
+        pub fn push_handle_oauth_callback_resp(&mut self, resp: Result<HandleOauthCallbackResp, Status>) -> &mut Self {
+            self.handle_oauth_callback_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `handle_oauth_callback` was called.
+        pub fn handle_oauth_callback_calls(&self) -> usize {
+            self.handle_oauth_callback_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `link_oauth_account` call.
+        pub fn push_link_oauth_account_resp(&mut self, resp: Result<LinkOauthAccountResp, Status>) -> &mut Self {
+            self.link_oauth_account_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `link_oauth_account` was called.
+        pub fn link_oauth_account_calls(&self) -> usize {
+            self.link_oauth_account_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `get_oauth_account` call.
+        pub fn push_get_oauth_account_resp(&mut self, resp: Result<GetOauthAccountResp, Status>) -> &mut Self {
+            self.get_oauth_account_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `get_oauth_account` was called.
+        pub fn get_oauth_account_calls(&self) -> usize {
+            self.get_oauth_account_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `refresh_oauth_token` call.
+        pub fn push_refresh_oauth_token_resp(&mut self, resp: Result<RefreshOauthTokenResp, Status>) -> &mut Self {
+            self.refresh_oauth_token_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `refresh_oauth_token` was called.
+        pub fn refresh_oauth_token_calls(&self) -> usize {
+            self.refresh_oauth_token_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `get_active_sessions_count` call.
+        pub fn push_get_active_sessions_count_resp(&mut self, resp: Result<GetActiveSessionsCountResp, Status>) -> &mut Self {
+            self.get_active_sessions_count_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `get_active_sessions_count` was called.
+        pub fn get_active_sessions_count_calls(&self) -> usize {
+            self.get_active_sessions_count_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `list_sessions` call.
+        pub fn push_list_sessions_resp(&mut self, resp: Result<ListSessionsResp, Status>) -> &mut Self {
+            self.list_sessions_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `list_sessions` was called.
+        pub fn list_sessions_calls(&self) -> usize {
+            self.list_sessions_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `delete_all_sessions` call.
+        pub fn push_delete_all_sessions_resp(&mut self, resp: Result<DeleteAllSessionsResp, Status>) -> &mut Self {
+            self.delete_all_sessions_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `delete_all_sessions` was called.
+        pub fn delete_all_sessions_calls(&self) -> usize {
+            self.delete_all_sessions_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `ping` call.
+        pub fn push_ping_resp(&mut self, resp: Result<PingResp, Status>) -> &mut Self {
+            self.ping_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `ping` was called.
+        pub fn ping_calls(&self) -> usize {
+            self.ping_call_count.load(Ordering::SeqCst)
+        }
+    }
+
     #[rustfmt::skip]
     #[async_trait]
     impl IAuthClient for MockAuthClient {
         async fn create_session(&self, req: Request<CreateSessionReq>) -> Result<Response<CreateSessionResp>, Status> {
+            self.create_session_call_count.fetch_add(1, Ordering::SeqCst);
             *self.create_session_req.lock().await = Some(req.into_inner());
-            self.create_session_resp.lock().await.take().unwrap().map(Response::new)
+            self.create_session_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("create_session called more times than configured"))
+                .map(Response::new)
         }
         async fn validate_session(&self, req: Request<ValidateSessionReq>) -> Result<Response<ValidateSessionResp>, Status> {
+            self.validate_session_call_count.fetch_add(1, Ordering::SeqCst);
             *self.validate_session_req.lock().await = Some(req.into_inner());
-            self.validate_session_resp.lock().await.take().unwrap().map(Response::new)
+            self.validate_session_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("validate_session called more times than configured"))
+                .map(Response::new)
         }
         async fn delete_session(&self, req: Request<DeleteSessionReq>) -> Result<Response<DeleteSessionResp>, Status> {
+            self.delete_session_call_count.fetch_add(1, Ordering::SeqCst);
             *self.delete_session_req.lock().await = Some(req.into_inner());
-            self.delete_session_resp.lock().await.take().unwrap().map(Response::new)
+            self.delete_session_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("delete_session called more times than configured"))
+                .map(Response::new)
         }
         async fn start_oauth_login(&self, req: Request<StartOauthLoginReq>) -> Result<Response<StartOauthLoginResp>, Status> {
+            self.start_oauth_login_call_count.fetch_add(1, Ordering::SeqCst);
             *self.start_oauth_login_req.lock().await = Some(req.into_inner());
-            self.start_oauth_login_resp.lock().await.take().unwrap().map(Response::new)
+            self.start_oauth_login_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("start_oauth_login called more times than configured"))
+                .map(Response::new)
         }
         async fn handle_oauth_callback(&self, req: Request<HandleOauthCallbackReq>) -> Result<Response<HandleOauthCallbackResp>, Status> {
+            self.handle_oauth_callback_call_count.fetch_add(1, Ordering::SeqCst);
             *self.handle_oauth_callback_req.lock().await = Some(req.into_inner());
-            self.handle_oauth_callback_resp.lock().await.take().unwrap().map(Response::new)
+            self.handle_oauth_callback_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("handle_oauth_callback called more times than configured"))
+                .map(Response::new)
         }
         async fn link_oauth_account(&self, req: Request<LinkOauthAccountReq>) -> Result<Response<LinkOauthAccountResp>, Status> {
+            self.link_oauth_account_call_count.fetch_add(1, Ordering::SeqCst);
             *self.link_oauth_account_req.lock().await = Some(req.into_inner());
-            self.link_oauth_account_resp.lock().await.take().unwrap().map(Response::new)
+            self.link_oauth_account_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("link_oauth_account called more times than configured"))
+                .map(Response::new)
         }
         async fn get_oauth_account(&self, req: Request<GetOauthAccountReq>) -> Result<Response<GetOauthAccountResp>, Status> {
+            self.get_oauth_account_call_count.fetch_add(1, Ordering::SeqCst);
             *self.get_oauth_account_req.lock().await = Some(req.into_inner());
-            self.get_oauth_account_resp.lock().await.take().unwrap().map(Response::new)
+            self.get_oauth_account_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("get_oauth_account called more times than configured"))
+                .map(Response::new)
+        }
+        async fn refresh_oauth_token(&self, req: Request<RefreshOauthTokenReq>) -> Result<Response<RefreshOauthTokenResp>, Status> {
+            self.refresh_oauth_token_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.refresh_oauth_token_req.lock().await = Some(req.into_inner());
+            self.refresh_oauth_token_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("refresh_oauth_token called more times than configured"))
+                .map(Response::new)
+        }
+        async fn get_active_sessions_count(&self, req: Request<GetActiveSessionsCountReq>) -> Result<Response<GetActiveSessionsCountResp>, Status> {
+            self.get_active_sessions_count_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.get_active_sessions_count_req.lock().await = Some(req.into_inner());
+            self.get_active_sessions_count_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("get_active_sessions_count called more times than configured"))
+                .map(Response::new)
+        }
+        async fn list_sessions(&self, req: Request<ListSessionsReq>) -> Result<Response<ListSessionsResp>, Status> {
+            self.list_sessions_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.list_sessions_req.lock().await = Some(req.into_inner());
+            self.list_sessions_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("list_sessions called more times than configured"))
+                .map(Response::new)
+        }
+        async fn delete_all_sessions(&self, req: Request<DeleteAllSessionsReq>) -> Result<Response<DeleteAllSessionsResp>, Status> {
+            self.delete_all_sessions_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.delete_all_sessions_req.lock().await = Some(req.into_inner());
+            self.delete_all_sessions_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("delete_all_sessions called more times than configured"))
+                .map(Response::new)
+        }
+        async fn ping(&self, req: Request<PingReq>) -> Result<Response<PingResp>, Status> {
+            self.ping_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.ping_req.lock().await = Some(req.into_inner());
+            self.ping_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("ping called more times than configured"))
+                .map(Response::new)
         }
     }
 }
@@ -1,24 +1,31 @@
 use crate::{
     db::DBClient,
-    error::Error,
+    error::{DBError, Error},
     handler::{Handler, SessionToken},
     proto::{CreateSessionReq, CreateSessionResp},
-    utils::{DBSession, hash_secret},
+    utils::{DBSession, SecretHasher},
 };
 use common::Now;
 use oauth::RandomSource;
 use setup::validate_user_id;
 use tonic::{Request, Response, Status};
 
-impl<D, R, N> Handler<D, R, N>
+/// How often a session is regenerated if its id already exists.
+const MAX_INSERT_SESSION_ATTEMPTS: usize = 3;
+
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
     R: RandomSource + Clone,
     N: Now,
+    H: SecretHasher,
 {
     /// Creates a new session.
     ///
+    /// A new id is generated if the session id is already taken.
+    ///
     /// # Errors
+    /// - aborted if no unique session id could be generated
     /// - database error
     ///
     /// # Further readings
@@ -31,24 +38,32 @@ where
 
         let user_id = validate_user_id(&req.user_id)?;
 
-        let id = R::alphanumeric(24);
-        let secret = R::alphanumeric(24);
-        let token: SessionToken = format!("{id}.{secret}");
+        for _ in 0..MAX_INSERT_SESSION_ATTEMPTS {
+            let id = R::alphanumeric(24);
+            let secret = R::alphanumeric(24);
+            let token: SessionToken = format!("{id}.{secret}");
 
-        let session = DBSession {
-            id,
-            secret_hash: hash_secret(&secret),
-            created_at: N::now(),
-            user_id,
-            ..Default::default()
-        };
+            let session = DBSession::new(
+                id,
+                self.hasher.hash(&secret),
+                user_id,
+                self.now.now(),
+                self.expiry,
+            );
 
-        self.db
-            .insert_session(session)
-            .await
-            .map_err(Error::InsertSession)?;
+            match self.db.insert_session(session).await {
+                Ok(()) => {
+                    return Ok(Response::new(CreateSessionResp {
+                        token,
+                        expires_in_seconds: self.expiry.num_seconds(),
+                    }));
+                }
+                Err(DBError::Conflict(_)) => continue,
+                Err(e) => return Err(Error::InsertSession(e).into()),
+            }
+        }
 
-        Ok(Response::new(CreateSessionResp { token }))
+        Err(Error::Conflict.into())
     }
 }
 
@@ -58,13 +73,15 @@ mod tests {
     use crate::db::test::MockDBClient;
     use crate::error::DBError;
     use crate::fixture::{fixture_token, fixture_uuid};
-    use crate::oauth::{github::GithubOAuth, google::GoogleOAuth};
+    use crate::oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider};
+    use crate::utils::Sha256Hasher;
     use common::mock::MockNow;
     use oauth::mock::MockRandom;
     use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
     use std::marker::PhantomData;
     use testutils::assert_response;
-    use tokio::sync::Mutex;
     use tonic::Code;
 
     #[rstest]
@@ -72,41 +89,67 @@ mod tests {
         CreateSessionReq {
             user_id: fixture_uuid().to_string(),
         },
-        Ok(()),
+        vec![Ok(())],
         Ok(CreateSessionResp {
             token: fixture_token(),
+            expires_in_seconds: 604800,
         })
     )]
     #[case::missing_user_id(
         CreateSessionReq {
             user_id: String::new(),
         },
-        Ok(()),
+        vec![],
         Err(Code::InvalidArgument)
     )]
+    #[case::regenerate_on_conflict(
+        CreateSessionReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        vec![Err(DBError::Conflict(String::new())), Ok(())],
+        Ok(CreateSessionResp {
+            token: fixture_token(),
+            expires_in_seconds: 604800,
+        })
+    )]
+    #[case::conflict(
+        CreateSessionReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        vec![
+            Err(DBError::Conflict(String::new())),
+            Err(DBError::Conflict(String::new())),
+            Err(DBError::Conflict(String::new())),
+        ],
+        Err(Code::Aborted)
+    )]
     #[case::db_error(
         CreateSessionReq {
             user_id: fixture_uuid().to_string(),
         },
-        Err(DBError::Unknown),
+        vec![Err(DBError::Unknown)],
         Err(Code::Internal)
     )]
     #[tokio::test]
     async fn test_create_session(
         #[case] req: CreateSessionReq,
-        #[case] db_result: Result<(), DBError>,
+        #[case] db_results: Vec<Result<(), DBError>>,
         #[case] want: Result<CreateSessionResp, Code>,
     ) {
         // given
-        let db = MockDBClient {
-            insert_session: Mutex::new(Some(db_result)),
-            ..Default::default()
-        };
+        let mut db = MockDBClient::default();
+        db.expect_insert_session(db_results);
         let handler = Handler {
             db,
-            google: GoogleOAuth::<MockRandom>::default(),
-            github: GithubOAuth::<MockRandom>::default(),
-            _now: PhantomData::<MockNow>,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
         };
 
         // when
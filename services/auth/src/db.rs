@@ -1,11 +1,10 @@
 use crate::{
     error::DBError,
     proto::OauthProvider,
-    utils::{DBSession, OAuthAccount},
+    utils::{DBSession, DBSessionWithUser, OAuthAccount},
 };
 use chrono::{DateTime, Utc};
 use deadpool_postgres::Pool;
-use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
 use tonic::async_trait;
 use uuid::Uuid;
 
@@ -16,10 +15,28 @@ pub trait DBClient: Send + Sync + 'static {
 
     async fn get_session(&self, id: &str) -> Result<DBSession, DBError>;
 
+    async fn get_session_with_user(&self, id: &str) -> Result<DBSessionWithUser, DBError>;
+
     async fn delete_session(&self, id: &str) -> Result<(), DBError>;
 
+    async fn delete_sessions_by_user(&self, user_id: Uuid) -> Result<u64, DBError>;
+
+    async fn prune_expired_sessions(&self) -> Result<u64, DBError>;
+
     async fn update_session(&self, id: &str, expires_at: &DateTime<Utc>) -> Result<(), DBError>;
 
+    async fn get_active_sessions_count(
+        &self,
+        user_id: Uuid,
+        now: &DateTime<Utc>,
+    ) -> Result<i64, DBError>;
+
+    async fn list_sessions_by_user(
+        &self,
+        user_id: Uuid,
+        now: &DateTime<Utc>,
+    ) -> Result<Vec<DBSession>, DBError>;
+
     async fn upsert_oauth_account(
         &self,
         oauth_account: &OAuthAccount,
@@ -52,20 +69,22 @@ impl DBClient for PostgresDBClient {
     /// Inserts a session into the database.
     ///
     /// # Errors
+    /// - a session with the same id already exists
     /// - database connection cannot be established
     /// - executing database statement fails
     async fn insert_session(&self, session: DBSession) -> Result<(), DBError> {
         let client = self.pool.get().await?;
-        let expires_at = session
-            .created_at
-            .checked_add_signed(SESSION_TOKEN_EXPIRY_DURATION);
 
-        client
+        let rows = client
             .execute(
-                "INSERT INTO sessions (id, secret_hash, user_id, created_at, expires_at) VALUES ($1, $2, $3, $4, $5)",
-                &[&session.id, &session.secret_hash, &session.user_id, &session.created_at, &expires_at],
+                "INSERT INTO sessions (id, secret_hash, user_id, created_at, expires_at) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (id) DO NOTHING",
+                &[&session.id, &session.secret_hash, &session.user_id, &session.created_at, &session.expires_at],
             )
-            .await?;
+            .await
+            .map_err(DBError::query("insert_session"))?;
+        if rows == 0 {
+            return Err(DBError::Conflict(session.id));
+        }
 
         Ok(())
     }
@@ -81,17 +100,53 @@ impl DBClient for PostgresDBClient {
 
         let stmt = client
             .prepare("SELECT id, secret_hash, created_at, expires_at, user_id FROM sessions WHERE id = $1")
-            .await?;
-        let row = client.query_opt(&stmt, &[&id]).await?;
+            .await
+            .map_err(DBError::query("get_session"))?;
+        let row = client
+            .query_opt(&stmt, &[&id])
+            .await
+            .map_err(DBError::query("get_session"))?;
         let Some(row) = row else {
             return Err(DBError::NotFound(id.to_string()));
         };
 
-        let session = DBSession::try_from(&row)?;
+        let session = DBSession::try_from(&row).map_err(DBError::query("get_session"))?;
 
         Ok(session)
     }
 
+    /// Returns a session joined with the display fields of the user's
+    /// most recently updated oauth account.
+    ///
+    /// # Errors
+    /// - not found
+    /// - database connection cannot be established
+    /// - executing database statement fails
+    async fn get_session_with_user(&self, id: &str) -> Result<DBSessionWithUser, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare(
+                "SELECT s.id, s.secret_hash, s.created_at, s.expires_at, s.user_id, a.external_user_name, a.external_user_email
+                 FROM sessions s
+                 LEFT JOIN oauth_accounts a ON a.user_id = s.user_id
+                 WHERE s.id = $1
+                 ORDER BY a.updated_at DESC NULLS LAST
+                 LIMIT 1",
+            )
+            .await
+            .map_err(DBError::query("get_session_with_user"))?;
+        let row = client
+            .query_opt(&stmt, &[&id])
+            .await
+            .map_err(DBError::query("get_session_with_user"))?;
+        let Some(row) = row else {
+            return Err(DBError::NotFound(id.to_string()));
+        };
+
+        DBSessionWithUser::try_from(&row).map_err(DBError::query("get_session_with_user"))
+    }
+
     /// Updates a session in the database.
     ///
     /// # Errors
@@ -105,11 +160,63 @@ impl DBClient for PostgresDBClient {
                 "UPDATE sessions SET expires_at = $1 WHERE id = $2",
                 &[&expires_at, &id],
             )
-            .await?;
+            .await
+            .map_err(DBError::query("update_session"))?;
 
         Ok(())
     }
 
+    /// Counts the sessions of a user that expire after `now`.
+    ///
+    /// # Errors
+    /// - database connection cannot be established
+    /// - executing database statement fails
+    async fn get_active_sessions_count(
+        &self,
+        user_id: Uuid,
+        now: &DateTime<Utc>,
+    ) -> Result<i64, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare("SELECT COUNT(*) FROM sessions WHERE user_id = $1 AND expires_at > $2")
+            .await
+            .map_err(DBError::query("get_active_sessions_count"))?;
+        let row = client
+            .query_one(&stmt, &[&user_id, &now])
+            .await
+            .map_err(DBError::query("get_active_sessions_count"))?;
+
+        Ok(row.get(0))
+    }
+
+    /// Returns the sessions of a user that expire after `now`, newest first.
+    ///
+    /// # Errors
+    /// - database connection cannot be established
+    /// - executing database statement fails
+    async fn list_sessions_by_user(
+        &self,
+        user_id: Uuid,
+        now: &DateTime<Utc>,
+    ) -> Result<Vec<DBSession>, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare("SELECT id, secret_hash, created_at, expires_at, user_id FROM sessions WHERE user_id = $1 AND expires_at > $2 ORDER BY created_at DESC")
+            .await
+            .map_err(DBError::query("list_sessions_by_user"))?;
+        let rows = client
+            .query(&stmt, &[&user_id, &now])
+            .await
+            .map_err(DBError::query("list_sessions_by_user"))?;
+
+        rows.iter()
+            .map(DBSession::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(DBError::query("list_sessions_by_user"))
+    }
+
     /// Deletes a session from the database.
     ///
     /// # Errors
@@ -120,13 +227,48 @@ impl DBClient for PostgresDBClient {
 
         client
             .execute("DELETE FROM sessions WHERE id = $1", &[&id])
-            .await?;
+            .await
+            .map_err(DBError::query("delete_session"))?;
 
         Ok(())
     }
 
+    /// Deletes all sessions of a user and returns how many were deleted.
+    ///
+    /// # Errors
+    /// - database connection cannot be established
+    /// - executing database statement fails
+    async fn delete_sessions_by_user(&self, user_id: Uuid) -> Result<u64, DBError> {
+        let client = self.pool.get().await?;
+
+        let count = client
+            .execute("DELETE FROM sessions WHERE user_id = $1", &[&user_id])
+            .await
+            .map_err(DBError::query("delete_sessions_by_user"))?;
+
+        Ok(count)
+    }
+
+    /// Deletes all expired sessions and returns how many were deleted.
+    ///
+    /// # Errors
+    /// - database connection cannot be established
+    /// - executing database statement fails
+    async fn prune_expired_sessions(&self) -> Result<u64, DBError> {
+        let client = self.pool.get().await?;
+
+        let count = client
+            .execute("DELETE FROM sessions WHERE expires_at < NOW()", &[])
+            .await
+            .map_err(DBError::query("prune_expired_sessions"))?;
+
+        Ok(count)
+    }
+
     /// Inserts or updates an oauth account. Returns the current user_id after upsert.
     ///
+    /// The stored refresh token is kept if the account has none.
+    ///
     /// # Errors
     /// - database connection cannot be established
     /// - executing database statement fails
@@ -140,7 +282,7 @@ impl DBClient for PostgresDBClient {
                  ON CONFLICT (external_user_id) DO UPDATE SET
                     access_token = EXCLUDED.access_token,
                     access_token_expires_at = EXCLUDED.access_token_expires_at,
-                    refresh_token = EXCLUDED.refresh_token,
+                    refresh_token = COALESCE(EXCLUDED.refresh_token, oauth_accounts.refresh_token),
                     updated_at = NOW()
                  RETURNING id, provider, external_user_id, external_user_name, external_user_email, access_token, access_token_expires_at, refresh_token, user_id",
                 &[
@@ -155,9 +297,11 @@ impl DBClient for PostgresDBClient {
                     &account.user_id,
                 ],
             )
-            .await?;
+            .await
+            .map_err(DBError::query("upsert_oauth_account"))?;
 
-        let oauth_account = OAuthAccount::try_from(&row)?;
+        let oauth_account =
+            OAuthAccount::try_from(&row).map_err(DBError::query("upsert_oauth_account"))?;
 
         Ok(oauth_account)
     }
@@ -179,12 +323,14 @@ impl DBClient for PostgresDBClient {
                  RETURNING id, provider, external_user_id, external_user_name, external_user_email, access_token, access_token_expires_at, refresh_token, user_id",
                 &[&id, &user_id],
             )
-            .await?;
+            .await
+            .map_err(DBError::query("update_oauth_account"))?;
         let Some(row) = row else {
             return Err(DBError::NotFound(id.to_string()));
         };
 
-        let oauth_account = OAuthAccount::try_from(&row)?;
+        let oauth_account =
+            OAuthAccount::try_from(&row).map_err(DBError::query("update_oauth_account"))?;
 
         Ok(oauth_account)
     }
@@ -205,13 +351,17 @@ impl DBClient for PostgresDBClient {
 
         let stmt = client
             .prepare("SELECT id, provider, external_user_id, external_user_name, external_user_email, access_token, access_token_expires_at, refresh_token, user_id FROM oauth_accounts WHERE user_id = $1 AND provider = $2")
-            .await?;
-        let row = client.query_opt(&stmt, &[&user_id, &provider]).await?;
+            .await
+            .map_err(DBError::query("get_oauth_account"))?;
+        let row = client
+            .query_opt(&stmt, &[&user_id, &provider])
+            .await
+            .map_err(DBError::query("get_oauth_account"))?;
         let Some(row) = row else {
             return Err(DBError::NotFound(user_id.to_string()));
         };
 
-        Ok(OAuthAccount::try_from(&row)?)
+        OAuthAccount::try_from(&row).map_err(DBError::query("get_oauth_account"))
     }
 }
 
@@ -286,6 +436,56 @@ pub(crate) mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_get_session_with_user() {
+        let session_id = "session-id-get-with-user";
+        let user_id = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+        let session = fixture_db_session(|s| {
+            s.id = session_id.to_string();
+            s.user_id = user_id;
+        });
+        let account = fixture_oauth_account(|v| {
+            v.id = "oauth-id-get-with-user".to_string();
+            v.external_user_id = "external-user-id-get-with-user".to_string();
+            v.user_id = Some(user_id);
+        });
+
+        run_db_session_test(vec![session.clone()], |db_client| async move {
+            db_client
+                .upsert_oauth_account(&account)
+                .await
+                .expect("failed to insert account");
+
+            let got = db_client
+                .get_session_with_user(session_id)
+                .await
+                .expect("failed to get session with user");
+
+            assert_eq!(
+                got,
+                DBSessionWithUser {
+                    session,
+                    user_name: account.external_user_name,
+                    user_email: account.external_user_email,
+                }
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_insert_session_conflict() {
+        let session_id = "session-id-conflict";
+        let session = fixture_db_session(|s| s.id = session_id.to_string());
+
+        run_db_session_test(vec![session.clone()], |db_client| async move {
+            let got = db_client.insert_session(session).await;
+
+            assert!(matches!(got, Err(DBError::Conflict(_))), "{got:?}");
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_update_session() {
         let session_id = "session-id-update";
@@ -308,6 +508,62 @@ pub(crate) mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_get_active_sessions_count() {
+        let user_id = Uuid::parse_str("00000000-0000-0000-0000-000000000003").unwrap();
+        let now = chrono::Utc.with_ymd_and_hms(2020, 1, 5, 0, 0, 0).unwrap();
+        let active = fixture_db_session(|s| {
+            s.id = "session-id-count-active".to_string();
+            s.user_id = user_id;
+            s.expires_at = chrono::Utc.with_ymd_and_hms(2020, 1, 9, 0, 0, 0).unwrap();
+        });
+        let expired = fixture_db_session(|s| {
+            s.id = "session-id-count-expired".to_string();
+            s.user_id = user_id;
+            s.expires_at = chrono::Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        });
+        let other_user = fixture_db_session(|s| {
+            s.id = "session-id-count-other-user".to_string();
+            s.expires_at = chrono::Utc.with_ymd_and_hms(2020, 1, 9, 0, 0, 0).unwrap();
+        });
+
+        run_db_session_test(vec![active, expired, other_user], |db_client| async move {
+            let got = db_client
+                .get_active_sessions_count(user_id, &now)
+                .await
+                .expect("failed to count sessions");
+
+            assert_eq!(got, 1);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_by_user() {
+        let user_id = Uuid::parse_str("00000000-0000-0000-0000-000000000004").unwrap();
+        let now = chrono::Utc.with_ymd_and_hms(2020, 1, 5, 0, 0, 0).unwrap();
+        let older = fixture_db_session(|s| {
+            s.id = "session-id-list-older".to_string();
+            s.user_id = user_id;
+            s.created_at = chrono::Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        });
+        let newer = fixture_db_session(|s| {
+            s.id = "session-id-list-newer".to_string();
+            s.user_id = user_id;
+            s.created_at = chrono::Utc.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap();
+        });
+
+        run_db_session_test(vec![older.clone(), newer.clone()], |db_client| async move {
+            let got = db_client
+                .list_sessions_by_user(user_id, &now)
+                .await
+                .expect("failed to list sessions");
+
+            assert_eq!(got, vec![newer, older]);
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_delete_session() {
         let session_id = "session-id-delete";
@@ -330,6 +586,57 @@ pub(crate) mod test {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_prune_expired_sessions() {
+        let session_id = "session-id-prune";
+        let session = fixture_db_session(|s| s.id = session_id.to_string());
+
+        run_db_session_test(vec![session], |db_client| async move {
+            let count = db_client
+                .prune_expired_sessions()
+                .await
+                .expect("failed to prune sessions");
+
+            assert!(count >= 1);
+            let got_result = db_client.get_session(session_id).await;
+            assert!(matches!(got_result, Err(DBError::NotFound(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_delete_sessions_by_user() {
+        let user_id = Uuid::parse_str("00000000-0000-0000-0000-000000000005").unwrap();
+        let session_ids = [
+            "session-id-delete-all-1",
+            "session-id-delete-all-2",
+            "session-id-delete-all-3",
+        ];
+        let sessions = session_ids
+            .iter()
+            .map(|id| {
+                fixture_db_session(|s| {
+                    s.id = id.to_string();
+                    s.user_id = user_id;
+                })
+            })
+            .collect();
+
+        run_db_session_test(sessions, |db_client| async move {
+            let count = db_client
+                .delete_sessions_by_user(user_id)
+                .await
+                .expect("failed to delete sessions");
+
+            assert_eq!(count, 3);
+            for id in session_ids {
+                let got_result = db_client.get_session(id).await;
+                assert!(matches!(got_result, Err(DBError::NotFound(_))));
+            }
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_upsert_oauth_account() {
         let oauth_id = "oauth-id-upsert";
@@ -377,7 +684,7 @@ pub(crate) mod test {
             account.user_id = Some(user_id);
 
             let got_account = db_client
-                .update_oauth_account(&oauth_id, user_id)
+                .update_oauth_account(oauth_id, user_id)
                 .await
                 .expect("failed to update account");
 
@@ -386,6 +693,38 @@ pub(crate) mod test {
         .await;
     }
 
+    /// The provider's user id is stored with the oauth account, not the
+    /// user, and can be read back once the account is linked.
+    #[tokio::test]
+    async fn test_link_and_get_oauth_account_provider_id() {
+        let user_id = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+        let account = fixture_oauth_account(|v| {
+            v.id = "oauth-id-link".to_string();
+            v.external_user_id = "external-user-id-link".to_string();
+            v.provider = OauthProvider::Github as i32;
+        });
+
+        run_db_oauth_accounts_test(vec![], |db_client| async move {
+            db_client
+                .upsert_oauth_account(&account)
+                .await
+                .expect("failed to insert account");
+            db_client
+                .update_oauth_account(&account.id, user_id)
+                .await
+                .expect("failed to link account");
+
+            let got = db_client
+                .get_oauth_account(user_id, OauthProvider::Github)
+                .await
+                .expect("failed to get account");
+
+            assert_eq!(got.external_user_id, "external-user-id-link");
+            assert_eq!(got.user_id, Some(user_id));
+        })
+        .await;
+    }
+
     #[rstest]
     #[case::happy_path(
         Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap(),
@@ -0,0 +1,116 @@
+use crate::{
+    db::DBClient,
+    error::Error,
+    handler::Handler,
+    proto::{DeleteAllSessionsReq, DeleteAllSessionsResp},
+};
+use setup::validate_user_id;
+use tonic::{Request, Response, Status};
+
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
+where
+    D: DBClient,
+{
+    /// Deletes every session of a user, logging them out on all devices.
+    ///
+    /// # Errors
+    /// - user id is missing or malformed
+    /// - database error
+    pub async fn delete_all_sessions(
+        &self,
+        req: Request<DeleteAllSessionsReq>,
+    ) -> Result<Response<DeleteAllSessionsResp>, Status> {
+        let req = req.into_inner();
+
+        let user_id = validate_user_id(&req.user_id)?;
+
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        let count = self
+            .db
+            .delete_sessions_by_user(user_id)
+            .await
+            .map_err(Error::DeleteAllSessions)?;
+
+        Ok(Response::new(DeleteAllSessionsResp { count }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::Sha256Hasher;
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::fixture_uuid,
+        handler::Handler,
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
+        proto::{DeleteAllSessionsReq, DeleteAllSessionsResp},
+    };
+    use common::mock::MockNow;
+    use oauth::mock::MockRandom;
+    use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
+    use std::marker::PhantomData;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    #[rstest]
+    #[case::happy_path(
+        DeleteAllSessionsReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Ok(3),
+        Ok(DeleteAllSessionsResp { count: 3 })
+    )]
+    #[case::missing_user_id(
+        DeleteAllSessionsReq {
+            user_id: String::new(),
+        },
+        Ok(3),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::invalid_user_id(
+        DeleteAllSessionsReq {
+            user_id: "invalid".to_string(),
+        },
+        Ok(3),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::db_error(
+        DeleteAllSessionsReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_delete_all_sessions(
+        #[case] req: DeleteAllSessionsReq,
+        #[case] db_result: Result<u64, DBError>,
+        #[case] want: Result<DeleteAllSessionsResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_delete_sessions_by_user([db_result]);
+        let handler = Handler {
+            db,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = handler.delete_all_sessions(Request::new(req)).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
     proto::{DeleteSessionReq, DeleteSessionResp},
 };
 
-impl<D, R, N> Handler<D, R, N>
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
 {
@@ -49,11 +49,13 @@ where
 mod tests {
     use std::marker::PhantomData;
 
+    use crate::utils::Sha256Hasher;
     use common::mock::MockNow;
     use oauth::mock::MockRandom;
     use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
     use testutils::assert_response;
-    use tokio::sync::Mutex;
     use tonic::{Code, Request};
 
     use crate::{
@@ -61,7 +63,7 @@ mod tests {
         error::DBError,
         fixture::fixture_token,
         handler::Handler,
-        oauth::{github::GithubOAuth, google::GoogleOAuth},
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
         proto::{DeleteSessionReq, DeleteSessionResp},
     };
 
@@ -101,15 +103,19 @@ mod tests {
         #[case] want: Result<DeleteSessionResp, Code>,
     ) {
         // given
-        let db = MockDBClient {
-            delete_session: Mutex::new(Some(db_result)),
-            ..Default::default()
-        };
+        let mut db = MockDBClient::default();
+        db.expect_delete_session([db_result]);
         let handler = Handler {
             db,
-            google: GoogleOAuth::<MockRandom>::default(),
-            github: GithubOAuth::<MockRandom>::default(),
-            _now: PhantomData::<MockNow>,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
         };
 
         // when
@@ -34,18 +34,36 @@ pub enum Error {
     #[error("delete session error: {0}")]
     DeleteSession(DBError),
 
+    #[error("delete all sessions error: {0}")]
+    DeleteAllSessions(DBError),
+
     #[error("insert session error: {0}")]
     InsertSession(DBError),
 
+    #[error("session id already exists")]
+    Conflict,
+
+    #[error("get active sessions count error: {0}")]
+    GetActiveSessionsCount(DBError),
+
+    #[error("list sessions error: {0}")]
+    ListSessions(DBError),
+
     #[error("update oauth account error: {0}")]
     UpdateOauthAccount(DBError),
 
     #[error("get oauth account error: {0}")]
     GetOauthAccount(DBError),
 
+    #[error("oauth account has no refresh token")]
+    MissingRefreshToken,
+
     #[error("oauth provider is not specified")]
     UnspecifiedOauthProvider,
 
+    #[error("email domain is not allowed: {0}")]
+    EmailDomainNotAllowed(String),
+
     #[error("upsert oauth account error: {0}")]
     UpsertOauthAccount(DBError),
 }
@@ -60,9 +78,16 @@ impl From<Error> for Status {
             | Error::UnspecifiedOauthProvider
             | Error::MissingOauthAccountID => Code::InvalidArgument,
             Error::SecretMismatch | Error::ExpiredToken | Error::NotFound => Code::Unauthenticated,
+            Error::EmailDomainNotAllowed(_) => Code::PermissionDenied,
+            Error::GetOauthAccount(DBError::NotFound(_)) => Code::NotFound,
+            Error::MissingRefreshToken => Code::FailedPrecondition,
+            Error::Conflict => Code::Aborted,
             Error::GetSession(_)
             | Error::DeleteSession(_)
+            | Error::DeleteAllSessions(_)
             | Error::InsertSession(_)
+            | Error::GetActiveSessionsCount(_)
+            | Error::ListSessions(_)
             | Error::UpdateOauthAccount(_)
             | Error::UpsertOauthAccount(_)
             | Error::GetOauthAccount(_) => Code::Internal,
@@ -77,12 +102,27 @@ pub enum DBError {
     #[error("unknown error occured")]
     Unknown,
 
-    #[error("internal database error: {0}")]
-    Internal(#[from] tokio_postgres::Error),
+    #[error("{op} query failed: {source}")]
+    Query {
+        /// The database operation that failed, e.g. `get_user`.
+        op: &'static str,
+        source: tokio_postgres::Error,
+    },
 
     #[error("connection error: {0}")]
     Connection(#[from] deadpool_postgres::PoolError),
 
     #[error("entity not found: {0}")]
     NotFound(String),
+
+    #[error("entity already exists: {0}")]
+    Conflict(String),
+}
+
+impl DBError {
+    /// Wraps a failed query of the given database operation, so that
+    /// the error tells which query failed.
+    pub(crate) fn query(op: &'static str) -> impl FnOnce(tokio_postgres::Error) -> Self {
+        move |source| Self::Query { op, source }
+    }
 }
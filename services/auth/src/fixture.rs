@@ -3,7 +3,7 @@
 use chrono::TimeZone;
 use uuid::Uuid;
 
-use crate::utils::{DBSession, OAuthAccount, hash_secret};
+use crate::utils::{DBSession, OAuthAccount, SecretHasher as _, Sha256Hasher};
 
 pub fn fixture_uuid() -> Uuid {
     Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap()
@@ -19,7 +19,7 @@ where
 {
     let mut session = DBSession {
         id: "session-id".to_string(),
-        secret_hash: hash_secret("secret"),
+        secret_hash: Sha256Hasher.hash("secret"),
         created_at: chrono::Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
         expires_at: chrono::Utc.with_ymd_and_hms(2020, 1, 8, 0, 0, 0).unwrap(),
         user_id: fixture_uuid(),
@@ -0,0 +1,119 @@
+use crate::{
+    db::DBClient,
+    error::Error,
+    handler::Handler,
+    proto::{GetActiveSessionsCountReq, GetActiveSessionsCountResp},
+};
+use common::Now;
+use setup::validate_user_id;
+use tonic::{Request, Response, Status};
+
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
+where
+    D: DBClient,
+    N: Now,
+{
+    /// Counts the unexpired sessions of a user, e.g. to tell them
+    /// that they are also logged in on another device.
+    ///
+    /// # Errors
+    /// - user id is missing or malformed
+    /// - database error
+    pub async fn get_active_sessions_count(
+        &self,
+        req: Request<GetActiveSessionsCountReq>,
+    ) -> Result<Response<GetActiveSessionsCountResp>, Status> {
+        let req = req.into_inner();
+
+        let user_id = validate_user_id(&req.user_id)?;
+
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        let count = self
+            .db
+            .get_active_sessions_count(user_id, &self.now.now())
+            .await
+            .map_err(Error::GetActiveSessionsCount)?;
+
+        Ok(Response::new(GetActiveSessionsCountResp { count }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::Sha256Hasher;
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::fixture_uuid,
+        handler::Handler,
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
+        proto::{GetActiveSessionsCountReq, GetActiveSessionsCountResp},
+    };
+    use common::mock::MockNow;
+    use oauth::mock::MockRandom;
+    use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
+    use std::marker::PhantomData;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    #[rstest]
+    #[case::happy_path(
+        GetActiveSessionsCountReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Ok(2),
+        Ok(GetActiveSessionsCountResp { count: 2 })
+    )]
+    #[case::missing_user_id(
+        GetActiveSessionsCountReq {
+            user_id: String::new(),
+        },
+        Ok(2),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::invalid_user_id(
+        GetActiveSessionsCountReq {
+            user_id: "invalid".to_string(),
+        },
+        Ok(2),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::db_error(
+        GetActiveSessionsCountReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_get_active_sessions_count(
+        #[case] req: GetActiveSessionsCountReq,
+        #[case] db_result: Result<i64, DBError>,
+        #[case] want: Result<GetActiveSessionsCountResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_get_active_sessions_count([db_result]);
+        let handler = Handler {
+            db,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = handler.get_active_sessions_count(Request::new(req)).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
@@ -9,12 +9,18 @@ use oauth::RandomSource;
 use setup::validate_user_id;
 use tonic::{Request, Response, Status};
 
-impl<D, R, N> Handler<D, R, N>
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
     R: RandomSource + Clone,
     N: Now,
 {
+    /// Returns the oauth account of a user for a provider.
+    ///
+    /// # Errors
+    /// - invalid user id
+    /// - no account of the provider is linked to the user (not found)
+    /// - getting oauth account (db)
     pub async fn get_oauth_account(
         &self,
         req: Request<GetOauthAccountReq>,
@@ -37,21 +43,23 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::utils::Sha256Hasher;
     use crate::{
         db::test::MockDBClient,
         error::DBError,
         fixture::fixture_oauth_account,
         handler::Handler,
-        oauth::{github::GithubOAuth, google::GoogleOAuth},
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
         proto::{GetOauthAccountReq, GetOauthAccountResp, OauthProvider},
         utils::OAuthAccount,
     };
     use common::mock::MockNow;
     use oauth::mock::MockRandom;
     use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
     use std::marker::PhantomData;
     use testutils::assert_response;
-    use tokio::sync::Mutex;
     use tonic::{Code, Request};
 
     fn fixture_uuid() -> uuid::Uuid {
@@ -69,6 +77,14 @@ mod tests {
             external_user_id: "external-user-id".to_string(),
         })
     )]
+    #[case::not_found(
+        GetOauthAccountReq {
+            user_id: fixture_uuid().to_string(),
+            provider: OauthProvider::Github as i32,
+        },
+        Err(DBError::NotFound("oauth account".to_string())),
+        Err(Code::NotFound)
+    )]
     #[case::missing_user_id(
         GetOauthAccountReq {
             user_id: String::new(),
@@ -84,15 +100,19 @@ mod tests {
         #[case] want: Result<GetOauthAccountResp, Code>,
     ) {
         // given
-        let db = MockDBClient {
-            get_oauth_account: Mutex::new(Some(db_result)),
-            ..Default::default()
-        };
+        let mut db = MockDBClient::default();
+        db.expect_get_oauth_account([db_result]);
         let handler = Handler {
             db,
-            google: GoogleOAuth::<MockRandom>::default(),
-            github: GithubOAuth::<MockRandom>::default(),
-            _now: PhantomData::<MockNow>,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
         };
 
         // when
@@ -4,22 +4,28 @@ use crate::{
     db::DBClient,
     error::Error,
     handler::Handler,
+    oauth::OAuthAccountProvider,
     proto::{HandleOauthCallbackReq, HandleOauthCallbackResp, OauthProvider},
 };
 use common::Now;
-use oauth::{OAuthProvider as _, RandomSource};
 
-impl<D, R, N> Handler<D, R, N>
+use setup::metrics::OAuthOutcome;
+
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
-    R: RandomSource + Clone,
     N: Now,
+    G: OAuthAccountProvider,
+    GH: OAuthAccountProvider,
+    A: OAuthAccountProvider,
 {
     /// Handles a oauth login callback
     ///
     /// # Errors
     /// - validating authorization code
     /// - decoding the id token
+    /// - the id token nonce does not match
+    /// - email domain is not allowed
     /// - upserting oauth token (db)
     pub async fn handle_oauth_callback(
         &self,
@@ -27,19 +33,41 @@ where
     ) -> Result<Response<HandleOauthCallbackResp>, Status> {
         let req = req.into_inner();
 
-        let (code, code_verifier) = (&req.code, &req.code_verifier);
+        let (code, code_verifier, nonce) = (&req.code, &req.code_verifier, &req.nonce);
+        let provider = req.provider();
+
+        let account = match provider {
+            OauthProvider::Google => self.google.exchange_code(code, code_verifier, nonce).await,
+            OauthProvider::Github => self.github.exchange_code(code, code_verifier, nonce).await,
+            OauthProvider::Apple => self.apple.exchange_code(code, code_verifier, nonce).await,
+            _ => {
+                self.oauth_metrics
+                    .record(provider.as_str_name(), OAuthOutcome::Error);
+                return Err(Error::UnspecifiedOauthProvider.into());
+            }
+        }
+        .inspect_err(|_| {
+            self.oauth_metrics
+                .record(provider.as_str_name(), OAuthOutcome::TokenExchangeFailed)
+        })?;
 
-        let account = match req.provider() {
-            OauthProvider::Google => self.google.exchange_code(code, code_verifier).await,
-            OauthProvider::Github => self.github.exchange_code(code, code_verifier).await,
-            _ => return Err(Error::UnspecifiedOauthProvider.into()),
-        }?;
+        if let Err(err) = self
+            .email_domains
+            .check(account.external_user_email.as_deref())
+        {
+            self.oauth_metrics
+                .record(provider.as_str_name(), OAuthOutcome::EmailDomainNotAllowed);
+            return Err(err.into());
+        }
 
-        let account = self
-            .db
-            .upsert_oauth_account(&account)
-            .await
-            .map_err(Error::UpsertOauthAccount)?;
+        let account = self.db.upsert_oauth_account(&account).await.map_err(|e| {
+            self.oauth_metrics
+                .record(provider.as_str_name(), OAuthOutcome::Error);
+            Error::UpsertOauthAccount(e)
+        })?;
+
+        self.oauth_metrics
+            .record(provider.as_str_name(), OAuthOutcome::Success);
 
         Ok(Response::new(HandleOauthCallbackResp {
             account_id: account.id,
@@ -49,3 +77,123 @@ where
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::Sha256Hasher;
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_oauth_account, fixture_uuid},
+        handler::Handler,
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
+        proto::{HandleOauthCallbackReq, HandleOauthCallbackResp, OauthProvider},
+        utils::OAuthAccount,
+    };
+    use common::mock::MockNow;
+    use oauth::mock::MockRandom;
+    use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
+    use std::marker::PhantomData;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    fn fixture_callback_req(provider: OauthProvider) -> HandleOauthCallbackReq {
+        HandleOauthCallbackReq {
+            provider: provider.into(),
+            code: "code".to_string(),
+            code_verifier: "code-verifier".to_string(),
+            nonce: "nonce".to_string(),
+        }
+    }
+
+    #[rstest]
+    #[case::new_user(
+        fixture_callback_req(OauthProvider::Google),
+        vec![Ok(fixture_oauth_account(|_| {}))],
+        Ok(HandleOauthCallbackResp {
+            account_id: "oauth-id".to_string(),
+            user_id: String::new(),
+            external_user_email: "external-user-email".to_string(),
+            external_user_name: "external-user-name".to_string(),
+        })
+    )]
+    #[case::existing_user(
+        fixture_callback_req(OauthProvider::Github),
+        vec![Ok(fixture_oauth_account(|a| a.user_id = Some(fixture_uuid())))],
+        Ok(HandleOauthCallbackResp {
+            account_id: "oauth-id".to_string(),
+            user_id: fixture_uuid().to_string(),
+            external_user_email: "external-user-email".to_string(),
+            external_user_name: "external-user-name".to_string(),
+        })
+    )]
+    #[case::unspecified_provider(
+        fixture_callback_req(OauthProvider::Unspecified),
+        vec![],
+        Err(Code::InvalidArgument)
+    )]
+    #[case::db_error(
+        fixture_callback_req(OauthProvider::Apple),
+        vec![Err(DBError::Unknown)],
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_handle_oauth_callback(
+        #[case] req: HandleOauthCallbackReq,
+        #[case] db_results: Vec<Result<OAuthAccount, DBError>>,
+        #[case] want: Result<HandleOauthCallbackResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_upsert_oauth_account(db_results);
+        let provider = MockOAuthProvider::new(fixture_oauth_account(|_| {}));
+        let handler = Handler {
+            db,
+            google: provider.clone(),
+            github: provider.clone(),
+            apple: provider,
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = handler.handle_oauth_callback(Request::new(req)).await;
+
+        // then
+        assert_response(got, want);
+    }
+
+    #[tokio::test]
+    async fn test_handle_oauth_callback_email_domain_not_allowed() {
+        // given
+        let provider = MockOAuthProvider::new(fixture_oauth_account(|a| {
+            a.external_user_email = Some("user@gmail.com".to_string());
+        }));
+        let handler = Handler {
+            db: MockDBClient::default(),
+            google: provider.clone(),
+            github: provider.clone(),
+            apple: provider,
+            email_domains: EmailDomainPolicy::new(&["company.com"], &[]),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+        let req = fixture_callback_req(OauthProvider::Google);
+
+        // when
+        let got = handler.handle_oauth_callback(Request::new(req)).await;
+
+        // then
+        assert_eq!(got.unwrap_err().code(), Code::PermissionDenied);
+        assert_eq!(handler.db.upsert_oauth_account_calls(), 0);
+    }
+}
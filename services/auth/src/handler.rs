@@ -12,34 +12,66 @@ use std::marker::PhantomData;
 
 use crate::{
     db::DBClient,
-    oauth::{github::GithubOAuth, google::GoogleOAuth},
+    oauth::{
+        OAuthAccountProvider, apple::AppleOAuth, email_domains::EmailDomainPolicy,
+        github::GithubOAuth, google::GoogleOAuth,
+    },
     proto::{
-        CreateSessionReq, CreateSessionResp, DeleteSessionReq, DeleteSessionResp,
+        CreateSessionReq, CreateSessionResp, DeleteAllSessionsReq, DeleteAllSessionsResp,
+        DeleteSessionReq, DeleteSessionResp, GetActiveSessionsCountReq, GetActiveSessionsCountResp,
         GetOauthAccountReq, GetOauthAccountResp, HandleOauthCallbackReq, HandleOauthCallbackResp,
-        LinkOauthAccountReq, LinkOauthAccountResp, StartOauthLoginReq, StartOauthLoginResp,
-        ValidateSessionReq, ValidateSessionResp, auth_service_server::AuthService,
+        LinkOauthAccountReq, LinkOauthAccountResp, ListSessionsReq, ListSessionsResp, PingReq,
+        PingResp, RefreshOauthTokenReq, RefreshOauthTokenResp, StartOauthLoginReq,
+        StartOauthLoginResp, ValidateSessionReq, ValidateSessionResp,
+        auth_service_server::AuthService,
     },
+    utils::{SecretHasher, Sha256Hasher},
 };
+use chrono::Duration;
 use common::{Now, SystemNow};
 use oauth::RandomSource;
+use setup::{metrics::OAuthMetrics, session::SESSION_TOKEN_EXPIRY_DURATION};
 use tonic::{Request, Response, Status};
 use tracing::instrument;
 
+/// The auth service handler.
+///
+/// The oauth providers are generic, so that tests can replace them
+/// with providers that do not send http requests.
 #[derive(Clone)]
-pub struct Handler<D, R, N> {
+pub struct Handler<D, R, N, H, G = GoogleOAuth<R>, GH = GithubOAuth<R>, A = AppleOAuth<R>> {
     pub db: D,
-    pub google: GoogleOAuth<R>,
-    pub github: GithubOAuth<R>,
-    pub(crate) _now: PhantomData<N>,
+    pub google: G,
+    pub github: GH,
+    pub apple: A,
+    pub email_domains: EmailDomainPolicy,
+    pub oauth_metrics: OAuthMetrics,
+    pub hasher: H,
+    /// How long a session is valid, and by how much it is extended on refresh.
+    pub expiry: Duration,
+    pub(crate) _random: PhantomData<R>,
+    pub now: N,
 }
 
-impl<D, R> Handler<D, R, SystemNow> {
-    pub fn new(db: D, google: GoogleOAuth<R>, github: GithubOAuth<R>) -> Self {
+impl<D, R> Handler<D, R, SystemNow, Sha256Hasher> {
+    pub fn new(
+        db: D,
+        google: GoogleOAuth<R>,
+        github: GithubOAuth<R>,
+        apple: AppleOAuth<R>,
+        email_domains: EmailDomainPolicy,
+    ) -> Self {
         Self {
             db,
             google,
             github,
-            _now: PhantomData,
+            apple,
+            email_domains,
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData,
+            now: SystemNow,
         }
     }
 }
@@ -47,11 +79,15 @@ impl<D, R> Handler<D, R, SystemNow> {
 pub(crate) type SessionToken = String;
 
 #[tonic::async_trait]
-impl<D, R, N> AuthService for Handler<D, R, N>
+impl<D, R, N, H, G, GH, A> AuthService for Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
     R: RandomSource + Clone,
     N: Now,
+    H: SecretHasher,
+    G: OAuthAccountProvider,
+    GH: OAuthAccountProvider,
+    A: OAuthAccountProvider,
 {
     #[instrument(skip_all, fields(user_id), err)]
     async fn create_session(
@@ -108,4 +144,40 @@ where
     ) -> Result<Response<GetOauthAccountResp>, Status> {
         self.get_oauth_account(req).await
     }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn get_active_sessions_count(
+        &self,
+        req: Request<GetActiveSessionsCountReq>,
+    ) -> Result<Response<GetActiveSessionsCountResp>, Status> {
+        self.get_active_sessions_count(req).await
+    }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn list_sessions(
+        &self,
+        req: Request<ListSessionsReq>,
+    ) -> Result<Response<ListSessionsResp>, Status> {
+        self.list_sessions(req).await
+    }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn refresh_oauth_token(
+        &self,
+        req: Request<RefreshOauthTokenReq>,
+    ) -> Result<Response<RefreshOauthTokenResp>, Status> {
+        self.refresh_oauth_token(req).await
+    }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn delete_all_sessions(
+        &self,
+        req: Request<DeleteAllSessionsReq>,
+    ) -> Result<Response<DeleteAllSessionsResp>, Status> {
+        self.delete_all_sessions(req).await
+    }
+
+    async fn ping(&self, _: Request<PingReq>) -> Result<Response<PingResp>, Status> {
+        Ok(Response::new(PingResp {}))
+    }
 }
@@ -3,38 +3,157 @@ pub mod proto;
 
 use crate::client::{AuthClient, IAuthClient};
 use crate::proto::ValidateSessionReq;
+use chrono::DateTime;
 use setup::middleware::SessionAuthClient;
 use setup::{
     middleware::auth::{AuthenticateSessionErr, AuthenticatedSession},
     session::SessionState,
 };
+use std::time::Duration;
+use tokio::time::Instant;
 use tonic::async_trait;
-use tonic::{Code, Request};
+use tonic::{Code, Request, Status};
 
 pub const GRPC_PORT: u16 = 50051;
 pub const SERVICE_NAME: &str = "auth";
 
+/// Maximum number of attempts to authenticate a session.
+const AUTHENTICATE_MAX_ATTEMPTS: u32 = 3;
+
+/// Backoff between two attempts to authenticate a session.
+const AUTHENTICATE_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Upper bound for the total time spent authenticating a session, including retries.
+const AUTHENTICATE_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[async_trait]
 impl SessionAuthClient for AuthClient {
     async fn authenticate_session(
         &mut self,
         token: &str,
     ) -> Result<AuthenticatedSession, AuthenticateSessionErr> {
-        let req = Request::new(ValidateSessionReq {
-            token: token.to_string(),
-        });
-        let resp = self
-            .validate_session(req)
-            .await
-            .map_err(|e| match e.code() {
-                Code::Internal => AuthenticateSessionErr::Internal,
-                _ => AuthenticateSessionErr::Unauthenticated,
-            })?;
+        let resp = retry_transient(|timeout| {
+            let mut req = Request::new(ValidateSessionReq {
+                token: token.to_string(),
+            });
+            req.set_timeout(timeout);
+            self.validate_session(req)
+        })
+        .await
+        .map_err(|e| match e.code() {
+            Code::Internal | Code::Unavailable | Code::DeadlineExceeded => {
+                AuthenticateSessionErr::Internal
+            }
+            _ => AuthenticateSessionErr::Unauthenticated,
+        })?;
         let resp = resp.into_inner();
 
         Ok(AuthenticatedSession {
             session_state: SessionState::new(resp.user_id),
             should_refresh_cookie: resp.should_refresh_cookie,
+            expires_at: DateTime::from_timestamp(resp.expires_at, 0)
+                .filter(|_| resp.expires_at > 0),
+        })
+    }
+}
+
+/// Returns whether a status code is worth retrying, e.g. during a rolling restart.
+fn is_transient(code: Code) -> bool {
+    matches!(code, Code::Unavailable | Code::Unknown)
+}
+
+/// Calls `f` and retries on transient errors with a short backoff.
+///
+/// `f` receives the remaining time budget so that it can propagate it
+/// as the request deadline. Non-transient errors such as
+/// `Unauthenticated` are returned immediately.
+async fn retry_transient<F, Fut, T>(mut f: F) -> Result<T, Status>
+where
+    F: FnMut(Duration) -> Fut,
+    Fut: Future<Output = Result<T, Status>>,
+{
+    let deadline = Instant::now() + AUTHENTICATE_TIMEOUT;
+    let mut attempt = 1;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let result = tokio::time::timeout(remaining, f(remaining))
+            .await
+            .map_err(|_| Status::deadline_exceeded("authenticate session timed out"))?;
+
+        match result {
+            Err(status)
+                if is_transient(status.code())
+                    && attempt < AUTHENTICATE_MAX_ATTEMPTS
+                    && Instant::now() + AUTHENTICATE_RETRY_BACKOFF < deadline =>
+            {
+                tokio::time::sleep(AUTHENTICATE_RETRY_BACKOFF).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_retry_transient_then_success() {
+        // given
+        let responses = Mutex::new(vec![Ok(()), Err(Status::unavailable("restarting"))]);
+        let calls = AtomicUsize::new(0);
+
+        // when
+        let got = retry_transient(|_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let resp = responses.lock().unwrap().pop().unwrap();
+            async move { resp }
         })
+        .await;
+
+        // then
+        assert!(got.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_unauthenticated_short_circuits() {
+        // given
+        let calls = AtomicUsize::new(0);
+
+        // when
+        let got: Result<(), Status> = retry_transient(|_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(Status::unauthenticated("invalid token")) }
+        })
+        .await;
+
+        // then
+        assert_eq!(got.unwrap_err().code(), Code::Unauthenticated);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_gives_up_after_max_attempts() {
+        // given
+        let calls = AtomicUsize::new(0);
+
+        // when
+        let got: Result<(), Status> = retry_transient(|_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(Status::unavailable("restarting")) }
+        })
+        .await;
+
+        // then
+        assert_eq!(got.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            AUTHENTICATE_MAX_ATTEMPTS as usize
+        );
     }
 }
@@ -9,7 +9,7 @@ use oauth::RandomSource;
 use setup::validate_user_id;
 use tonic::{Request, Response, Status};
 
-impl<D, R, N> Handler<D, R, N>
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
     R: RandomSource + Clone,
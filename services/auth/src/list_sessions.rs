@@ -0,0 +1,132 @@
+use crate::{
+    db::DBClient,
+    error::Error,
+    handler::Handler,
+    proto::{ListSessionsReq, ListSessionsResp, SessionInfo},
+};
+use common::Now;
+use setup::validate_user_id;
+use tonic::{Request, Response, Status};
+
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
+where
+    D: DBClient,
+    N: Now,
+{
+    /// Lists the unexpired sessions of a user, e.g. to show them
+    /// on which devices they are logged in.
+    ///
+    /// Only the session ids are returned, never the secret hashes.
+    ///
+    /// # Errors
+    /// - user id is missing or malformed
+    /// - database error
+    pub async fn list_sessions(
+        &self,
+        req: Request<ListSessionsReq>,
+    ) -> Result<Response<ListSessionsResp>, Status> {
+        let req = req.into_inner();
+
+        let user_id = validate_user_id(&req.user_id)?;
+
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        let sessions = self
+            .db
+            .list_sessions_by_user(user_id, &self.now.now())
+            .await
+            .map_err(Error::ListSessions)?
+            .into_iter()
+            .map(|s| SessionInfo {
+                id: s.id,
+                created_at: s.created_at.timestamp(),
+            })
+            .collect();
+
+        Ok(Response::new(ListSessionsResp { sessions }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{DBSession, Sha256Hasher};
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_db_session, fixture_uuid},
+        handler::Handler,
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
+        proto::{ListSessionsReq, ListSessionsResp, SessionInfo},
+    };
+    use common::mock::MockNow;
+    use oauth::mock::MockRandom;
+    use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
+    use std::marker::PhantomData;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    #[rstest]
+    #[case::happy_path(
+        ListSessionsReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Ok(vec![fixture_db_session(|_| {})]),
+        Ok(ListSessionsResp {
+            sessions: vec![SessionInfo {
+                id: "session-id".to_string(),
+                created_at: 1577836800,
+            }],
+        })
+    )]
+    #[case::no_sessions(
+        ListSessionsReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Ok(vec![]),
+        Ok(ListSessionsResp { sessions: vec![] })
+    )]
+    #[case::missing_user_id(
+        ListSessionsReq {
+            user_id: String::new(),
+        },
+        Ok(vec![]),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::db_error(
+        ListSessionsReq {
+            user_id: fixture_uuid().to_string(),
+        },
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_list_sessions(
+        #[case] req: ListSessionsReq,
+        #[case] db_result: Result<Vec<DBSession>, DBError>,
+        #[case] want: Result<ListSessionsResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_list_sessions_by_user([db_result]);
+        let handler = Handler {
+            db,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = handler.list_sessions(Request::new(req)).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
@@ -1,15 +1,20 @@
 #![allow(dead_code)]
 pub(crate) mod create_session;
 pub(crate) mod db;
+pub(crate) mod delete_all_sessions;
 pub(crate) mod delete_session;
 pub(crate) mod error;
+pub(crate) mod get_active_sessions_count;
 pub(crate) mod get_oauth_account;
 pub(crate) mod handle_oauth_callback;
 pub(crate) mod handler;
 pub(crate) mod link_oauth_account;
+pub(crate) mod list_sessions;
 pub(crate) mod oauth;
 #[allow(clippy::all)]
 pub(crate) mod proto;
+pub(crate) mod prune;
+pub(crate) mod refresh_oauth_token;
 pub(crate) mod start_oauth_login;
 pub(crate) mod utils;
 pub(crate) mod validate_session;
@@ -20,12 +25,17 @@ mod fixture;
 use crate::{
     db::PostgresDBClient,
     handler::Handler,
-    oauth::{config::OauthConfig, github::GithubOAuth, google::GoogleOAuth},
-    proto::auth_service_server::AuthServiceServer,
+    oauth::{
+        apple::AppleOAuth, config::OauthConfig, email_domains::EmailDomainPolicy,
+        github::GithubOAuth, google::GoogleOAuth,
+    },
+    proto::auth_service_server::{self, AuthServiceServer},
 };
 use auth::{GRPC_PORT, SERVICE_NAME};
 use dotenv::dotenv;
-use setup::{middleware::TracingGrpcServiceLayer, tracing::init_tracer};
+use setup::{
+    health::grpc_health_service, middleware::TracingGrpcServiceLayer, tracing::init_tracer,
+};
 use std::error::Error;
 
 #[tokio::main]
@@ -38,11 +48,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let pool = database::connect(&pg_cfg)?;
     database::run_migrations!(pool, "./migrations");
 
+    let health_pool = pool.clone();
+    let health = grpc_health_service(auth_service_server::SERVICE_NAME, move || {
+        database::ping(health_pool.clone())
+    })
+    .await;
+
+    let db = PostgresDBClient::new(pool);
+    prune::spawn_session_pruning(db.clone(), prune::prune_interval_from_env()?);
+
     let oauth_cfg = OauthConfig::from_env();
     let handler = Handler::new(
-        PostgresDBClient::new(pool),
+        db,
         GoogleOAuth::from_config(&oauth_cfg),
         GithubOAuth::from_config(&oauth_cfg),
+        AppleOAuth::from_config(&oauth_cfg),
+        EmailDomainPolicy::from_env(),
     );
 
     let address = format!("0.0.0.0:{GRPC_PORT}").parse()?;
@@ -50,7 +71,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     println!("listening on :{GRPC_PORT}");
     let mut server = tonic::transport::Server::builder().layer(TracingGrpcServiceLayer);
-    server.add_service(service).serve(address).await.unwrap();
+    server
+        .add_service(health)
+        .add_service(service)
+        .serve(address)
+        .await
+        .unwrap();
 
     tracer.shutdown()?;
 
@@ -5,6 +5,11 @@ pub(crate) struct OauthConfig {
     pub(super) github_client_id: String,
     pub(super) github_client_secret: String,
     pub(super) github_redirect_uri: String,
+    pub(super) apple_client_id: String,
+    pub(super) apple_team_id: String,
+    pub(super) apple_key_id: String,
+    pub(super) apple_private_key: String,
+    pub(super) apple_redirect_uri: String,
 }
 
 impl OauthConfig {
@@ -20,6 +25,11 @@ impl OauthConfig {
             github_client_id: Self::must_get_env("GITHUB_CLIENT_ID"),
             github_client_secret: Self::must_get_env("GITHUB_CLIENT_SECRET"),
             github_redirect_uri: Self::must_get_env("GITHUB_REDIRECT_URI"),
+            apple_client_id: Self::must_get_env("APPLE_CLIENT_ID"),
+            apple_team_id: Self::must_get_env("APPLE_TEAM_ID"),
+            apple_key_id: Self::must_get_env("APPLE_KEY_ID"),
+            apple_private_key: Self::must_get_env("APPLE_PRIVATE_KEY"),
+            apple_redirect_uri: Self::must_get_env("APPLE_REDIRECT_URI"),
         }
     }
 }
@@ -0,0 +1,105 @@
+use crate::error::Error;
+
+/// Restricts oauth sign-in to users with certain email domains.
+///
+/// An empty allowlist allows every domain that is not denied.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EmailDomainPolicy {
+    allowed: Vec<String>,
+    denied: Vec<String>,
+}
+
+impl EmailDomainPolicy {
+    /// Creates a new [`EmailDomainPolicy`] from lists of domains.
+    pub(crate) fn new<S: AsRef<str>>(allowed: &[S], denied: &[S]) -> Self {
+        let normalize = |domains: &[S]| {
+            domains
+                .iter()
+                .map(|d| d.as_ref().trim().trim_start_matches('@').to_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect()
+        };
+        Self {
+            allowed: normalize(allowed),
+            denied: normalize(denied),
+        }
+    }
+
+    /// Reads comma separated domains from `ALLOWED_EMAIL_DOMAINS`
+    /// and `DENIED_EMAIL_DOMAINS`. Both are optional.
+    pub(crate) fn from_env() -> Self {
+        let get_env = |key: &str| -> Vec<String> {
+            std::env::var(key)
+                .unwrap_or_default()
+                .split(',')
+                .map(String::from)
+                .collect()
+        };
+        Self::new(
+            &get_env("ALLOWED_EMAIL_DOMAINS"),
+            &get_env("DENIED_EMAIL_DOMAINS"),
+        )
+    }
+
+    /// Returns whether a restriction is configured at all.
+    fn is_restricted(&self) -> bool {
+        !self.allowed.is_empty() || !self.denied.is_empty()
+    }
+
+    /// Checks whether a user with the given email may sign in.
+    ///
+    /// # Errors
+    /// - the email is missing while a restriction is configured
+    /// - the domain is denied or not allowed
+    pub(crate) fn check(&self, email: Option<&str>) -> Result<(), Error> {
+        if !self.is_restricted() {
+            return Ok(());
+        }
+
+        let Some((_, domain)) = email.and_then(|e| e.rsplit_once('@')) else {
+            return Err(Error::EmailDomainNotAllowed(String::new()));
+        };
+        let domain = domain.to_lowercase();
+
+        let denied = self.denied.contains(&domain);
+        let allowed = self.allowed.is_empty() || self.allowed.contains(&domain);
+        if denied || !allowed {
+            return Err(Error::EmailDomainNotAllowed(domain));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::no_restriction(&[], &[], Some("user@gmail.com"), true)]
+    #[case::no_restriction_no_email(&[], &[], None, true)]
+    #[case::allowed_domain(&["company.com"], &[], Some("user@company.com"), true)]
+    #[case::allowed_domain_case_insensitive(&["@Company.com"], &[], Some("user@COMPANY.com"), true)]
+    #[case::disallowed_domain(&["company.com"], &[], Some("user@gmail.com"), false)]
+    #[case::subdomain_not_allowed(&["company.com"], &[], Some("user@evil.company.com"), false)]
+    #[case::denied_domain(&[], &["gmail.com"], Some("user@gmail.com"), false)]
+    #[case::denied_overrides_allowed(&["gmail.com"], &["gmail.com"], Some("user@gmail.com"), false)]
+    #[case::missing_email(&["company.com"], &[], None, false)]
+    #[case::invalid_email(&["company.com"], &[], Some("company.com"), false)]
+    fn test_check_email_domain(
+        #[case] allowed: &[&str],
+        #[case] denied: &[&str],
+        #[case] email: Option<&str>,
+        #[case] want_ok: bool,
+    ) {
+        // given
+        let policy = EmailDomainPolicy::new(allowed, denied);
+
+        // when
+        let got = policy.check(email);
+
+        // then
+        assert_eq!(got.is_ok(), want_ok, "{got:?}");
+    }
+}
@@ -27,6 +27,9 @@ pub enum Error {
     #[error("missing email")]
     MissingEmail,
 
+    #[error("failed to sign client secret: {0}")]
+    SignClientSecret(#[from] jsonwebtoken::errors::Error),
+
     #[error("reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
 
@@ -36,6 +39,11 @@ pub enum Error {
 
 impl From<Error> for Status {
     fn from(err: Error) -> Self {
-        Status::new(Code::Internal, err.to_string())
+        let code = match err {
+            // e.g. the authorization code was reused or has expired
+            Error::Error(oauth::Error::OAuthProviderError { .. }) => Code::InvalidArgument,
+            _ => Code::Internal,
+        };
+        Status::new(code, err.to_string())
     }
 }
@@ -1,17 +1,13 @@
+use chrono::Utc;
+use oauth::{OAuth, OAuth2Token, OAuthProvider};
 use oauth::{RandomSource, SecureRandom};
-use std::marker::PhantomData;
-
-use oauth::{OAuth, OAuthProvider};
-use reqwest::{
-    Client,
-    header::{AUTHORIZATION, USER_AGENT},
-};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
 use serde::Deserialize;
 use tonic::async_trait;
 
 use crate::{
     SERVICE_NAME,
-    oauth::{config::OauthConfig, error::Error, models::OAuth2Token},
+    oauth::{apply_token, config::OauthConfig, error::Error},
     proto::OauthProvider,
     utils::OAuthAccount,
 };
@@ -30,7 +26,7 @@ pub(crate) struct GithubOAuth<R> {
     client_id: String,
     client_secret: String,
     redirect_uri: String,
-    _phantom: PhantomData<R>,
+    oauth: OAuth<R>,
 }
 
 impl GithubOAuth<SecureRandom> {
@@ -40,7 +36,7 @@ impl GithubOAuth<SecureRandom> {
             client_id: config.github_client_id.clone(),
             client_secret: config.github_client_secret.clone(),
             redirect_uri: config.github_redirect_uri.clone(),
-            _phantom: PhantomData,
+            oauth: OAuth::new(),
         }
     }
 }
@@ -58,6 +54,7 @@ where
         &self,
         state: &str,
         code_challenge: &str,
+        _nonce: &str,
     ) -> Result<String, Self::Error> {
         let authorizaton_url = OAuth::<R>::generate_authorization_url(
             GITHUB_AUTH_ENDPOINT,
@@ -66,6 +63,7 @@ where
             vec!["user", "user:email"],
             state,
             code_challenge,
+            "",
         )?;
 
         Ok(authorizaton_url)
@@ -77,6 +75,7 @@ where
         &self,
         code: &str,
         code_verifier: &str,
+        _nonce: &str,
     ) -> Result<Self::Account, Self::Error> {
         #[derive(Debug, Deserialize)]
         struct GithubUser {
@@ -93,19 +92,24 @@ where
         }
 
         // Exchange authorization code for token
-        let token = OAuth::<R>::validate_authorization_code::<OAuth2Token>(
-            GITHUB_TOKEN_ENDPOINT,
-            &self.client_id,
-            &self.client_secret,
-            &self.redirect_uri,
-            code,
-            code_verifier,
-        )
-        .await?;
+        let token = self
+            .oauth
+            .validate_authorization_code::<OAuth2Token>(
+                GITHUB_TOKEN_ENDPOINT,
+                &self.client_id,
+                &self.client_secret,
+                &self.redirect_uri,
+                code,
+                code_verifier,
+            )
+            .await?;
 
-        let access_token = token.access_token.ok_or(Self::Error::MissingAccessToken)?;
+        let access_token = token
+            .access_token
+            .clone()
+            .ok_or(Self::Error::MissingAccessToken)?;
 
-        let client = Client::new();
+        let client = self.oauth.client();
 
         // Fetch GitHub user info
         let user_response = client
@@ -121,14 +125,16 @@ where
 
         // Use email if available directly
         if let Some(user_email) = user.email {
-            return Ok(Self::Account {
+            let mut account = Self::Account {
                 id: R::uuid().to_string(),
                 provider: OauthProvider::Github.into(),
                 external_user_id: user_id,
                 external_user_name: Some(user_name),
                 external_user_email: Some(user_email),
                 ..Default::default()
-            });
+            };
+            apply_token(&mut account, token, Utc::now());
+            return Ok(account);
         }
 
         // Otherwise, fetch email list
@@ -147,13 +153,32 @@ where
             .map(|e| e.email.clone())
             .ok_or(Self::Error::MissingEmail)?;
 
-        Ok(Self::Account {
+        let mut account = Self::Account {
             id: R::uuid().to_string(),
             provider: OauthProvider::Github.into(),
             external_user_id: user_id,
             external_user_name: Some(user_name),
             external_user_email: Some(user_email),
             ..Default::default()
-        })
+        };
+        apply_token(&mut account, token, Utc::now());
+
+        Ok(account)
+    }
+
+    /// Exchanges a refresh token for a new GitHub access token.
+    ///
+    /// GitHub only issues refresh tokens if token expiration is enabled.
+    async fn refresh_access_token(&self, refresh_token: &str) -> Result<OAuth2Token, Self::Error> {
+        let token = self
+            .oauth
+            .refresh_access_token(
+                GITHUB_TOKEN_ENDPOINT,
+                &self.client_id,
+                &self.client_secret,
+                refresh_token,
+            )
+            .await?;
+        Ok(token)
     }
 }
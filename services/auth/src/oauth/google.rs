@@ -1,10 +1,9 @@
-use std::marker::PhantomData;
-
-use oauth::{OAuth, OAuthProvider, RandomSource, SecureRandom};
+use chrono::Utc;
+use oauth::{OAuth, OAuth2Token, OAuthProvider, RandomSource, SecureRandom};
 use tonic::async_trait;
 
 use crate::{
-    oauth::{config::OauthConfig, error::Error, models::OAuth2Token},
+    oauth::{apply_token, config::OauthConfig, error::Error},
     proto::OauthProvider,
     utils::OAuthAccount,
 };
@@ -13,6 +12,7 @@ use crate::{
 const GOOGLE_JWKS_CERTS_ENDPOINT: &str = "https://www.googleapis.com/oauth2/v3/certs";
 const GOOGLE_TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
 const GOOGLE_AUTH_ENDPOINT: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_ISSUERS: &[&str] = &["https://accounts.google.com", "accounts.google.com"];
 
 /// OAuth 2.0 client for Google sign-in.
 ///
@@ -22,7 +22,7 @@ pub(crate) struct GoogleOAuth<R> {
     client_id: String,
     client_secret: String,
     redirect_uri: String,
-    _phantom: PhantomData<R>,
+    oauth: OAuth<R>,
 }
 
 impl GoogleOAuth<SecureRandom> {
@@ -32,7 +32,7 @@ impl GoogleOAuth<SecureRandom> {
             client_id: config.google_client_id.clone(),
             client_secret: config.google_client_secret.clone(),
             redirect_uri: config.google_redirect_uri.clone(),
-            _phantom: PhantomData,
+            oauth: OAuth::new(),
         }
     }
 }
@@ -50,6 +50,7 @@ where
         &self,
         state: &str,
         code_challenge: &str,
+        nonce: &str,
     ) -> Result<String, Self::Error> {
         let authorization_url = OAuth::<R>::generate_authorization_url(
             GOOGLE_AUTH_ENDPOINT,
@@ -58,8 +59,10 @@ where
             vec!["openid", "profile", "email"],
             state,
             code_challenge,
+            nonce,
         )?;
-        Ok(authorization_url)
+        // Without offline access Google does not return a refresh token.
+        Ok(format!("{authorization_url}&access_type=offline"))
     }
 
     /// Exchanges the authorization code for tokens, verifies the ID token,
@@ -68,32 +71,59 @@ where
         &self,
         code: &str,
         code_verifier: &str,
+        nonce: &str,
     ) -> Result<Self::Account, Self::Error> {
         // Exchange authorization code for token
-        let token = OAuth::<R>::validate_authorization_code::<OAuth2Token>(
-            GOOGLE_TOKEN_ENDPOINT,
-            &self.client_id,
-            &self.client_secret,
-            &self.redirect_uri,
-            code,
-            code_verifier,
-        )
-        .await?;
+        let token = self
+            .oauth
+            .validate_authorization_code::<OAuth2Token>(
+                GOOGLE_TOKEN_ENDPOINT,
+                &self.client_id,
+                &self.client_secret,
+                &self.redirect_uri,
+                code,
+                code_verifier,
+            )
+            .await?;
 
-        let id_token = token.id_token.ok_or(Self::Error::MissingIDToken)?;
+        let id_token = token.id_token.clone().ok_or(Self::Error::MissingIDToken)?;
 
         // Verify ID token and extract OIDC claims
-        let claims =
-            OAuth::<R>::verify_oidc_token(GOOGLE_JWKS_CERTS_ENDPOINT, &id_token, &self.client_id)
-                .await?;
+        let claims = self
+            .oauth
+            .verify_oidc_token(
+                GOOGLE_JWKS_CERTS_ENDPOINT,
+                &id_token,
+                &self.client_id,
+                GOOGLE_ISSUERS,
+                nonce,
+            )
+            .await?;
 
-        Ok(OAuthAccount {
+        let mut account = OAuthAccount {
             id: R::uuid().to_string(),
             provider: OauthProvider::Google.into(),
             external_user_id: claims.sub,
             external_user_name: claims.name,
             external_user_email: claims.email,
             ..Default::default()
-        })
+        };
+        apply_token(&mut account, token, Utc::now());
+
+        Ok(account)
+    }
+
+    /// Exchanges a refresh token for a new Google access token.
+    async fn refresh_access_token(&self, refresh_token: &str) -> Result<OAuth2Token, Self::Error> {
+        let token = self
+            .oauth
+            .refresh_access_token(
+                GOOGLE_TOKEN_ENDPOINT,
+                &self.client_id,
+                &self.client_secret,
+                refresh_token,
+            )
+            .await?;
+        Ok(token)
     }
 }
@@ -0,0 +1,50 @@
+use oauth::{OAuth2Token, OAuthProvider};
+use tonic::async_trait;
+
+use crate::{oauth::error::Error, utils::OAuthAccount};
+
+/// An oauth provider that returns a canned account without sending
+/// any http requests.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MockOAuthProvider {
+    /// The account returned by [`MockOAuthProvider::exchange_code`].
+    pub(crate) account: OAuthAccount,
+}
+
+impl MockOAuthProvider {
+    /// Creates a new [`MockOAuthProvider`] returning `account`.
+    pub(crate) fn new(account: OAuthAccount) -> Self {
+        Self { account }
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for MockOAuthProvider {
+    type Account = OAuthAccount;
+    type Error = Error;
+
+    fn generate_authorization_url(
+        &self,
+        state: &str,
+        _code_challenge: &str,
+        _nonce: &str,
+    ) -> Result<String, Self::Error> {
+        Ok(format!("https://example.com/auth?state={state}"))
+    }
+
+    async fn exchange_code(
+        &self,
+        _code: &str,
+        _code_verifier: &str,
+        _nonce: &str,
+    ) -> Result<Self::Account, Self::Error> {
+        Ok(self.account.clone())
+    }
+
+    async fn refresh_access_token(&self, _refresh_token: &str) -> Result<OAuth2Token, Self::Error> {
+        Ok(OAuth2Token {
+            access_token: self.account.access_token.clone(),
+            ..OAuth2Token::default()
+        })
+    }
+}
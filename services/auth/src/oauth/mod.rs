@@ -1,5 +1,68 @@
+pub(crate) mod apple;
 pub(crate) mod config;
+pub(crate) mod email_domains;
 pub(crate) mod error;
 pub(crate) mod github;
 pub(crate) mod google;
-pub(crate) mod models;
+#[cfg(test)]
+pub(crate) mod mock;
+
+use crate::utils::OAuthAccount;
+use chrono::{DateTime, Duration, Utc};
+use oauth::{OAuth2Token, OAuthProvider};
+
+/// An oauth provider whose login yields an [`OAuthAccount`].
+pub(crate) trait OAuthAccountProvider:
+    OAuthProvider<Account = OAuthAccount, Error = error::Error> + 'static
+{
+}
+
+impl<T> OAuthAccountProvider for T where
+    T: OAuthProvider<Account = OAuthAccount, Error = error::Error> + 'static
+{
+}
+
+/// Copies the tokens of a token response into an account.
+///
+/// A missing refresh token keeps the stored one, because most providers
+/// only return a refresh token on the first login.
+pub(crate) fn apply_token(account: &mut OAuthAccount, token: OAuth2Token, now: DateTime<Utc>) {
+    account.access_token = token.access_token;
+    account.access_token_expires_at = token
+        .expires_in
+        .and_then(|secs| i64::try_from(secs).ok())
+        .map(|secs| now + Duration::seconds(secs));
+    if token.refresh_token.is_some() {
+        account.refresh_token = token.refresh_token;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::fixture_oauth_account;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_apply_token() {
+        // given
+        let mut account = fixture_oauth_account(|a| a.refresh_token = Some("old".to_string()));
+        let now = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let token = OAuth2Token {
+            access_token: Some("new-access-token".to_string()),
+            expires_in: Some(3600),
+            ..Default::default()
+        };
+
+        // when
+        apply_token(&mut account, token, now);
+
+        // then
+        assert_eq!(account.access_token.as_deref(), Some("new-access-token"));
+        assert_eq!(
+            account.access_token_expires_at,
+            Some(Utc.with_ymd_and_hms(2020, 1, 1, 1, 0, 0).unwrap())
+        );
+        assert_eq!(account.refresh_token.as_deref(), Some("old"));
+    }
+}
@@ -19,6 +19,9 @@ pub struct CreateSessionResp {
     /// The created session token.
     #[prost(string, tag = "1")]
     pub token: ::prost::alloc::string::String,
+    /// The number of seconds until the session expires.
+    #[prost(int64, tag = "2")]
+    pub expires_in_seconds: i64,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -36,6 +39,15 @@ pub struct ValidateSessionResp {
     /// Whether the session cookie should be refreshed.
     #[prost(bool, tag = "2")]
     pub should_refresh_cookie: bool,
+    /// The user's display name from the linked OAuth account, if any.
+    #[prost(string, tag = "3")]
+    pub user_name: ::prost::alloc::string::String,
+    /// The user's email from the linked OAuth account, if any.
+    #[prost(string, tag = "4")]
+    pub user_email: ::prost::alloc::string::String,
+    /// When the session expires, as unix timestamp in seconds.
+    #[prost(int64, tag = "5")]
+    pub expires_at: i64,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -66,6 +78,9 @@ pub struct StartOauthLoginResp {
     /// Code verifier for PKCE.
     #[prost(string, tag = "3")]
     pub code_verifier: ::prost::alloc::string::String,
+    /// OpenID Connect nonce, empty if the provider does not support it.
+    #[prost(string, tag = "4")]
+    pub nonce: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -79,6 +94,9 @@ pub struct HandleOauthCallbackReq {
     /// Code verifier for PKCE.
     #[prost(string, tag = "3")]
     pub code_verifier: ::prost::alloc::string::String,
+    /// OpenID Connect nonce returned by StartOauthLogin.
+    #[prost(string, tag = "4")]
+    pub nonce: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -127,12 +145,88 @@ pub struct GetOauthAccountResp {
     pub external_user_id: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RefreshOauthTokenReq {
+    /// The user ID whose OAuth account is refreshed.
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    /// The OAuth provider.
+    #[prost(enumeration = "OauthProvider", tag = "2")]
+    pub provider: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RefreshOauthTokenResp {
+    /// The number of seconds until the new access token expires, 0 if unknown.
+    #[prost(int64, tag = "1")]
+    pub expires_in_seconds: i64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GetActiveSessionsCountReq {
+    /// The user ID to count the sessions for.
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GetActiveSessionsCountResp {
+    /// The number of unexpired sessions of the user.
+    #[prost(int64, tag = "1")]
+    pub count: i64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ListSessionsReq {
+    /// The user ID to list the sessions for.
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SessionInfo {
+    /// The session ID, without the secret.
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// The creation time as unix timestamp in seconds.
+    #[prost(int64, tag = "2")]
+    pub created_at: i64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSessionsResp {
+    /// The unexpired sessions of the user, newest first.
+    #[prost(message, repeated, tag = "1")]
+    pub sessions: ::prost::alloc::vec::Vec<SessionInfo>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct DeleteAllSessionsReq {
+    /// The user ID to delete the sessions of.
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct DeleteAllSessionsResp {
+    /// The number of deleted sessions.
+    #[prost(uint64, tag = "1")]
+    pub count: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PingReq {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PingResp {}
+#[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum OauthProvider {
     Unspecified = 0,
     Google = 1,
     Github = 2,
+    Apple = 3,
 }
 impl OauthProvider {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -144,6 +238,7 @@ impl OauthProvider {
             Self::Unspecified => "OAUTH_PROVIDER_UNSPECIFIED",
             Self::Google => "OAUTH_PROVIDER_GOOGLE",
             Self::Github => "OAUTH_PROVIDER_GITHUB",
+            Self::Apple => "OAUTH_PROVIDER_APPLE",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -152,6 +247,7 @@ impl OauthProvider {
             "OAUTH_PROVIDER_UNSPECIFIED" => Some(Self::Unspecified),
             "OAUTH_PROVIDER_GOOGLE" => Some(Self::Google),
             "OAUTH_PROVIDER_GITHUB" => Some(Self::Github),
+            "OAUTH_PROVIDER_APPLE" => Some(Self::Apple),
             _ => None,
         }
     }
@@ -423,6 +519,125 @@ pub mod auth_service_client {
                 .insert(GrpcMethod::new("auth.AuthService", "GetOauthAccount"));
             self.inner.unary(req, path, codec).await
         }
+        /// Renews the access token of a user's OAuth account with its refresh token.
+        pub async fn refresh_oauth_token(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RefreshOauthTokenReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshOauthTokenResp>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.AuthService/RefreshOauthToken",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("auth.AuthService", "RefreshOauthToken"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Counts the unexpired sessions of a user.
+        pub async fn get_active_sessions_count(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetActiveSessionsCountReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetActiveSessionsCountResp>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.AuthService/GetActiveSessionsCount",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("auth.AuthService", "GetActiveSessionsCount"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Lists the unexpired sessions of a user, newest first.
+        pub async fn list_sessions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListSessionsReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSessionsResp>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.AuthService/ListSessions",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("auth.AuthService", "ListSessions"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Deletes every session of a user, e.g. after a security event.
+        pub async fn delete_all_sessions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteAllSessionsReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteAllSessionsResp>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/auth.AuthService/DeleteAllSessions",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("auth.AuthService", "DeleteAllSessions"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns immediately, to check that the service is reachable.
+        pub async fn ping(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PingReq>,
+        ) -> std::result::Result<tonic::Response<super::PingResp>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/auth.AuthService/Ping");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("auth.AuthService", "Ping"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -494,6 +709,43 @@ pub mod auth_service_server {
             tonic::Response<super::GetOauthAccountResp>,
             tonic::Status,
         >;
+        /// Renews the access token of a user's OAuth account with its refresh token.
+        async fn refresh_oauth_token(
+            &self,
+            request: tonic::Request<super::RefreshOauthTokenReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::RefreshOauthTokenResp>,
+            tonic::Status,
+        >;
+        /// Counts the unexpired sessions of a user.
+        async fn get_active_sessions_count(
+            &self,
+            request: tonic::Request<super::GetActiveSessionsCountReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetActiveSessionsCountResp>,
+            tonic::Status,
+        >;
+        /// Lists the unexpired sessions of a user, newest first.
+        async fn list_sessions(
+            &self,
+            request: tonic::Request<super::ListSessionsReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSessionsResp>,
+            tonic::Status,
+        >;
+        /// Deletes every session of a user, e.g. after a security event.
+        async fn delete_all_sessions(
+            &self,
+            request: tonic::Request<super::DeleteAllSessionsReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteAllSessionsResp>,
+            tonic::Status,
+        >;
+        /// Returns immediately, to check that the service is reachable.
+        async fn ping(
+            &self,
+            request: tonic::Request<super::PingReq>,
+        ) -> std::result::Result<tonic::Response<super::PingResp>, tonic::Status>;
     }
     /// Service for authentication, session management, and OAuth integration.
     #[derive(Debug)]
@@ -889,6 +1141,235 @@ pub mod auth_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/auth.AuthService/RefreshOauthToken" => {
+                    #[allow(non_camel_case_types)]
+                    struct RefreshOauthTokenSvc<T: AuthService>(pub Arc<T>);
+                    impl<
+                        T: AuthService,
+                    > tonic::server::UnaryService<super::RefreshOauthTokenReq>
+                    for RefreshOauthTokenSvc<T> {
+                        type Response = super::RefreshOauthTokenResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RefreshOauthTokenReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AuthService>::refresh_oauth_token(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RefreshOauthTokenSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.AuthService/GetActiveSessionsCount" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetActiveSessionsCountSvc<T: AuthService>(pub Arc<T>);
+                    impl<
+                        T: AuthService,
+                    > tonic::server::UnaryService<super::GetActiveSessionsCountReq>
+                    for GetActiveSessionsCountSvc<T> {
+                        type Response = super::GetActiveSessionsCountResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetActiveSessionsCountReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AuthService>::get_active_sessions_count(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetActiveSessionsCountSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.AuthService/ListSessions" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSessionsSvc<T: AuthService>(pub Arc<T>);
+                    impl<
+                        T: AuthService,
+                    > tonic::server::UnaryService<super::ListSessionsReq>
+                    for ListSessionsSvc<T> {
+                        type Response = super::ListSessionsResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListSessionsReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AuthService>::list_sessions(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListSessionsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.AuthService/DeleteAllSessions" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteAllSessionsSvc<T: AuthService>(pub Arc<T>);
+                    impl<
+                        T: AuthService,
+                    > tonic::server::UnaryService<super::DeleteAllSessionsReq>
+                    for DeleteAllSessionsSvc<T> {
+                        type Response = super::DeleteAllSessionsResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteAllSessionsReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AuthService>::delete_all_sessions(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DeleteAllSessionsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/auth.AuthService/Ping" => {
+                    #[allow(non_camel_case_types)]
+                    struct PingSvc<T: AuthService>(pub Arc<T>);
+                    impl<T: AuthService> tonic::server::UnaryService<super::PingReq>
+                    for PingSvc<T> {
+                        type Response = super::PingResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PingReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AuthService>::ping(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = PingSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
@@ -0,0 +1,34 @@
+use crate::db::DBClient;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How often expired sessions are pruned if `SESSION_PRUNE_INTERVAL_SECONDS` is unset.
+const DEFAULT_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Reads the pruning interval from `SESSION_PRUNE_INTERVAL_SECONDS`.
+///
+/// # Errors
+/// - the variable is not a valid number
+pub(crate) fn prune_interval_from_env() -> Result<Duration, std::num::ParseIntError> {
+    match std::env::var("SESSION_PRUNE_INTERVAL_SECONDS") {
+        Ok(secs) => Ok(Duration::from_secs(secs.parse()?)),
+        Err(_) => Ok(DEFAULT_PRUNE_INTERVAL),
+    }
+}
+
+/// Periodically deletes expired sessions in the background.
+///
+/// Expired sessions are otherwise only deleted when they are used again,
+/// so sessions that are never used again would accumulate.
+pub(crate) fn spawn_session_pruning<D: DBClient>(db: D, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match db.prune_expired_sessions().await {
+                Ok(count) => tracing::info!(count, "pruned expired sessions"),
+                Err(err) => tracing::error!("failed to prune expired sessions: {err}"),
+            }
+        }
+    })
+}
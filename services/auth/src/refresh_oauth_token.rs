@@ -0,0 +1,156 @@
+use crate::{
+    db::DBClient,
+    error::Error,
+    handler::Handler,
+    oauth::{OAuthAccountProvider, apply_token},
+    proto::{OauthProvider, RefreshOauthTokenReq, RefreshOauthTokenResp},
+};
+use common::Now;
+use setup::validate_user_id;
+use tonic::{Request, Response, Status};
+
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
+where
+    D: DBClient,
+    N: Now,
+    G: OAuthAccountProvider,
+    GH: OAuthAccountProvider,
+    A: OAuthAccountProvider,
+{
+    /// Renews the access token of a user's oauth account with the stored
+    /// refresh token, so that the user does not have to log in again.
+    ///
+    /// # Errors
+    /// - user id is missing or malformed
+    /// - provider is unspecified
+    /// - the account has no refresh token
+    /// - the provider rejects the refresh token
+    /// - database error
+    pub async fn refresh_oauth_token(
+        &self,
+        req: Request<RefreshOauthTokenReq>,
+    ) -> Result<Response<RefreshOauthTokenResp>, Status> {
+        let req = req.into_inner();
+
+        let user_id = validate_user_id(&req.user_id)?;
+        let provider = req.provider();
+        if provider == OauthProvider::Unspecified {
+            return Err(Error::UnspecifiedOauthProvider.into());
+        }
+
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        let mut account = self
+            .db
+            .get_oauth_account(user_id, provider)
+            .await
+            .map_err(Error::GetOauthAccount)?;
+        let refresh_token = account
+            .refresh_token
+            .clone()
+            .ok_or(Error::MissingRefreshToken)?;
+
+        let token = match provider {
+            OauthProvider::Google => self.google.refresh_access_token(&refresh_token).await,
+            OauthProvider::Github => self.github.refresh_access_token(&refresh_token).await,
+            OauthProvider::Apple => self.apple.refresh_access_token(&refresh_token).await,
+            OauthProvider::Unspecified => return Err(Error::UnspecifiedOauthProvider.into()),
+        }?;
+        let expires_in_seconds = token
+            .expires_in
+            .and_then(|secs| i64::try_from(secs).ok())
+            .unwrap_or_default();
+
+        apply_token(&mut account, token, self.now.now());
+        self.db
+            .upsert_oauth_account(&account)
+            .await
+            .map_err(Error::UpsertOauthAccount)?;
+
+        Ok(Response::new(RefreshOauthTokenResp { expires_in_seconds }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::Sha256Hasher;
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_oauth_account, fixture_uuid},
+        handler::Handler,
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
+        proto::{OauthProvider, RefreshOauthTokenReq, RefreshOauthTokenResp},
+        utils::OAuthAccount,
+    };
+    use common::mock::MockNow;
+    use oauth::mock::MockRandom;
+    use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
+    use std::marker::PhantomData;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    #[rstest]
+    #[case::missing_user_id(
+        RefreshOauthTokenReq {
+            user_id: String::new(),
+            provider: OauthProvider::Google as i32,
+        },
+        vec![],
+        Err(Code::InvalidArgument)
+    )]
+    #[case::unspecified_provider(
+        RefreshOauthTokenReq {
+            user_id: fixture_uuid().to_string(),
+            provider: OauthProvider::Unspecified as i32,
+        },
+        vec![],
+        Err(Code::InvalidArgument)
+    )]
+    #[case::missing_refresh_token(
+        RefreshOauthTokenReq {
+            user_id: fixture_uuid().to_string(),
+            provider: OauthProvider::Google as i32,
+        },
+        vec![Ok(fixture_oauth_account(|a| a.refresh_token = None))],
+        Err(Code::FailedPrecondition)
+    )]
+    #[case::db_error(
+        RefreshOauthTokenReq {
+            user_id: fixture_uuid().to_string(),
+            provider: OauthProvider::Google as i32,
+        },
+        vec![Err(DBError::Unknown)],
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_refresh_oauth_token(
+        #[case] req: RefreshOauthTokenReq,
+        #[case] db_results: Vec<Result<OAuthAccount, DBError>>,
+        #[case] want: Result<RefreshOauthTokenResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_get_oauth_account(db_results);
+        let handler = Handler {
+            db,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = handler.refresh_oauth_token(Request::new(req)).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
@@ -1,15 +1,19 @@
-use oauth::{OAuth, OAuthProvider as _, RandomSource};
+use oauth::{OAuth, RandomSource};
 use tonic::{Request, Response, Status};
 
 use crate::{
     error::Error,
     handler::Handler,
+    oauth::OAuthAccountProvider,
     proto::{OauthProvider, StartOauthLoginReq, StartOauthLoginResp},
 };
 
-impl<D, R, N> Handler<D, R, N>
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     R: RandomSource + Clone,
+    G: OAuthAccountProvider,
+    GH: OAuthAccountProvider,
+    A: OAuthAccountProvider,
 {
     /// Starts a oauth login.
     ///
@@ -22,19 +26,29 @@ where
         let req = req.into_inner();
 
         let state = OAuth::<R>::generate_state();
-        let (code_verifier, authorization_url) = match req.provider() {
+        let (code_verifier, nonce, authorization_url) = match req.provider() {
             OauthProvider::Google => {
                 let verifier = OAuth::<R>::generate_code_verifier();
                 let challenge = OAuth::<R>::create_s256_code_challenge(&verifier);
+                let nonce = OAuth::<R>::generate_nonce();
 
-                let auth_url = self.google.generate_authorization_url(&state, &challenge)?;
+                let auth_url = self
+                    .google
+                    .generate_authorization_url(&state, &challenge, &nonce)?;
 
-                (verifier, auth_url)
+                (verifier, nonce, auth_url)
             }
             OauthProvider::Github => {
-                let auth_url = self.github.generate_authorization_url(&state, "")?;
+                let auth_url = self.github.generate_authorization_url(&state, "", "")?;
 
-                (String::new(), auth_url)
+                (String::new(), String::new(), auth_url)
+            }
+            OauthProvider::Apple => {
+                let nonce = OAuth::<R>::generate_nonce();
+
+                let auth_url = self.apple.generate_authorization_url(&state, "", &nonce)?;
+
+                (String::new(), nonce, auth_url)
             }
             _ => return Err(Error::UnspecifiedOauthProvider.into()),
         };
@@ -43,6 +57,7 @@ where
             state,
             code_verifier,
             authorization_url,
+            nonce,
         }))
     }
 }
@@ -1,11 +1,16 @@
 use uuid::Uuid;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use reqwest::Client;
 use serde::Deserialize;
+use setup::constant_time_equal;
 use sha2::{Digest, Sha256};
 use tokio_postgres::Row;
 
+/// A session as stored in the database.
+///
+/// Prefer [`DBSession::new`] over [`Default`], which leaves
+/// `expires_at` at the epoch and is meant for test fixtures only.
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct DBSession {
     pub id: String,
@@ -15,6 +20,26 @@ pub struct DBSession {
     pub user_id: Uuid,
 }
 
+impl DBSession {
+    /// Creates a new session that expires `expiry` after `created_at`.
+    #[must_use]
+    pub fn new(
+        id: String,
+        secret_hash: Vec<u8>,
+        user_id: Uuid,
+        created_at: DateTime<Utc>,
+        expiry: Duration,
+    ) -> Self {
+        Self {
+            id,
+            secret_hash,
+            created_at,
+            expires_at: created_at + expiry,
+            user_id,
+        }
+    }
+}
+
 impl TryFrom<&Row> for DBSession {
     type Error = tokio_postgres::Error;
 
@@ -29,6 +54,26 @@ impl TryFrom<&Row> for DBSession {
     }
 }
 
+/// A session together with the display fields of the user's oauth account.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DBSessionWithUser {
+    pub session: DBSession,
+    pub user_name: Option<String>,
+    pub user_email: Option<String>,
+}
+
+impl TryFrom<&Row> for DBSessionWithUser {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        Ok(DBSessionWithUser {
+            session: DBSession::try_from(row)?,
+            user_name: row.try_get("external_user_name")?,
+            user_email: row.try_get("external_user_email")?,
+        })
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct OAuthAccount {
     pub id: String,
@@ -60,29 +105,32 @@ impl TryFrom<&Row> for OAuthAccount {
     }
 }
 
+/// Hashes session secrets before they are stored.
+pub trait SecretHasher: Send + Sync + 'static {
+    /// Hashes a secret.
+    fn hash(&self, secret: &str) -> Vec<u8>;
+
+    /// Returns whether the secret matches the hash. The comparison
+    /// runs in constant time.
+    fn verify(&self, secret: &str, hash: &[u8]) -> bool {
+        constant_time_equal(&self.hash(secret), hash)
+    }
+}
+
 /// Hashes a secret using SHA-256. While SHA-256 is unsuitable
 /// for user passwords, because the secret has 120 bits of entropy
 /// an offline brute-force attack is impossible.
 ///
 /// [`Documentation`]: https://lucia-auth.com/sessions/basic
-#[must_use]
-pub fn hash_secret(secret: &str) -> Vec<u8> {
-    let mut hasher = Sha256::new();
-    hasher.update(secret);
-    hasher.finalize().to_vec()
-}
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256Hasher;
 
-/// Compares two byte slices for equality in constant time to prevent timing attacks.
-#[must_use]
-pub fn constant_time_equal(a: &[u8], b: &[u8]) -> bool {
-    if a.len() != b.len() {
-        return false;
+impl SecretHasher for Sha256Hasher {
+    fn hash(&self, secret: &str) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(secret);
+        hasher.finalize().to_vec()
     }
-    let mut c = 0u8;
-    for (&x, &y) in a.iter().zip(b.iter()) {
-        c |= x ^ y;
-    }
-    c == 0
 }
 
 /// Represents the claims in an OIDC ID token.
@@ -124,3 +172,45 @@ async fn get_jwks(endpoint: &str) -> Result<Jwks, Box<dyn std::error::Error>> {
     let res = client.get(endpoint).send().await?.json::<Jwks>().await?;
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_new_session_expires_in_the_future() {
+        // given
+        let created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+
+        // when
+        let session = DBSession::new(
+            "id".to_string(),
+            Sha256Hasher.hash("secret"),
+            Uuid::nil(),
+            created_at,
+            Duration::days(7),
+        );
+
+        // then
+        assert!(session.expires_at > created_at);
+        assert_eq!(
+            session.expires_at,
+            Utc.with_ymd_and_hms(2020, 1, 8, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_verify_secret() {
+        // given
+        let hash = Sha256Hasher.hash("secret");
+
+        // when
+        let valid = Sha256Hasher.verify("secret", &hash);
+        let invalid = Sha256Hasher.verify("other", &hash);
+
+        // then
+        assert!(valid);
+        assert!(!invalid);
+    }
+}
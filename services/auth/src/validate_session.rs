@@ -3,6 +3,9 @@
 //! Validates a session token by parsing out the id and secret
 //! from the token, getting the session with the id, checking
 //! the expiration and comparing the secret against the hash.
+//!
+//! The session is fetched together with the user's display fields
+//! so that callers don't need a second round-trip for them.
 
 use tonic::{Request, Response, Status};
 
@@ -11,17 +14,17 @@ use crate::{
     error::{DBError, Error},
     handler::Handler,
     proto::{ValidateSessionReq, ValidateSessionResp},
-    utils::{constant_time_equal, hash_secret},
+    utils::{DBSessionWithUser, SecretHasher},
 };
 use common::Now;
 use oauth::RandomSource;
-use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
 
-impl<D, R, N> Handler<D, R, N>
+impl<D, R, N, H, G, GH, A> Handler<D, R, N, H, G, GH, A>
 where
     D: DBClient,
     R: RandomSource + Clone,
     N: Now,
+    H: SecretHasher,
 {
     /// Validates a sessions token by parsing out the id and secret
     /// from the token, getting the session with the id, checking
@@ -53,27 +56,37 @@ where
         let session_id = token_parts[0];
         let session_secret = token_parts[1];
 
-        let session = self.db.get_session(session_id).await.map_err(|e| match e {
-            DBError::NotFound(_) => Error::NotFound,
-            _ => Error::GetSession(e),
-        })?;
+        let DBSessionWithUser {
+            session,
+            user_name,
+            user_email,
+        } = self
+            .db
+            .get_session_with_user(session_id)
+            .await
+            .map_err(|e| match e {
+                DBError::NotFound(_) => Error::NotFound,
+                _ => Error::GetSession(e),
+            })?;
 
-        if N::now() >= session.expires_at {
+        let now = self.now.now();
+        if now >= session.expires_at {
             let result = self.db.delete_session(&session.id).await;
             result.map_err(Error::DeleteSession)?;
             return Err(Error::ExpiredToken.into());
         }
 
         let mut should_refresh_cookie = false;
-        if session.expires_at.signed_duration_since(N::now()) < SESSION_TOKEN_EXPIRY_DURATION / 2
-            && let Some(new_expiry) = N::now().checked_add_signed(SESSION_TOKEN_EXPIRY_DURATION)
+        let mut expires_at = session.expires_at;
+        if session.expires_at.signed_duration_since(now) < self.expiry / 2
+            && let Some(new_expiry) = now.checked_add_signed(self.expiry)
         {
             let _ = self.db.update_session(session_id, &new_expiry).await;
             should_refresh_cookie = true;
+            expires_at = new_expiry;
         }
 
-        let token_secret_hash = hash_secret(session_secret);
-        let valid_secret = constant_time_equal(&token_secret_hash, &session.secret_hash);
+        let valid_secret = self.hasher.verify(session_secret, &session.secret_hash);
         if !valid_secret {
             return Err(Error::SecretMismatch.into());
         }
@@ -81,6 +94,9 @@ where
         Ok(Response::new(ValidateSessionResp {
             user_id: session.user_id.to_string(),
             should_refresh_cookie,
+            user_name: user_name.unwrap_or_default(),
+            user_email: user_email.unwrap_or_default(),
+            expires_at: expires_at.timestamp(),
         }))
     }
 }
@@ -90,11 +106,12 @@ mod tests {
     use std::marker::PhantomData;
 
     use chrono::TimeZone;
-    use common::mock::MockNow;
+    use common::{Now as _, mock::MockNow};
     use oauth::mock::MockRandom;
     use rstest::rstest;
+    use setup::metrics::OAuthMetrics;
+    use setup::session::SESSION_TOKEN_EXPIRY_DURATION;
     use testutils::assert_response;
-    use tokio::sync::Mutex;
     use tonic::{Code, Request};
 
     use crate::{
@@ -102,9 +119,9 @@ mod tests {
         error::DBError,
         fixture::{fixture_db_session, fixture_token, fixture_uuid},
         handler::Handler,
-        oauth::{github::GithubOAuth, google::GoogleOAuth},
+        oauth::{email_domains::EmailDomainPolicy, mock::MockOAuthProvider},
         proto::{ValidateSessionReq, ValidateSessionResp},
-        utils::DBSession,
+        utils::{DBSession, DBSessionWithUser, Sha256Hasher},
     };
 
     #[rstest]
@@ -118,6 +135,8 @@ mod tests {
         Ok(ValidateSessionResp {
             user_id: fixture_uuid().to_string(),
             should_refresh_cookie: false,
+            expires_at: chrono::Utc.with_ymd_and_hms(2020, 1, 8, 0, 0, 0).unwrap().timestamp(),
+            ..Default::default()
         })
     )]
     #[case::missing_token(
@@ -170,6 +189,8 @@ mod tests {
         Ok(ValidateSessionResp {
             user_id: fixture_uuid().to_string(),
             should_refresh_cookie: true,
+            expires_at: (MockNow::default_time().now() + SESSION_TOKEN_EXPIRY_DURATION).timestamp(),
+            ..Default::default()
         })
     )]
     #[case::secret_mismatch(
@@ -201,17 +222,24 @@ mod tests {
         #[case] want: Result<ValidateSessionResp, Code>,
     ) {
         // given
-        let db = MockDBClient {
-            get_session: Mutex::new(Some(db_result)),
-            delete_session: Mutex::new(Some(Ok(()))),
-            update_session: Mutex::new(Some(Ok(()))),
+        let mut db = MockDBClient::default();
+        db.expect_get_session_with_user([db_result.map(|session| DBSessionWithUser {
+            session,
             ..Default::default()
-        };
+        })])
+        .expect_delete_session([Ok(())])
+        .expect_update_session([Ok(())]);
         let handler = Handler {
             db,
-            google: GoogleOAuth::<MockRandom>::default(),
-            github: GithubOAuth::<MockRandom>::default(),
-            _now: PhantomData::<MockNow>,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
         };
 
         // when
@@ -232,4 +260,96 @@ mod tests {
             "delete_session_calls mismatch",
         );
     }
+
+    #[tokio::test]
+    async fn test_validate_session_returns_user_data() {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_get_session_with_user([Ok(DBSessionWithUser {
+            session: fixture_db_session(|_| {}),
+            user_name: Some("name".to_string()),
+            user_email: Some("email".to_string()),
+        })]);
+        let handler = Handler {
+            db,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: MockNow::default_time(),
+        };
+        let req = ValidateSessionReq {
+            token: fixture_token(),
+        };
+
+        // when
+        let got = handler.validate_session(Request::new(req)).await;
+
+        // then
+        let want = ValidateSessionResp {
+            user_id: fixture_uuid().to_string(),
+            should_refresh_cookie: false,
+            user_name: "name".to_string(),
+            user_email: "email".to_string(),
+            expires_at: chrono::Utc
+                .with_ymd_and_hms(2020, 1, 8, 0, 0, 0)
+                .unwrap()
+                .timestamp(),
+        };
+        assert_response(got, Ok(want));
+    }
+
+    #[tokio::test]
+    async fn test_validate_session_refreshes_after_half_the_expiry() {
+        // given
+        let mut db = MockDBClient::default();
+        let session = fixture_db_session(|_| {});
+        db.expect_get_session_with_user([
+            Ok(DBSessionWithUser {
+                session: session.clone(),
+                ..Default::default()
+            }),
+            Ok(DBSessionWithUser {
+                session,
+                ..Default::default()
+            }),
+        ])
+        .expect_update_session([Ok(())]);
+        let now = MockNow::default_time();
+        let handler = Handler {
+            db,
+            google: MockOAuthProvider::default(),
+            github: MockOAuthProvider::default(),
+            apple: MockOAuthProvider::default(),
+            email_domains: EmailDomainPolicy::default(),
+            oauth_metrics: OAuthMetrics::default(),
+            hasher: Sha256Hasher,
+            expiry: SESSION_TOKEN_EXPIRY_DURATION,
+            _random: PhantomData::<MockRandom>,
+            now: now.clone(),
+        };
+        let req = || {
+            Request::new(ValidateSessionReq {
+                token: fixture_token(),
+            })
+        };
+
+        // when
+        let before = handler.validate_session(req()).await.unwrap().into_inner();
+        now.advance(SESSION_TOKEN_EXPIRY_DURATION / 2 + chrono::Duration::seconds(1));
+        let after = handler.validate_session(req()).await.unwrap().into_inner();
+
+        // then
+        assert!(!before.should_refresh_cookie);
+        assert!(after.should_refresh_cookie);
+        assert_eq!(
+            after.expires_at,
+            (now.now() + SESSION_TOKEN_EXPIRY_DURATION).timestamp()
+        );
+        assert_eq!(handler.db.update_session_calls(), 1);
+    }
 }
@@ -1,70 +1,183 @@
 // This file is generated.
 use crate::GRPC_PORT;
 use crate::SERVICE_NAME;
+use crate::proto::CreateEntityReq;
+use crate::proto::CreateEntityResp;
 use crate::proto::GetEntityReq;
 use crate::proto::GetEntityResp;
+use crate::proto::ListEntitiesReq;
+use crate::proto::ListEntitiesResp;
 use crate::proto::dummy_service_client::DummyServiceClient;
-use setup::{middleware::tracing::TracingServiceClient, patched_host};
-use std::{error::Error, str::FromStr as _};
-use tonic::transport::{Channel, Endpoint};
+use setup::endpoint::{
+    ConnectRetry, DEFAULT_REQUEST_TIMEOUT, connect_with_retry, deadline_exceeded, service_endpoint,
+};
+use setup::middleware::tracing::TracingServiceClient;
+use std::error::Error;
+use std::time::Duration;
+use tonic::transport::Channel;
 use tonic::{Request, Response, Status, async_trait};
 
 #[derive(Clone)]
 pub struct DummyClient(DummyServiceClient<TracingServiceClient<Channel>>);
 
 impl DummyClient {
+    /// Creates a client with the [`DEFAULT_REQUEST_TIMEOUT`].
     pub async fn new() -> Result<Self, Box<dyn Error>> {
-        let host = patched_host(String::from(SERVICE_NAME));
-        let endpoint = Endpoint::from_str(&format!("http://{host}:{GRPC_PORT}"))?;
-        let channel = endpoint.connect().await?;
-        let client = TracingServiceClient::new(channel);
-        let client = DummyServiceClient::new(client);
+        Self::new_with_timeout(DEFAULT_REQUEST_TIMEOUT).await
+    }
+
+    /// Creates a client whose requests fail with `DeadlineExceeded`
+    /// if they take longer than `timeout`.
+    ///
+    /// Connecting is retried as configured by [`ConnectRetry::from_env`].
+    pub async fn new_with_timeout(timeout: Duration) -> Result<Self, Box<dyn Error>> {
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(timeout);
+        let channel = connect_with_retry(&endpoint, ConnectRetry::from_env()?).await?;
+
+        Ok(Self::from_channel(channel))
+    }
+
+    /// Creates a client that connects on its first request.
+    ///
+    /// Unlike [`Self::new`], this does not wait for the service to be up,
+    /// so callers can start in any order and recover once it becomes
+    /// reachable. The tradeoff is that a misconfigured or unreachable
+    /// service is only noticed when the first request fails.
+    pub fn lazy() -> Result<Self, Box<dyn Error>> {
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(DEFAULT_REQUEST_TIMEOUT);
+        let channel = endpoint.connect_lazy();
 
-        Ok(Self(client))
+        Ok(Self::from_channel(channel))
+    }
+
+    fn from_channel(channel: Channel) -> Self {
+        let client = TracingServiceClient::new(channel);
+        Self(DummyServiceClient::new(client))
     }
 }
 
 #[rustfmt::skip]
 #[async_trait]
 pub trait IDummyClient: Send + Sync + 'static {
+    async fn create_entity(&self, req: Request<CreateEntityReq>) -> Result<Response<CreateEntityResp>, Status>;
     async fn get_entity(&self, req: Request<GetEntityReq>) -> Result<Response<GetEntityResp>, Status>;
+    async fn list_entities(&self, req: Request<ListEntitiesReq>) -> Result<Response<ListEntitiesResp>, Status>;
 }
 
 #[rustfmt::skip]
 #[async_trait]
 impl IDummyClient for DummyClient {
+    async fn create_entity(&self, req: Request<CreateEntityReq>) -> Result<Response<CreateEntityResp>, Status> {
+        self.0.clone().create_entity(req).await.map_err(deadline_exceeded)
+    }
     async fn get_entity(&self, req: Request<GetEntityReq>) -> Result<Response<GetEntityResp>, Status> {
-        self.0.clone().get_entity(req).await
+        self.0.clone().get_entity(req).await.map_err(deadline_exceeded)
+    }
+    async fn list_entities(&self, req: Request<ListEntitiesReq>) -> Result<Response<ListEntitiesResp>, Status> {
+        self.0.clone().list_entities(req).await.map_err(deadline_exceeded)
     }
 }
 
 #[cfg(feature = "testutils")]
 pub mod testutils {
     use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tokio::sync::Mutex;
     use tonic::{Request, Response, Status};
 
     #[rustfmt::skip]
     pub struct MockDummyClient {
+        pub create_entity_req: Mutex<Option<CreateEntityReq>>,
+        pub create_entity_resp: Mutex<VecDeque<Result<CreateEntityResp, Status>>>,
+        create_entity_call_count: AtomicUsize,
         pub get_entity_req: Mutex<Option<GetEntityReq>>,
-        pub get_entity_resp: Mutex<Option<Result<GetEntityResp, Status>>>,
+        pub get_entity_resp: Mutex<VecDeque<Result<GetEntityResp, Status>>>,
+        get_entity_call_count: AtomicUsize,
+        pub list_entities_req: Mutex<Option<ListEntitiesReq>>,
+        pub list_entities_resp: Mutex<VecDeque<Result<ListEntitiesResp, Status>>>,
+        list_entities_call_count: AtomicUsize,
     }
 
     impl Default for MockDummyClient {
         fn default() -> Self {
             Self {
+                create_entity_req: Mutex::new(None),
+                create_entity_resp: Mutex::new(VecDeque::new()),
+                create_entity_call_count: AtomicUsize::new(0),
                 get_entity_req: Mutex::new(None),
-                get_entity_resp: Mutex::new(None),
+                get_entity_resp: Mutex::new(VecDeque::new()),
+                get_entity_call_count: AtomicUsize::new(0),
+                list_entities_req: Mutex::new(None),
+                list_entities_resp: Mutex::new(VecDeque::new()),
+                list_entities_call_count: AtomicUsize::new(0),
             }
         }
     }
 
+    #[rustfmt::skip]
+    impl MockDummyClient {
+        /// Queues the result of the next unanswered `create_entity` call.
+        pub fn push_create_entity_resp(&mut self, resp: Result<CreateEntityResp, Status>) -> &mut Self {
+            self.create_entity_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `create_entity` was called.
+        pub fn create_entity_calls(&self) -> usize {
+            self.create_entity_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `get_entity` call.
+        pub fn push_get_entity_resp(&mut self, resp: Result<GetEntityResp, Status>) -> &mut Self {
+            self.get_entity_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `get_entity` was called.
+        pub fn get_entity_calls(&self) -> usize {
+            self.get_entity_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `list_entities` call.
+        pub fn push_list_entities_resp(&mut self, resp: Result<ListEntitiesResp, Status>) -> &mut Self {
+            self.list_entities_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `list_entities` was called.
+        pub fn list_entities_calls(&self) -> usize {
+            self.list_entities_call_count.load(Ordering::SeqCst)
+        }
+    }
+
     #[rustfmt::skip]
     #[async_trait]
     impl IDummyClient for MockDummyClient {
+        async fn create_entity(&self, req: Request<CreateEntityReq>) -> Result<Response<CreateEntityResp>, Status> {
+            self.create_entity_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.create_entity_req.lock().await = Some(req.into_inner());
+            self.create_entity_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("create_entity called more times than configured"))
+                .map(Response::new)
+        }
         async fn get_entity(&self, req: Request<GetEntityReq>) -> Result<Response<GetEntityResp>, Status> {
+            self.get_entity_call_count.fetch_add(1, Ordering::SeqCst);
             *self.get_entity_req.lock().await = Some(req.into_inner());
-            self.get_entity_resp.lock().await.take().unwrap().map(Response::new)
+            self.get_entity_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("get_entity called more times than configured"))
+                .map(Response::new)
+        }
+        async fn list_entities(&self, req: Request<ListEntitiesReq>) -> Result<Response<ListEntitiesResp>, Status> {
+            self.list_entities_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.list_entities_req.lock().await = Some(req.into_inner());
+            self.list_entities_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("list_entities called more times than configured"))
+                .map(Response::new)
         }
     }
 }
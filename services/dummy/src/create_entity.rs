@@ -0,0 +1,104 @@
+use crate::error::Error;
+use crate::utils::authenticated_user_id;
+
+use crate::{
+    db::DBClient,
+    handler::Handler,
+    proto::{CreateEntityReq, CreateEntityResp, Entity},
+};
+use common::UuidGenerator;
+use tonic::{Request, Response, Status};
+
+impl<D, U> Handler<D, U>
+where
+    D: DBClient,
+    U: UuidGenerator,
+{
+    /// Creates an entity for the authenticated user.
+    ///
+    /// # Errors
+    /// - unauthenticated if the request carries no user id
+    /// - invalid argument if the user id is not a uuid
+    /// - internal error if the entity cannot be inserted into the db
+    pub async fn create_entity(
+        &self,
+        req: Request<CreateEntityReq>,
+    ) -> Result<Response<CreateEntityResp>, Status> {
+        let user_id = authenticated_user_id(req.metadata())?;
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        let id = self.uuid.generate();
+        tracing::Span::current().record("entity_id", id.to_string());
+
+        self.db
+            .insert_entity(id, user_id)
+            .await
+            .map_err(Error::InsertEntity)?;
+
+        Ok(Response::new(CreateEntityResp {
+            entity: Some(Entity { id: id.to_string() }),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::mock::MockUuidGenerator;
+    use rstest::rstest;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_authenticated_req, fixture_entity},
+        handler::Handler,
+        proto::{CreateEntityReq, CreateEntityResp},
+    };
+
+    #[rstest]
+    #[case::happy_path(
+        fixture_authenticated_req(CreateEntityReq {}),
+        vec![Ok(())],
+        Ok(CreateEntityResp { entity: Some(fixture_entity(|_| {})) })
+    )]
+    #[case::missing_user_id(
+        Request::new(CreateEntityReq {}),
+        vec![],
+        Err(Code::Unauthenticated)
+    )]
+    #[case::invalid_user_id(
+        {
+            let mut req = Request::new(CreateEntityReq {});
+            req.metadata_mut().insert("x-user-id", "not-uuid".parse().unwrap());
+            req
+        },
+        vec![],
+        Err(Code::InvalidArgument)
+    )]
+    #[case::internal_error(
+        fixture_authenticated_req(CreateEntityReq {}),
+        vec![Err(DBError::Unknown)],
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_create_entity(
+        #[case] req: Request<CreateEntityReq>,
+        #[case] db_results: Vec<Result<(), DBError>>,
+        #[case] want: Result<CreateEntityResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_insert_entity(db_results);
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::new(),
+        };
+
+        // when
+        let got = service.create_entity(req).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
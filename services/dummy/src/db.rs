@@ -13,6 +13,8 @@ pub trait DBClient: Send + Sync + 'static {
     async fn insert_entity(&self, id: Uuid, user_id: Uuid) -> Result<(), DBError>;
 
     async fn get_entity(&self, id: Uuid, user_id: Uuid) -> Result<Entity, DBError>;
+
+    async fn list_entities(&self, user_id: Uuid) -> Result<Vec<Entity>, DBError>;
 }
 
 #[derive(Clone, Debug)]
@@ -62,6 +64,22 @@ impl DBClient for PostgresDBClient {
 
         Ok(Entity::try_from(row)?)
     }
+
+    /// Returns the entities of a user, oldest first.
+    ///
+    /// # Errors
+    /// - if the database connection cannot be established
+    /// - if the database query fails
+    async fn list_entities(&self, user_id: Uuid) -> Result<Vec<Entity>, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare("SELECT id FROM entities WHERE user_id = $1 ORDER BY created_at, id")
+            .await?;
+        let rows = client.query(&stmt, &[&user_id]).await?;
+
+        rows.into_iter().map(Entity::try_from).collect()
+    }
 }
 
 impl TryFrom<Row> for Entity {
@@ -99,7 +117,7 @@ pub mod test {
 
         for entity in given_entity {
             db_client
-                .insert_entity(entity.id.clone(), entity.user_id.clone())
+                .insert_entity(entity.id, entity.user_id)
                 .await
                 .expect("failed to insert entity");
         }
@@ -140,4 +158,29 @@ pub mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_list_entities() {
+        let other_user = Uuid::from_u128(2);
+        let given_entities = vec![
+            fixture_db_entity(|e| e.id = Uuid::from_u128(10)),
+            fixture_db_entity(|e| {
+                e.id = Uuid::from_u128(11);
+                e.user_id = other_user;
+            }),
+        ];
+
+        run_db_test(given_entities, |db_client| async move {
+            let got = db_client
+                .list_entities(fixture_uuid())
+                .await
+                .expect("failed to list entities");
+
+            assert_eq!(
+                got,
+                vec![fixture_entity(|e| e.id = Uuid::from_u128(10).to_string())]
+            );
+        })
+        .await;
+    }
 }
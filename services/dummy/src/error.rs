@@ -6,6 +6,9 @@ use tonic::Status;
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum Error {
+    #[error("missing user id in request metadata")]
+    Unauthenticated,
+
     #[error("missing entity id")]
     MissingEntityId,
 
@@ -15,16 +18,23 @@ pub enum Error {
     #[error("entity not found: {0}")]
     EntityNotFound(String),
 
+    #[error("insert entity error: {0}")]
+    InsertEntity(DBError),
+
     #[error("get entity error: {0}")]
     GetEntity(DBError),
+
+    #[error("list entities error: {0}")]
+    ListEntities(DBError),
 }
 
 impl From<Error> for Status {
     fn from(err: Error) -> Self {
         let code = match err {
+            Error::Unauthenticated => Code::Unauthenticated,
             Error::MissingEntityId | Error::InvalidEntityId(_) => Code::InvalidArgument,
             Error::EntityNotFound(_) => Code::NotFound,
-            Error::GetEntity(_) => Code::Internal,
+            Error::InsertEntity(_) | Error::GetEntity(_) | Error::ListEntities(_) => Code::Internal,
         };
         Status::new(code, err.to_string())
     }
@@ -1,5 +1,7 @@
 #![cfg(test)]
 
+use setup::session::SessionState;
+use tonic::Request;
 use uuid::Uuid;
 
 use crate::proto::{Entity, GetEntityReq, GetEntityResp};
@@ -14,12 +16,18 @@ where
 {
     let mut entity = GetEntityReq {
         id: fixture_uuid().to_string(),
-        user_id: fixture_uuid().to_string(),
     };
     func(&mut entity);
     entity
 }
 
+/// Wraps a message in a request of the fixture user.
+pub fn fixture_authenticated_req<T>(message: T) -> Request<T> {
+    let mut req = Request::new(message);
+    SessionState::new(fixture_uuid().to_string()).insert_into(req.metadata_mut());
+    req
+}
+
 pub fn fixture_get_entity_resp<F>(mut func: F) -> GetEntityResp
 where
     F: FnMut(&mut GetEntityResp),
@@ -1,5 +1,5 @@
 use crate::error::{DBError, Error};
-use crate::utils::validate_entity_id;
+use crate::utils::{authenticated_user_id, validate_entity_id};
 
 use crate::{
     db::DBClient,
@@ -7,7 +7,6 @@ use crate::{
     proto::{GetEntityReq, GetEntityResp},
 };
 use common::UuidGenerator;
-use setup::validate_user_id;
 use tonic::{Request, Response, Status};
 
 impl<D, U> Handler<D, U>
@@ -15,17 +14,21 @@ where
     D: DBClient,
     U: UuidGenerator,
 {
-    /// Gets an entity by identifier.
+    /// Gets an entity of the authenticated user by identifier.
     ///
     /// # Errors
-    /// - ?
+    /// - unauthenticated if the request carries no user id
+    /// - invalid argument if the entity id is missing or invalid
+    /// - not found if the user has no entity with the id
+    /// - internal error if the entity cannot be read from the db
     pub async fn get_entity(
         &self,
         req: Request<GetEntityReq>,
     ) -> Result<Response<GetEntityResp>, Status> {
-        let req = req.into_inner();
+        let user_id = authenticated_user_id(req.metadata())?;
+        tracing::Span::current().record("user_id", user_id.to_string());
 
-        let user_id = validate_user_id(&req.user_id)?;
+        let req = req.into_inner();
 
         let id = validate_entity_id(&req.id)?;
 
@@ -43,13 +46,15 @@ where
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
-    use tokio::sync::Mutex;
     use tonic::{Code, Request};
 
     use crate::{
         db::test::MockDBClient,
         error::DBError,
-        fixture::{fixture_entity, fixture_get_entity_req, fixture_get_entity_resp},
+        fixture::{
+            fixture_authenticated_req, fixture_entity, fixture_get_entity_req,
+            fixture_get_entity_resp,
+        },
         handler::Handler,
         proto::{Entity, GetEntityReq, GetEntityResp},
     };
@@ -65,11 +70,6 @@ mod tests {
         Ok(fixture_entity(|_| {})),
         Err(Code::InvalidArgument)
     )]
-    #[case::missing_user_id(
-        fixture_get_entity_req(|v| { v.user_id = String::new(); }),
-        Ok(fixture_entity(|_| {})),
-        Err(Code::InvalidArgument)
-    )]
     #[case::not_found(
         fixture_get_entity_req(|_| {}),
         Err(DBError::NotFound),
@@ -89,19 +89,47 @@ mod tests {
         // given
         use common::mock::MockUuidGenerator;
         use testutils::assert_response;
-        let db = MockDBClient {
-            get_entity: Mutex::new(Some(db_result)),
-            ..Default::default()
-        };
+        let mut db = MockDBClient::default();
+        db.expect_get_entity([db_result]);
         let service = Handler {
             db,
             uuid: MockUuidGenerator::default(),
         };
 
         // when
-        let got = service.get_entity(Request::new(req)).await;
+        let got = service.get_entity(fixture_authenticated_req(req)).await;
 
         // then
         assert_response(got, want);
     }
+
+    #[rstest]
+    #[case::missing_user_id(Request::new(fixture_get_entity_req(|_| {})), Code::Unauthenticated)]
+    #[case::invalid_user_id(
+        {
+            let mut req = Request::new(fixture_get_entity_req(|_| {}));
+            req.metadata_mut().insert("x-user-id", "not-uuid".parse().unwrap());
+            req
+        },
+        Code::InvalidArgument
+    )]
+    #[tokio::test]
+    async fn test_get_entity_unauthenticated(
+        #[case] req: Request<GetEntityReq>,
+        #[case] want: Code,
+    ) {
+        // given
+        use common::mock::MockUuidGenerator;
+        let service = Handler {
+            db: MockDBClient::default(),
+            uuid: MockUuidGenerator::default(),
+        };
+
+        // when
+        let got = service.get_entity(req).await;
+
+        // then
+        assert_eq!(got.unwrap_err().code(), want);
+        assert_eq!(service.db.get_entity_calls(), 0);
+    }
 }
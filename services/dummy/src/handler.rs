@@ -1,6 +1,9 @@
 use crate::{
     db::DBClient,
-    proto::{GetEntityReq, GetEntityResp, dummy_service_server::DummyService},
+    proto::{
+        CreateEntityReq, CreateEntityResp, GetEntityReq, GetEntityResp, ListEntitiesReq,
+        ListEntitiesResp, dummy_service_server::DummyService,
+    },
 };
 use common::UuidGenerator;
 use tonic::{Request, Response, Status};
@@ -9,7 +12,6 @@ use tracing::instrument;
 #[derive(Clone)]
 pub struct Handler<D, U> {
     pub db: D,
-    #[allow(dead_code)]
     pub uuid: U,
 }
 
@@ -19,6 +21,14 @@ where
     D: DBClient,
     U: UuidGenerator,
 {
+    #[instrument(skip_all, fields(user_id, entity_id), err)]
+    async fn create_entity(
+        &self,
+        req: Request<CreateEntityReq>,
+    ) -> Result<Response<CreateEntityResp>, Status> {
+        self.create_entity(req).await
+    }
+
     #[instrument(skip_all, fields(user_id), err)]
     async fn get_entity(
         &self,
@@ -26,4 +36,12 @@ where
     ) -> Result<Response<GetEntityResp>, Status> {
         self.get_entity(req).await
     }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn list_entities(
+        &self,
+        req: Request<ListEntitiesReq>,
+    ) -> Result<Response<ListEntitiesResp>, Status> {
+        self.list_entities(req).await
+    }
 }
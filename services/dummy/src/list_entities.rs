@@ -0,0 +1,90 @@
+use crate::error::Error;
+use crate::utils::authenticated_user_id;
+
+use crate::{
+    db::DBClient,
+    handler::Handler,
+    proto::{ListEntitiesReq, ListEntitiesResp},
+};
+use common::UuidGenerator;
+use tonic::{Request, Response, Status};
+
+impl<D, U> Handler<D, U>
+where
+    D: DBClient,
+    U: UuidGenerator,
+{
+    /// Lists the entities of the authenticated user.
+    ///
+    /// # Errors
+    /// - unauthenticated if the request carries no user id
+    /// - internal error if the entities cannot be read from the db
+    pub async fn list_entities(
+        &self,
+        req: Request<ListEntitiesReq>,
+    ) -> Result<Response<ListEntitiesResp>, Status> {
+        let user_id = authenticated_user_id(req.metadata())?;
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        let entities = self
+            .db
+            .list_entities(user_id)
+            .await
+            .map_err(Error::ListEntities)?;
+
+        Ok(Response::new(ListEntitiesResp { entities }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::mock::MockUuidGenerator;
+    use rstest::rstest;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_authenticated_req, fixture_entity},
+        handler::Handler,
+        proto::{Entity, ListEntitiesReq, ListEntitiesResp},
+    };
+
+    #[rstest]
+    #[case::happy_path(
+        fixture_authenticated_req(ListEntitiesReq {}),
+        vec![Ok(vec![fixture_entity(|_| {})])],
+        Ok(ListEntitiesResp { entities: vec![fixture_entity(|_| {})] })
+    )]
+    #[case::missing_user_id(
+        Request::new(ListEntitiesReq {}),
+        vec![],
+        Err(Code::Unauthenticated)
+    )]
+    #[case::internal_error(
+        fixture_authenticated_req(ListEntitiesReq {}),
+        vec![Err(DBError::Unknown)],
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_list_entities(
+        #[case] req: Request<ListEntitiesReq>,
+        #[case] db_results: Vec<Result<Vec<Entity>, DBError>>,
+        #[case] want: Result<ListEntitiesResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_list_entities(db_results);
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+        };
+
+        // when
+        let got = service.list_entities(req).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
@@ -1,7 +1,9 @@
+pub mod create_entity;
 pub mod db;
 pub mod error;
 pub mod get_entity;
 pub mod handler;
+pub mod list_entities;
 #[allow(clippy::all)]
 pub mod proto;
 pub mod utils;
@@ -9,12 +11,17 @@ pub mod utils;
 #[cfg(test)]
 mod fixture;
 
-use crate::{handler::Handler, proto::dummy_service_server::DummyServiceServer};
+use crate::{
+    handler::Handler,
+    proto::dummy_service_server::{self, DummyServiceServer},
+};
 use common::UuidV4Generator;
 use db::PostgresDBClient;
 use dotenv::dotenv;
 use dummy::{GRPC_PORT, SERVICE_NAME};
-use setup::{middleware::TracingGrpcServiceLayer, tracing::init_tracer};
+use setup::{
+    health::grpc_health_service, middleware::TracingGrpcServiceLayer, tracing::init_tracer,
+};
 use std::error::Error;
 
 #[tokio::main]
@@ -27,6 +34,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let pool = database::connect(&pg_cfg)?;
     database::run_migrations!(pool, "./migrations");
 
+    let health_pool = pool.clone();
+    let health = grpc_health_service(dummy_service_server::SERVICE_NAME, move || {
+        database::ping(health_pool.clone())
+    })
+    .await;
+
     let handler = Handler {
         db: PostgresDBClient::new(pool),
         uuid: UuidV4Generator,
@@ -37,7 +50,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     println!("listening on :{GRPC_PORT}");
     let mut server = tonic::transport::Server::builder().layer(TracingGrpcServiceLayer);
-    server.add_service(svc).serve(addr).await.unwrap();
+    server
+        .add_service(health)
+        .add_service(svc)
+        .serve(addr)
+        .await
+        .unwrap();
 
     tracer.shutdown()?;
 
@@ -1,11 +1,19 @@
 // This file is @generated by prost-build.
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct CreateEntityReq {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct CreateEntityResp {
+    /// The created entity.
+    #[prost(message, optional, tag = "1")]
+    pub entity: ::core::option::Option<Entity>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct GetEntityReq {
     #[prost(string, tag = "1")]
     pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub user_id: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -14,6 +22,16 @@ pub struct GetEntityResp {
     pub entity: ::core::option::Option<Entity>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ListEntitiesReq {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListEntitiesResp {
+    /// The entities of the user, oldest first.
+    #[prost(message, repeated, tag = "1")]
+    pub entities: ::prost::alloc::vec::Vec<Entity>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct Entity {
     #[prost(string, tag = "1")]
@@ -30,6 +48,8 @@ pub mod dummy_service_client {
     )]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    /// Service for managing the entities of the authenticated user, whose id
+    /// is read from the x-user-id metadata.
     #[derive(Debug, Clone)]
     pub struct DummyServiceClient<T> {
         inner: tonic::client::Grpc<T>,
@@ -110,6 +130,32 @@ pub mod dummy_service_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
+        /// Creates an entity for the user.
+        pub async fn create_entity(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateEntityReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateEntityResp>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/dummy.DummyService/CreateEntity",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("dummy.DummyService", "CreateEntity"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Gets an entity of the user.
         pub async fn get_entity(
             &mut self,
             request: impl tonic::IntoRequest<super::GetEntityReq>,
@@ -131,6 +177,31 @@ pub mod dummy_service_client {
                 .insert(GrpcMethod::new("dummy.DummyService", "GetEntity"));
             self.inner.unary(req, path, codec).await
         }
+        /// Lists the entities of the user.
+        pub async fn list_entities(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListEntitiesReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListEntitiesResp>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/dummy.DummyService/ListEntities",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("dummy.DummyService", "ListEntities"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -146,11 +217,30 @@ pub mod dummy_service_server {
     /// Generated trait containing gRPC methods that should be implemented for use with DummyServiceServer.
     #[async_trait]
     pub trait DummyService: std::marker::Send + std::marker::Sync + 'static {
+        /// Creates an entity for the user.
+        async fn create_entity(
+            &self,
+            request: tonic::Request<super::CreateEntityReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateEntityResp>,
+            tonic::Status,
+        >;
+        /// Gets an entity of the user.
         async fn get_entity(
             &self,
             request: tonic::Request<super::GetEntityReq>,
         ) -> std::result::Result<tonic::Response<super::GetEntityResp>, tonic::Status>;
+        /// Lists the entities of the user.
+        async fn list_entities(
+            &self,
+            request: tonic::Request<super::ListEntitiesReq>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListEntitiesResp>,
+            tonic::Status,
+        >;
     }
+    /// Service for managing the entities of the authenticated user, whose id
+    /// is read from the x-user-id metadata.
     #[derive(Debug)]
     pub struct DummyServiceServer<T> {
         inner: Arc<T>,
@@ -227,6 +317,51 @@ pub mod dummy_service_server {
         }
         fn call(&mut self, req: http::Request<B>) -> Self::Future {
             match req.uri().path() {
+                "/dummy.DummyService/CreateEntity" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateEntitySvc<T: DummyService>(pub Arc<T>);
+                    impl<
+                        T: DummyService,
+                    > tonic::server::UnaryService<super::CreateEntityReq>
+                    for CreateEntitySvc<T> {
+                        type Response = super::CreateEntityResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateEntityReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as DummyService>::create_entity(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = CreateEntitySvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/dummy.DummyService/GetEntity" => {
                     #[allow(non_camel_case_types)]
                     struct GetEntitySvc<T: DummyService>(pub Arc<T>);
@@ -272,6 +407,51 @@ pub mod dummy_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/dummy.DummyService/ListEntities" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListEntitiesSvc<T: DummyService>(pub Arc<T>);
+                    impl<
+                        T: DummyService,
+                    > tonic::server::UnaryService<super::ListEntitiesReq>
+                    for ListEntitiesSvc<T> {
+                        type Response = super::ListEntitiesResp;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListEntitiesReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as DummyService>::list_entities(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListEntitiesSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
-use tonic::Status;
+use setup::{session::SessionState, validate_user_id};
+use tonic::{Status, metadata::MetadataMap};
 use uuid::Uuid;
 
 use crate::error::Error;
@@ -18,3 +19,13 @@ pub fn validate_entity_id(entity_id: &str) -> Result<Uuid, Status> {
 
     Ok(entity_uuid)
 }
+
+/// Returns the id of the authenticated user from the request metadata.
+///
+/// # Errors
+/// - unauthenticated if the metadata carries no user id
+/// - invalid argument if the user id is not a uuid
+pub fn authenticated_user_id(metadata: &MetadataMap) -> Result<Uuid, Status> {
+    let session = SessionState::from_metadata(metadata).ok_or(Error::Unauthenticated)?;
+    Ok(validate_user_id(&session.user_id)?)
+}
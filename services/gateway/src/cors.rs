@@ -0,0 +1,89 @@
+//! Cross-origin configuration of the gateway.
+use axum::http::{
+    HeaderValue, Method, Uri,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
+use tower_http::cors::CorsLayer;
+
+/// The origin of the local SvelteKit dev server.
+const DEFAULT_ALLOWED_ORIGIN: &str = "http://localhost:5173";
+
+/// Builds the [`CorsLayer`] for the origins in `CORS_ALLOWED_ORIGINS`.
+pub(crate) fn cors_layer_from_env() -> Result<CorsLayer, Box<dyn std::error::Error>> {
+    let origins = std::env::var("CORS_ALLOWED_ORIGINS")
+        .unwrap_or_else(|_| String::from(DEFAULT_ALLOWED_ORIGIN));
+    Ok(CorsLayer::new()
+        .allow_origin(parse_allowed_origins(&origins)?)
+        .allow_credentials(true)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers(vec![AUTHORIZATION, CONTENT_TYPE]))
+}
+
+/// Parses a comma separated list of origins such as
+/// `https://example.com,https://staging.example.com`.
+///
+/// Each origin must be an http(s) scheme and host with an optional port,
+/// as browsers send it in the `Origin` header. Wildcards are rejected,
+/// since they cannot be combined with credentials.
+fn parse_allowed_origins(value: &str) -> Result<Vec<HeaderValue>, String> {
+    let origins = value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(parse_origin)
+        .collect::<Result<Vec<_>, _>>()?;
+    if origins.is_empty() {
+        return Err(String::from("CORS_ALLOWED_ORIGINS is empty"));
+    }
+    Ok(origins)
+}
+
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let invalid = || format!("invalid cors origin: {origin}");
+    let uri: Uri = origin.parse().map_err(|_| invalid())?;
+    let has_http_scheme = matches!(uri.scheme_str(), Some("http" | "https"));
+    let is_bare_origin = uri
+        .authority()
+        .is_some_and(|authority| origin.ends_with(authority.as_str()));
+    if !has_http_scheme || !is_bare_origin {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(origin).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::single("http://localhost:5173", Ok(vec!["http://localhost:5173"]))]
+    #[case::multiple(
+        "https://example.com, https://staging.example.com",
+        Ok(vec!["https://example.com", "https://staging.example.com"])
+    )]
+    #[case::trailing_comma("https://example.com,", Ok(vec!["https://example.com"]))]
+    #[case::empty("", Err(()))]
+    #[case::missing_scheme("example.com", Err(()))]
+    #[case::other_scheme("ftp://example.com", Err(()))]
+    #[case::with_path("https://example.com/app", Err(()))]
+    #[case::trailing_slash("https://example.com/", Err(()))]
+    #[case::wildcard("*", Err(()))]
+    #[case::one_malformed("https://example.com,not an origin", Err(()))]
+    fn test_parse_allowed_origins(#[case] value: &str, #[case] want: Result<Vec<&str>, ()>) {
+        // when
+        let got = parse_allowed_origins(value);
+
+        // then
+        let got = got
+            .map(|origins| {
+                origins
+                    .iter()
+                    .map(|o| o.to_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .map_err(|_| ());
+        let want = want.map(|origins| origins.into_iter().map(String::from).collect());
+        assert_eq!(got, want);
+    }
+}
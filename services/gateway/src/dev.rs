@@ -0,0 +1,83 @@
+//! Endpoints for local development and end-to-end tests.
+//!
+//! They are only compiled with the `dev-auth` feature, which cannot be
+//! combined with release builds.
+use crate::handler::Handler;
+use axum::Router;
+
+#[cfg(all(feature = "dev-auth", not(debug_assertions)))]
+compile_error!("the `dev-auth` feature must not be enabled in release builds");
+
+#[cfg(feature = "dev-auth")]
+const DEV_LOGIN_PATH: &str = "/dev/login";
+
+/// Returns the dev routes, which are empty without the `dev-auth` feature.
+pub(crate) fn dev_routes() -> Router<Handler> {
+    let router = Router::new();
+    #[cfg(feature = "dev-auth")]
+    let router = router.route(DEV_LOGIN_PATH, axum::routing::post(login::dev_login));
+    router
+}
+
+/// Returns the dev routes that do not require authentication.
+pub(crate) fn no_auth_endpoints() -> Vec<String> {
+    #[cfg(feature = "dev-auth")]
+    return vec![String::from(DEV_LOGIN_PATH)];
+    #[cfg(not(feature = "dev-auth"))]
+    return Vec::new();
+}
+
+#[cfg(feature = "dev-auth")]
+mod login {
+    use crate::error::ApiError;
+    use crate::handler::Handler;
+    use crate::utils::session_token_cookie;
+    use auth::client::IAuthClient;
+    use auth::proto::CreateSessionReq;
+    use axum::{Json, body::Body, extract::State, http::StatusCode, response::Response};
+    use axum_macros::debug_handler;
+    use serde::Deserialize;
+    use setup::cookie::ResponseCookies;
+    use tonic::Request;
+    use tracing::instrument;
+
+    #[derive(Debug, Deserialize)]
+    pub struct DevLoginReq {
+        user_id: String,
+    }
+
+    /// Creates a session for the given user without going through oauth.
+    #[debug_handler]
+    #[instrument(skip(h), err)]
+    pub async fn dev_login(
+        State(h): State<Handler>,
+        Json(req): Json<DevLoginReq>,
+    ) -> Result<Response, ApiError> {
+        let req = Request::new(CreateSessionReq {
+            user_id: req.user_id,
+        });
+        let session = h.auth_client.create_session(req).await?.into_inner();
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .with_cookie(session_token_cookie(session, &h.cookie_config))
+            .body(Body::empty())?;
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dev_routes_only_with_dev_auth() {
+        // when
+        let router = dev_routes();
+
+        // then
+        assert_eq!(router.has_routes(), cfg!(feature = "dev-auth"));
+        assert_eq!(no_auth_endpoints().is_empty(), !cfg!(feature = "dev-auth"));
+    }
+}
@@ -2,15 +2,19 @@ use axum::http;
 use axum::response::{IntoResponse, Response};
 use axum::{Json, http::StatusCode};
 use serde_json::json;
-use tonic::Status;
+use setup::metrics::OAuthOutcome;
+use tonic::{Code, Status};
 
 use crate::utils::grpc_to_http_status;
 
 /// Error for api endpoints.
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
-    #[error("unauthenticated")]
-    Unauthenticated,
+    #[error("invalid return_to: {0}")]
+    InvalidReturnTo(String),
+
+    #[error("unsupported oauth provider: {0}")]
+    UnsupportedProvider(String),
 
     #[error("request failed: {0}")]
     Request(#[from] Status),
@@ -18,6 +22,9 @@ pub enum ApiError {
     #[error("failed to serialize response: {0}")]
     SerializeResponse(#[from] serde_json::Error),
 
+    #[error("failed to serialize query: {0}")]
+    SerializeQuery(#[from] serde_urlencoded::ser::Error),
+
     #[error("failed to build response")]
     BuildResponse(#[from] http::Error),
 }
@@ -25,13 +32,19 @@ pub enum ApiError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
-            Self::Unauthenticated => (StatusCode::UNAUTHORIZED, "unauthenticated".to_string()),
-            Self::Request(e) => (grpc_to_http_status(e.code()), Self::Request(e).to_string()),
+            Self::InvalidReturnTo(e) => (
+                StatusCode::BAD_REQUEST,
+                Self::InvalidReturnTo(e).to_string(),
+            ),
+            Self::UnsupportedProvider(e) => (
+                StatusCode::BAD_REQUEST,
+                Self::UnsupportedProvider(e).to_string(),
+            ),
+            Self::Request(e) => (grpc_to_http_status(e.code()), e.message().to_string()),
             internal => (StatusCode::INTERNAL_SERVER_ERROR, internal.to_string()),
         };
 
-        let body = Json(json!({ "error": error_message }));
-        (status, body).into_response()
+        error_response(status, error_message)
     }
 }
 
@@ -40,10 +53,20 @@ impl IntoResponse for ApiError {
 pub enum OAuthError {
     #[error("gRPC request failed: {0}")]
     RequestError(#[from] Status),
+    #[error("failed to exchange authorization code: {0}")]
+    TokenExchange(Status),
     #[error("state mismatch in oauth flow")]
     StateMismatch,
+    #[error("oauth state expired")]
+    StateExpired,
+    #[error("oauth provider denied access: {0}")]
+    ProviderDenied(String),
     #[error("missing cookie")]
     MissingCookie(&'static str),
+    #[error("oauth account is already linked to another user")]
+    AccountAlreadyLinked,
+    #[error("failed to serialize response: {0}")]
+    SerializeResponse(#[from] serde_json::Error),
     #[error("failed to build response")]
     BuildResponse(#[from] http::Error),
 }
@@ -51,15 +74,121 @@ pub enum OAuthError {
 impl IntoResponse for OAuthError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
-            Self::RequestError(e) => (
+            Self::RequestError(e) => (grpc_to_http_status(e.code()), e.message().to_string()),
+            Self::TokenExchange(e) => (
                 grpc_to_http_status(e.code()),
-                Self::RequestError(e).to_string(),
+                Self::TokenExchange(e).to_string(),
             ),
             Self::StateMismatch => (StatusCode::UNAUTHORIZED, Self::StateMismatch.to_string()),
+            Self::StateExpired => (StatusCode::UNAUTHORIZED, Self::StateExpired.to_string()),
+            Self::ProviderDenied(e) => (StatusCode::FORBIDDEN, Self::ProviderDenied(e).to_string()),
+            Self::AccountAlreadyLinked => {
+                (StatusCode::CONFLICT, Self::AccountAlreadyLinked.to_string())
+            }
+            Self::MissingCookie(e) => {
+                (StatusCode::UNAUTHORIZED, Self::MissingCookie(e).to_string())
+            }
             internal => (StatusCode::INTERNAL_SERVER_ERROR, internal.to_string()),
         };
 
-        let body = Json(json!({ "error": error_message }));
-        (status, body).into_response()
+        error_response(status, error_message)
+    }
+}
+
+/// Returns a JSON error body such as
+/// `{"error":"not found","message":"user not found"}`, where `error` is
+/// the reason phrase of the status. Failed requests to other services
+/// carry the message of their status.
+fn error_response(status: StatusCode, message: String) -> Response {
+    let error = status
+        .canonical_reason()
+        .unwrap_or("unknown error")
+        .to_lowercase();
+    let body = Json(json!({ "error": error, "message": message }));
+    (status, body).into_response()
+}
+
+impl OAuthError {
+    /// Returns the outcome under which the error is counted.
+    pub(crate) fn outcome(&self) -> OAuthOutcome {
+        match self {
+            Self::ProviderDenied(_) => OAuthOutcome::ProviderDenied,
+            Self::MissingCookie(_) => OAuthOutcome::MissingCookie,
+            Self::StateMismatch => OAuthOutcome::StateMismatch,
+            Self::StateExpired => OAuthOutcome::StateExpired,
+            Self::TokenExchange(e) if e.code() == Code::PermissionDenied => {
+                OAuthOutcome::EmailDomainNotAllowed
+            }
+            Self::TokenExchange(_) => OAuthOutcome::TokenExchangeFailed,
+            Self::AccountAlreadyLinked
+            | Self::RequestError(_)
+            | Self::SerializeResponse(_)
+            | Self::BuildResponse(_) => OAuthOutcome::Error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::invalid_argument(Code::InvalidArgument, StatusCode::BAD_REQUEST)]
+    #[case::unauthenticated(Code::Unauthenticated, StatusCode::UNAUTHORIZED)]
+    #[case::permission_denied(Code::PermissionDenied, StatusCode::FORBIDDEN)]
+    #[case::not_found(Code::NotFound, StatusCode::NOT_FOUND)]
+    #[case::already_exists(Code::AlreadyExists, StatusCode::CONFLICT)]
+    #[case::resource_exhausted(Code::ResourceExhausted, StatusCode::TOO_MANY_REQUESTS)]
+    #[case::unavailable(Code::Unavailable, StatusCode::SERVICE_UNAVAILABLE)]
+    #[case::internal(Code::Internal, StatusCode::INTERNAL_SERVER_ERROR)]
+    #[case::unknown(Code::Unknown, StatusCode::INTERNAL_SERVER_ERROR)]
+    #[case::data_loss(Code::DataLoss, StatusCode::INTERNAL_SERVER_ERROR)]
+    fn test_request_error_status(#[case] code: Code, #[case] want: StatusCode) {
+        // given
+        let api_err = ApiError::Request(Status::new(code, "failed"));
+        let oauth_err = OAuthError::RequestError(Status::new(code, "failed"));
+
+        // when
+        let api_resp = api_err.into_response();
+        let oauth_resp = oauth_err.into_response();
+
+        // then
+        assert_eq!(api_resp.status(), want);
+        assert_eq!(oauth_resp.status(), want);
+    }
+
+    #[tokio::test]
+    async fn test_error_body() {
+        // given
+        let err = ApiError::Request(Status::not_found("user not found"));
+
+        // when
+        let resp = err.into_response();
+
+        // then
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "error": "not found",
+                "message": "user not found"
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_oauth_cookie_status() {
+        // given
+        let err = OAuthError::MissingCookie("oauth_state");
+
+        // when
+        let resp = err.into_response();
+
+        // then
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 }
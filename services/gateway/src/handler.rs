@@ -1,33 +1,54 @@
 use crate::error::{ApiError, OAuthError};
-use crate::utils::{OAUTH_CODE_VERIFIER, OAUTH_STATE, OauthCookieJar, parse_provider};
+use crate::oauth_state::OAuthStateSigner;
+use crate::utils::{
+    OAUTH_CODE_VERIFIER, OAUTH_NONCE, OAUTH_RETURN_TO, OAUTH_STATE, OauthCookieJar,
+    SUPPORTED_PROVIDERS, is_valid_return_to, parse_provider, session_token_cookie,
+};
 use auth::client::{AuthClient, IAuthClient};
 use auth::proto::{
-    CreateSessionReq, DeleteSessionReq, HandleOauthCallbackReq, LinkOauthAccountReq,
-    StartOauthLoginReq,
+    CreateSessionReq, DeleteSessionReq, GetActiveSessionsCountReq, GetActiveSessionsCountResp,
+    GetOauthAccountReq, HandleOauthCallbackReq, HandleOauthCallbackResp, LinkOauthAccountReq,
+    OauthProvider, PingReq as AuthPingReq, StartOauthLoginReq, ValidateSessionReq,
 };
 use axum::{
     Extension, Json,
     body::Body,
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode, header::LOCATION},
+    extract::{Form, Path, Query, State},
+    http::{
+        HeaderMap, StatusCode,
+        header::{CONTENT_TYPE, LOCATION},
+    },
     response::Response,
 };
 use axum_macros::debug_handler;
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use setup::constant_time_str_equal;
 use setup::cookie::{
-    ResponseCookies, create_expired_oauth_cookie, create_oauth_cookie, create_session_token_cookie,
-    expire_session_token_cookie, extract_session_token_cookie,
+    Cookie, CookieConfig, ResponseCookies, create_expired_oauth_cookie, create_oauth_cookie,
+    expire_session_token_cookie, extract_cookie_from_headers,
 };
-use setup::session::SessionState;
+use setup::metrics::{OAuthMetrics, OAuthOutcome};
+use setup::session::{SESSION_TOKEN_COOKIE_KEY, SessionState};
 use tonic::{Code, Request, Status};
 use tracing::instrument;
 use user::client::{IUserClient, UserClient};
-use user::proto::{CreateUserReq, GetUserReq, GetUserResp};
+use user::proto::{
+    CreateUserReq, GetUserByEmailReq, GetUserReq, GetUserResp, PingReq as UserPingReq,
+};
 
+/// The state of the gateway routes.
+///
+/// Generic over the service clients so that tests can swap in mocks.
 #[derive(Clone)]
-pub(crate) struct Handler {
-    auth_client: AuthClient,
-    user_client: UserClient,
+pub(crate) struct Handler<A = AuthClient, U = UserClient> {
+    pub(crate) auth_client: A,
+    user_client: U,
+    oauth_metrics: OAuthMetrics,
+    pub(crate) cookie_config: CookieConfig,
+    oauth_state: OAuthStateSigner,
+    /// The origin of the frontend, e.g. `https://example.com`.
+    frontend_url: String,
 }
 
 impl Handler {
@@ -40,18 +61,87 @@ impl Handler {
             .await
             .map_err(|e| format!("UserClient initialization failed: {}", e))?;
 
+        Self::with_clients(auth_client, user_client)
+    }
+}
+
+impl<A: IAuthClient, U: IUserClient> Handler<A, U> {
+    /// Creates a handler with the given clients and the remaining
+    /// configuration read from the environment.
+    pub(crate) fn with_clients(
+        auth_client: A,
+        user_client: U,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         Ok(Self {
             auth_client,
             user_client,
+            oauth_metrics: OAuthMetrics::default(),
+            cookie_config: CookieConfig::from_env(),
+            oauth_state: OAuthStateSigner::from_env()?,
+            frontend_url: frontend_url_from_env(),
         })
     }
 }
 
-/// Gets the current authenticated user.
+/// Reads the origin of the frontend from `FRONTEND_URL`.
+fn frontend_url_from_env() -> String {
+    std::env::var("FRONTEND_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| String::from("http://localhost:5173"))
+}
+
+/// Reports that the gateway is up. Does not require authentication.
+pub async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// The dependencies of the gateway that did not answer a ping.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ReadyzResp {
+    unhealthy: Vec<&'static str>,
+}
+
+/// Reports whether the auth and user services are reachable, with
+/// `503 Service Unavailable` if either is not. Does not require
+/// authentication.
 #[debug_handler]
+#[instrument(skip(h))]
+pub async fn readyz(State(h): State<Handler>) -> (StatusCode, Json<ReadyzResp>) {
+    check_readiness(&h.auth_client, &h.user_client).await
+}
+
+/// Pings the auth and user services concurrently.
+async fn check_readiness(
+    auth_client: &impl IAuthClient,
+    user_client: &impl IUserClient,
+) -> (StatusCode, Json<ReadyzResp>) {
+    let (auth, user) = tokio::join!(
+        auth_client.ping(Request::new(AuthPingReq {})),
+        user_client.ping(Request::new(UserPingReq {})),
+    );
+
+    let mut unhealthy = Vec::new();
+    if let Err(err) = auth {
+        tracing::warn!("auth service is not ready: {err}");
+        unhealthy.push(auth::SERVICE_NAME);
+    }
+    if let Err(err) = user {
+        tracing::warn!("user service is not ready: {err}");
+        unhealthy.push(user::SERVICE_NAME);
+    }
+
+    let status = if unhealthy.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadyzResp { unhealthy }))
+}
+
+/// Gets the current authenticated user.
 #[instrument(skip(h), err)]
-pub async fn get_current_user(
-    State(h): State<Handler>,
+pub async fn get_current_user<A: IAuthClient, U: IUserClient>(
+    State(h): State<Handler<A, U>>,
     Extension(SessionState { user_id }): Extension<SessionState>,
 ) -> Result<Json<GetUserResp>, ApiError> {
     let req = Request::new(GetUserReq { id: user_id });
@@ -59,40 +149,143 @@ pub async fn get_current_user(
     Ok(Json(resp.into_inner()))
 }
 
-/// Logs the current authenticated user out.
+/// Counts the active sessions of the current authenticated user, so
+/// the frontend can tell whether they are logged in elsewhere.
+#[debug_handler]
+#[instrument(skip(h), err)]
+pub async fn get_active_sessions_count(
+    State(h): State<Handler>,
+    Extension(SessionState { user_id }): Extension<SessionState>,
+) -> Result<Json<GetActiveSessionsCountResp>, ApiError> {
+    let req = Request::new(GetActiveSessionsCountReq { user_id });
+    let resp = h.auth_client.get_active_sessions_count(req).await?;
+    Ok(Json(resp.into_inner()))
+}
+
+/// An oauth provider that is linked to the current user.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Connection {
+    /// The provider, e.g. `google`.
+    provider: &'static str,
+    /// The id of the user at the provider.
+    external_user_id: String,
+}
+
+/// The oauth providers that are linked to the current user.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetConnectionsResp {
+    connections: Vec<Connection>,
+}
+
+/// Lists the oauth providers linked to the current authenticated user,
+/// so the frontend can show the connected accounts.
+#[debug_handler]
+#[instrument(skip(h), err)]
+pub async fn get_connections(
+    State(h): State<Handler>,
+    Extension(SessionState { user_id }): Extension<SessionState>,
+) -> Result<Json<GetConnectionsResp>, ApiError> {
+    let connections = list_connections(&h.auth_client, &user_id).await?;
+    Ok(Json(GetConnectionsResp { connections }))
+}
+
+/// Looks up the account of each supported provider. Providers without an
+/// account for the user are omitted.
+async fn list_connections(
+    auth_client: &impl IAuthClient,
+    user_id: &str,
+) -> Result<Vec<Connection>, ApiError> {
+    let mut connections = Vec::new();
+    for (name, provider) in SUPPORTED_PROVIDERS {
+        let req = Request::new(GetOauthAccountReq {
+            user_id: user_id.to_string(),
+            provider: provider.into(),
+        });
+        match auth_client.get_oauth_account(req).await {
+            Ok(resp) => connections.push(Connection {
+                provider: name,
+                external_user_id: resp.into_inner().external_user_id,
+            }),
+            Err(status) if status.code() == Code::NotFound => {}
+            Err(status) => return Err(status.into()),
+        }
+    }
+    Ok(connections)
+}
+
+/// Logs the current user out.
+///
+/// Logging out is idempotent: without a session cookie there is no
+/// session to delete, and the cookie is expired all the same.
 #[debug_handler]
 #[instrument(skip(h), err)]
 pub async fn logout_user(
     State(h): State<Handler>,
     headers: HeaderMap,
 ) -> Result<Response, ApiError> {
-    let Some(cookie_header) = headers.get("cookie") else {
-        return Err(ApiError::Unauthenticated);
-    };
-    let Some(token) = extract_session_token_cookie(cookie_header) else {
-        return Err(ApiError::Unauthenticated);
-    };
+    let token = extract_cookie_from_headers(SESSION_TOKEN_COOKIE_KEY, &headers);
 
-    let req = Request::new(DeleteSessionReq {
-        token: token.clone(),
-    });
-    h.auth_client.delete_session(req).await?;
+    if let Some(token) = token {
+        delete_session(&h, token).await?;
+    }
 
     let response = Response::builder()
         .status(StatusCode::OK)
-        .with_cookie(expire_session_token_cookie())
+        .with_cookie(expire_session_token_cookie(&h.cookie_config))
         .body(Body::empty())?;
 
     Ok(response)
 }
 
+/// Deletes the session of the token. Since `/logout` is not behind the
+/// auth middleware, the token is validated first so that the session
+/// cannot be deleted with its id alone. Invalid or expired tokens have
+/// no session to delete.
+async fn delete_session(h: &Handler, token: String) -> Result<(), ApiError> {
+    let req = Request::new(ValidateSessionReq {
+        token: token.clone(),
+    });
+    match h.auth_client.validate_session(req).await {
+        Ok(_) => {}
+        Err(status) if matches!(status.code(), Code::Unauthenticated | Code::InvalidArgument) => {
+            return Ok(());
+        }
+        Err(status) => return Err(status.into()),
+    }
+
+    let req = Request::new(DeleteSessionReq { token });
+    h.auth_client.delete_session(req).await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartOauthLoginQuery {
+    /// Where the frontend should navigate to after a successful login.
+    return_to: Option<String>,
+}
+
+impl StartOauthLoginQuery {
+    /// Returns the validated `return_to` target, if any.
+    fn return_to(&self) -> Result<Option<&str>, ApiError> {
+        match self.return_to.as_deref() {
+            Some(target) if !is_valid_return_to(target) => {
+                Err(ApiError::InvalidReturnTo(target.to_string()))
+            }
+            target => Ok(target),
+        }
+    }
+}
+
 /// Initiates the OAuth login flow. Does not require authentication.
 #[debug_handler]
 #[instrument(skip(h), err)]
 pub async fn start_oauth_login(
     Path(provider): Path<String>,
     State(h): State<Handler>,
+    Query(query): Query<StartOauthLoginQuery>,
 ) -> Result<Response, ApiError> {
+    let return_to = query.return_to()?.unwrap_or_default();
     let provider = parse_provider(provider);
     let req = Request::new(StartOauthLoginReq {
         provider: provider.into(),
@@ -103,18 +296,49 @@ pub async fn start_oauth_login(
         .status(StatusCode::TEMPORARY_REDIRECT)
         .header(LOCATION, &resp.authorization_url)
         .with_cookies([
-            create_oauth_cookie(OAUTH_STATE, resp.state),
-            create_oauth_cookie(OAUTH_CODE_VERIFIER, resp.code_verifier),
+            create_oauth_cookie(
+                OAUTH_STATE,
+                h.oauth_state.sign(&resp.state, Utc::now()),
+                &h.cookie_config,
+            ),
+            create_oauth_cookie(OAUTH_CODE_VERIFIER, resp.code_verifier, &h.cookie_config),
+            create_oauth_cookie(OAUTH_NONCE, resp.nonce, &h.cookie_config),
+            create_oauth_cookie(OAUTH_RETURN_TO, return_to, &h.cookie_config),
         ])
         .body(Body::empty())?;
 
     Ok(response)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct OauthCallbackQuery {
+    #[serde(default)]
     state: String,
+    #[serde(default)]
     code: String,
+    /// Set by the provider if the user denied consent or the request failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Human readable description accompanying `error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_description: Option<String>,
+}
+
+impl OauthCallbackQuery {
+    /// Returns [`OAuthError::ProviderDenied`] if the provider redirected back with an error.
+    fn check_provider_error(&self) -> Result<(), OAuthError> {
+        let Some(error) = &self.error else {
+            return Ok(());
+        };
+        let description = self.error_description.as_ref().unwrap_or(error);
+        Err(OAuthError::ProviderDenied(description.clone()))
+    }
+}
+
+#[derive(Serialize)]
+pub struct OauthCallbackResp {
+    /// Where the frontend should navigate to, as passed to `start_oauth_login`.
+    return_to: Option<String>,
 }
 
 /// Handles the OAuth callback, creates a session and logs the user in.
@@ -129,21 +353,119 @@ pub async fn handle_oauth_callback(
 ) -> Result<Response, OAuthError> {
     let provider = parse_provider(provider);
 
-    let jar = OauthCookieJar::from_headers(&headers)?;
-    let stored_state = jar.extract(OAUTH_STATE)?;
-    let code_verifier = jar.extract(OAUTH_CODE_VERIFIER)?;
+    let result = oauth_callback(&h, provider, query, &headers).await;
+    let outcome = match &result {
+        Ok(_) => OAuthOutcome::Success,
+        Err(err) => err.outcome(),
+    };
+    h.oauth_metrics.record(provider.as_str_name(), outcome);
+
+    result
+}
+
+/// Forwards an OAuth callback that the provider sent as form POST, as
+/// Apple does, to the frontend callback page as query parameters.
+/// Does not require authentication.
+#[debug_handler]
+#[instrument(skip(h, form), err)]
+pub async fn forward_oauth_form_post(
+    Path(provider): Path<String>,
+    State(h): State<Handler>,
+    Form(form): Form<OauthCallbackQuery>,
+) -> Result<Response, ApiError> {
+    if parse_provider(&provider) == OauthProvider::Unspecified {
+        return Err(ApiError::UnsupportedProvider(provider));
+    }
+
+    let query = serde_urlencoded::to_string(&form)?;
+    let location = format!("{}/login/{provider}/callback?{query}", h.frontend_url);
 
-    if query.state != stored_state {
+    let response = Response::builder()
+        .status(StatusCode::SEE_OTHER)
+        .header(LOCATION, location)
+        .body(Body::empty())?;
+
+    Ok(response)
+}
+
+/// Checks that the state returned by the provider matches the signed
+/// state cookie and that the cookie is not stale.
+fn check_state(
+    h: &Handler,
+    query: &OauthCallbackQuery,
+    jar: &OauthCookieJar,
+    now: DateTime<Utc>,
+) -> Result<(), OAuthError> {
+    let stored_state = h.oauth_state.verify(&jar.extract(OAUTH_STATE)?, now)?;
+    if !constant_time_str_equal(&query.state, &stored_state) {
         return Err(OAuthError::StateMismatch);
     }
+    Ok(())
+}
+
+/// The result of a completed oauth flow.
+struct OauthExchange {
+    account: HandleOauthCallbackResp,
+    /// Where the frontend should navigate to, as passed to `start_oauth_login`.
+    return_to: Option<String>,
+}
+
+/// Verifies the callback of the provider against the oauth cookies and
+/// exchanges the authorization code for the provider account.
+async fn exchange_oauth_code(
+    h: &Handler,
+    provider: OauthProvider,
+    query: OauthCallbackQuery,
+    headers: &HeaderMap,
+) -> Result<OauthExchange, OAuthError> {
+    query.check_provider_error()?;
+
+    let jar = OauthCookieJar::from_headers(headers)?;
+    check_state(h, &query, &jar, Utc::now())?;
+    let code_verifier = jar.extract(OAUTH_CODE_VERIFIER)?;
+    let nonce = jar.extract(OAUTH_NONCE)?;
+    let return_to = jar
+        .extract_optional(OAUTH_RETURN_TO)
+        .filter(|target| is_valid_return_to(target));
 
     let callback_req = Request::new(HandleOauthCallbackReq {
         provider: provider.into(),
         code: query.code,
-        code_verifier: code_verifier.clone(),
+        code_verifier,
+        nonce,
     });
-    let callback_resp = h.auth_client.handle_oauth_callback(callback_req).await?;
-    let callback_data = callback_resp.into_inner();
+    let callback_resp = h
+        .auth_client
+        .handle_oauth_callback(callback_req)
+        .await
+        .map_err(OAuthError::TokenExchange)?;
+
+    Ok(OauthExchange {
+        account: callback_resp.into_inner(),
+        return_to,
+    })
+}
+
+/// Returns the cookies that expire the oauth flow's cookies.
+fn expired_oauth_cookies(config: &CookieConfig) -> [Cookie; 4] {
+    [
+        create_expired_oauth_cookie(OAUTH_STATE, config),
+        create_expired_oauth_cookie(OAUTH_CODE_VERIFIER, config),
+        create_expired_oauth_cookie(OAUTH_NONCE, config),
+        create_expired_oauth_cookie(OAUTH_RETURN_TO, config),
+    ]
+}
+
+async fn oauth_callback(
+    h: &Handler,
+    provider: OauthProvider,
+    query: OauthCallbackQuery,
+    headers: &HeaderMap,
+) -> Result<Response, OAuthError> {
+    let OauthExchange {
+        account: callback_data,
+        return_to,
+    } = exchange_oauth_code(h, provider, query, headers).await?;
 
     let account_id = callback_data.account_id;
     let name = callback_data.external_user_name;
@@ -151,12 +473,7 @@ pub async fn handle_oauth_callback(
 
     let mut user_id = callback_data.user_id;
     if user_id.is_empty() {
-        let req = Request::new(CreateUserReq { name, email });
-        let resp = h.user_client.create_user(req).await?;
-        let user = resp.into_inner().user.ok_or_else(|| {
-            OAuthError::RequestError(Status::new(Code::Internal, "failed to create user"))
-        })?;
-        user_id = user.id;
+        user_id = find_or_create_user(&h.user_client, name, email).await?;
 
         let req = Request::new(LinkOauthAccountReq {
             account_id,
@@ -166,17 +483,563 @@ pub async fn handle_oauth_callback(
     }
 
     let session_req = Request::new(CreateSessionReq { user_id });
-    let session_resp = h.auth_client.create_session(session_req).await?;
-    let session_token = session_resp.into_inner().token;
+    let session = h
+        .auth_client
+        .create_session(session_req)
+        .await?
+        .into_inner();
 
+    let body = serde_json::to_vec(&OauthCallbackResp { return_to })?;
     let response = Response::builder()
         .status(StatusCode::OK)
-        .with_cookies([
-            create_session_token_cookie(session_token),
-            create_expired_oauth_cookie(OAUTH_STATE),
-            create_expired_oauth_cookie(OAUTH_CODE_VERIFIER),
-        ])
-        .body(Body::empty())?;
+        .header(CONTENT_TYPE, "application/json")
+        .with_cookie(session_token_cookie(session, &h.cookie_config))
+        .with_cookies(expired_oauth_cookies(&h.cookie_config))
+        .body(Body::from(body))?;
+
+    Ok(response)
+}
+
+/// Returns the id of the user with the email of a new provider account,
+/// so that signing in with a second provider does not create a duplicate
+/// user, or creates a new user otherwise.
+///
+/// This relies on the providers only returning verified emails.
+async fn find_or_create_user(
+    user_client: &impl IUserClient,
+    name: String,
+    email: String,
+) -> Result<String, OAuthError> {
+    if !email.is_empty() {
+        let req = Request::new(GetUserByEmailReq {
+            email: email.clone(),
+        });
+        match user_client.get_user_by_email(req).await {
+            Ok(resp) => {
+                if let Some(user) = resp.into_inner().user {
+                    return Ok(user.id);
+                }
+            }
+            Err(status) if status.code() == Code::NotFound => {}
+            Err(status) => return Err(status.into()),
+        }
+    }
+
+    let req = Request::new(CreateUserReq { name, email });
+    let resp = user_client.create_user(req).await?;
+    let user = resp.into_inner().user.ok_or_else(|| {
+        OAuthError::RequestError(Status::new(Code::Internal, "failed to create user"))
+    })?;
+    Ok(user.id)
+}
+
+/// Links the provider account of an oauth flow to the current
+/// authenticated user, e.g. to add GitHub to a Google account.
+///
+/// The frontend starts the flow with `/auth/{provider}/login` like a
+/// login and posts the callback query here instead of to
+/// `/auth/{provider}/callback`. No new user or session is created.
+#[debug_handler]
+#[instrument(skip(h, query), err)]
+pub async fn link_oauth_account(
+    Path(provider): Path<String>,
+    State(h): State<Handler>,
+    Extension(SessionState { user_id }): Extension<SessionState>,
+    Query(query): Query<OauthCallbackQuery>,
+    headers: HeaderMap,
+) -> Result<Response, OAuthError> {
+    let provider = parse_provider(provider);
+
+    let OauthExchange { account, return_to } =
+        exchange_oauth_code(&h, provider, query, &headers).await?;
+
+    if needs_link(&account.user_id, &user_id)? {
+        let req = Request::new(LinkOauthAccountReq {
+            account_id: account.account_id,
+            user_id,
+        });
+        let _ = h.auth_client.link_oauth_account(req).await?;
+    }
+
+    let body = serde_json::to_vec(&OauthCallbackResp { return_to })?;
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .with_cookies(expired_oauth_cookies(&h.cookie_config))
+        .body(Body::from(body))?;
 
     Ok(response)
 }
+
+/// Returns whether a provider account that is linked to `linked_user_id`,
+/// which is empty for new accounts, must be linked to `user_id`.
+///
+/// # Errors
+/// - [`OAuthError::AccountAlreadyLinked`] if it belongs to another user
+fn needs_link(linked_user_id: &str, user_id: &str) -> Result<bool, OAuthError> {
+    match linked_user_id {
+        "" => Ok(true),
+        linked if linked == user_id => Ok(false),
+        _ => Err(OAuthError::AccountAlreadyLinked),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auth::client::testutils::MockAuthClient;
+    use axum::http::Uri;
+    use axum::response::IntoResponse;
+    use rstest::rstest;
+    use user::client::testutils::MockUserClient;
+
+    #[rstest]
+    #[case::healthy(Ok(()), Ok(()), StatusCode::OK, vec![])]
+    #[case::auth_unavailable(
+        Err(Status::unavailable("down")),
+        Ok(()),
+        StatusCode::SERVICE_UNAVAILABLE,
+        vec!["auth"]
+    )]
+    #[case::all_unavailable(
+        Err(Status::unavailable("down")),
+        Err(Status::deadline_exceeded("slow")),
+        StatusCode::SERVICE_UNAVAILABLE,
+        vec!["auth", "user"]
+    )]
+    #[tokio::test]
+    async fn test_check_readiness(
+        #[case] auth_ping: Result<(), Status>,
+        #[case] user_ping: Result<(), Status>,
+        #[case] want_status: StatusCode,
+        #[case] want_unhealthy: Vec<&'static str>,
+    ) {
+        // given
+        let mut auth_client = MockAuthClient::default();
+        auth_client.push_ping_resp(auth_ping.map(|()| auth::proto::PingResp {}));
+        let mut user_client = MockUserClient::default();
+        user_client.push_ping_resp(user_ping.map(|()| user::proto::PingResp {}));
+
+        // when
+        let (status, Json(body)) = check_readiness(&auth_client, &user_client).await;
+
+        // then
+        assert_eq!(auth_client.ping_calls(), 1);
+        assert_eq!(user_client.ping_calls(), 1);
+        assert_eq!(status, want_status);
+        assert_eq!(
+            body,
+            ReadyzResp {
+                unhealthy: want_unhealthy
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_readiness_recovers() {
+        // given
+        let mut auth_client = MockAuthClient::default();
+        auth_client
+            .push_ping_resp(Err(Status::unavailable("down")))
+            .push_ping_resp(Ok(auth::proto::PingResp {}));
+        let mut user_client = MockUserClient::default();
+        user_client
+            .push_ping_resp(Ok(user::proto::PingResp {}))
+            .push_ping_resp(Ok(user::proto::PingResp {}));
+
+        // when
+        let (first, _) = check_readiness(&auth_client, &user_client).await;
+        let (second, _) = check_readiness(&auth_client, &user_client).await;
+
+        // then
+        assert_eq!(first, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(second, StatusCode::OK);
+        assert_eq!(auth_client.ping_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_connections() {
+        // given
+        let mut auth_client = MockAuthClient::default();
+        auth_client
+            .push_get_oauth_account_resp(Ok(auth::proto::GetOauthAccountResp {
+                external_user_id: String::from("google-id"),
+            }))
+            .push_get_oauth_account_resp(Err(Status::not_found("no account")))
+            .push_get_oauth_account_resp(Ok(auth::proto::GetOauthAccountResp {
+                external_user_id: String::from("apple-id"),
+            }));
+
+        // when
+        let got = list_connections(&auth_client, "user-id").await;
+
+        // then
+        assert_eq!(
+            got.unwrap(),
+            vec![
+                Connection {
+                    provider: "google",
+                    external_user_id: String::from("google-id"),
+                },
+                Connection {
+                    provider: "apple",
+                    external_user_id: String::from("apple-id"),
+                },
+            ]
+        );
+        assert_eq!(auth_client.get_oauth_account_calls(), 3);
+    }
+
+    #[rstest]
+    #[case::invalid_user_id(Status::invalid_argument("invalid user id"), StatusCode::BAD_REQUEST)]
+    #[case::internal(Status::internal("db error"), StatusCode::INTERNAL_SERVER_ERROR)]
+    #[case::unavailable(Status::unavailable("down"), StatusCode::SERVICE_UNAVAILABLE)]
+    #[tokio::test]
+    async fn test_list_connections_error(#[case] status: Status, #[case] want: StatusCode) {
+        // given
+        let mut auth_client = MockAuthClient::default();
+        auth_client.push_get_oauth_account_resp(Err(status));
+
+        // when
+        let got = list_connections(&auth_client, "user-id").await;
+
+        // then
+        let Err(err) = got else {
+            panic!("expected an error");
+        };
+        assert_eq!(err.into_response().status(), want);
+        assert_eq!(auth_client.get_oauth_account_calls(), 1);
+    }
+
+    fn fixture_user(id: &str) -> user::proto::User {
+        user::proto::User {
+            id: id.to_string(),
+            name: String::from("name"),
+            email: String::from("user@example.com"),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::found(Ok(GetUserResp { user: Some(fixture_user("user-id")) }), StatusCode::OK)]
+    #[case::not_found(Err(Status::not_found("user not found")), StatusCode::NOT_FOUND)]
+    #[tokio::test]
+    async fn test_get_current_user(
+        #[case] get_user: Result<GetUserResp, Status>,
+        #[case] want: StatusCode,
+    ) {
+        // given
+        let mut user_client = MockUserClient::default();
+        user_client.push_get_user_resp(get_user);
+        let handler = Handler::with_clients(MockAuthClient::default(), user_client).unwrap();
+        let session = SessionState {
+            user_id: String::from("user-id"),
+        };
+
+        // when
+        let got = get_current_user(State(handler), Extension(session)).await;
+
+        // then
+        assert_eq!(got.into_response().status(), want);
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_user_existing_email() {
+        // given
+        let mut user_client = MockUserClient::default();
+        user_client.push_get_user_by_email_resp(Ok(user::proto::GetUserByEmailResp {
+            user: Some(fixture_user("existing-id")),
+        }));
+
+        // when
+        let got = find_or_create_user(
+            &user_client,
+            String::from("name"),
+            String::from("User@Example.com"),
+        )
+        .await;
+
+        // then
+        assert_eq!(got.unwrap(), "existing-id");
+        assert_eq!(user_client.create_user_calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_user_new_email() {
+        // given
+        let mut user_client = MockUserClient::default();
+        user_client
+            .push_get_user_by_email_resp(Err(Status::not_found("user not found")))
+            .push_create_user_resp(Ok(user::proto::CreateUserResp {
+                user: Some(fixture_user("new-id")),
+            }));
+
+        // when
+        let got = find_or_create_user(
+            &user_client,
+            String::from("name"),
+            String::from("user@example.com"),
+        )
+        .await;
+
+        // then
+        assert_eq!(got.unwrap(), "new-id");
+        assert_eq!(user_client.create_user_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_or_create_user_lookup_error() {
+        // given
+        let mut user_client = MockUserClient::default();
+        user_client.push_get_user_by_email_resp(Err(Status::internal("db error")));
+
+        // when
+        let got = find_or_create_user(
+            &user_client,
+            String::from("name"),
+            String::from("user@example.com"),
+        )
+        .await;
+
+        // then
+        assert!(matches!(got, Err(OAuthError::RequestError(_))), "{got:?}");
+        assert_eq!(user_client.create_user_calls(), 0);
+    }
+
+    fn lazy_handler() -> Handler {
+        Handler {
+            auth_client: AuthClient::lazy().unwrap(),
+            user_client: UserClient::lazy().unwrap(),
+            oauth_metrics: OAuthMetrics::default(),
+            cookie_config: CookieConfig::default(),
+            oauth_state: OAuthStateSigner::new("secret", chrono::Duration::minutes(10)),
+            frontend_url: String::from("https://example.com"),
+        }
+    }
+
+    #[rstest]
+    #[case::fresh(chrono::Duration::minutes(1), None)]
+    #[case::stale(chrono::Duration::minutes(11), Some(OAuthOutcome::StateExpired))]
+    #[tokio::test]
+    async fn test_oauth_callback_state_age(
+        #[case] age: chrono::Duration,
+        #[case] want_err: Option<OAuthOutcome>,
+    ) {
+        // given
+        let handler = lazy_handler();
+        let issued_at = DateTime::from_timestamp(1577836800, 0).unwrap();
+        let stored_state = handler.oauth_state.sign("state", issued_at);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "cookie",
+            format!("{OAUTH_STATE}={stored_state}").parse().unwrap(),
+        );
+        let jar = OauthCookieJar::from_headers(&headers).unwrap();
+        let uri: Uri = "/auth/google/callback?code=code&state=state"
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<OauthCallbackQuery>::try_from_uri(&uri).unwrap();
+
+        // when
+        let got = check_state(&handler, &query, &jar, issued_at + age);
+
+        // then
+        assert_eq!(got.map_err(|e| e.outcome()).err(), want_err);
+    }
+
+    #[tokio::test]
+    async fn test_logout_without_session_cookie() {
+        // given
+        let handler = lazy_handler();
+
+        // when
+        let got = logout_user(State(handler), HeaderMap::new()).await;
+
+        // then
+        let response = got.expect("logout without a session should succeed");
+        assert_eq!(response.status(), StatusCode::OK);
+        let set_cookie = response
+            .headers()
+            .get(axum::http::header::SET_COOKIE)
+            .expect("expected the session cookie to be expired")
+            .to_str()
+            .unwrap();
+        assert!(set_cookie.starts_with("session_token=;"), "{set_cookie}");
+        assert!(set_cookie.contains("Max-Age=0"), "{set_cookie}");
+    }
+
+    #[tokio::test]
+    async fn test_forward_oauth_form_post() {
+        // given
+        let form = OauthCallbackQuery {
+            state: String::from("state"),
+            code: String::from("a/b"),
+            error: None,
+            error_description: None,
+        };
+
+        // when
+        let got = forward_oauth_form_post(
+            Path(String::from("apple")),
+            State(lazy_handler()),
+            Form(form),
+        )
+        .await;
+
+        // then
+        let response = got.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get(LOCATION).unwrap(),
+            "https://example.com/login/apple/callback?state=state&code=a%2Fb"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_oauth_form_post_unsupported_provider() {
+        // given
+        let form = OauthCallbackQuery {
+            state: String::from("state"),
+            code: String::from("code"),
+            error: None,
+            error_description: None,
+        };
+
+        // when
+        let got = forward_oauth_form_post(
+            Path(String::from("unknown")),
+            State(lazy_handler()),
+            Form(form),
+        )
+        .await;
+
+        // then
+        let Err(err) = got else {
+            panic!("expected unsupported provider error");
+        };
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_oauth_callback_provider_denied() {
+        // given
+        let uri: Uri =
+            "/auth/google/callback?error=access_denied&error_description=user%20denied&state=state"
+                .parse()
+                .unwrap();
+        let Query(query) = Query::<OauthCallbackQuery>::try_from_uri(&uri).unwrap();
+
+        // when
+        let got = query.check_provider_error();
+
+        // then
+        let Err(err) = got else {
+            panic!("expected provider denied error");
+        };
+        assert!(matches!(&err, OAuthError::ProviderDenied(d) if d == "user denied"));
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[rstest]
+    #[case::root("/auth/google/login?return_to=/", Some("/"))]
+    #[case::path_with_query(
+        "/auth/google/login?return_to=%2Fsettings%3Ftab%3D1",
+        Some("/settings?tab=1")
+    )]
+    #[case::missing("/auth/google/login", None)]
+    fn test_start_oauth_login_valid_return_to(#[case] uri: &str, #[case] want: Option<&str>) {
+        // given
+        let uri: Uri = uri.parse().unwrap();
+        let Query(query) = Query::<StartOauthLoginQuery>::try_from_uri(&uri).unwrap();
+
+        // when
+        let got = query.return_to();
+
+        // then
+        assert_eq!(got.unwrap(), want);
+    }
+
+    #[rstest]
+    #[case::absolute_url("https://evil.com")]
+    #[case::protocol_relative("//evil.com")]
+    #[case::backslash("/%5Cevil.com")]
+    #[case::relative_path("settings")]
+    #[case::cookie_separator("/path%3Bx%3Dy")]
+    #[case::whitespace("/path%20name")]
+    fn test_start_oauth_login_invalid_return_to(#[case] return_to: &str) {
+        // given
+        let uri: Uri = format!("/auth/google/login?return_to={return_to}")
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<StartOauthLoginQuery>::try_from_uri(&uri).unwrap();
+
+        // when
+        let got = query.return_to();
+
+        // then
+        let Err(err) = got else {
+            panic!("expected invalid return_to error");
+        };
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_oauth_callback_outcomes() {
+        // given
+        let errors = [
+            (OAuthError::StateMismatch, OAuthOutcome::StateMismatch),
+            (
+                OAuthError::ProviderDenied(String::from("denied")),
+                OAuthOutcome::ProviderDenied,
+            ),
+            (
+                OAuthError::TokenExchange(Status::internal("failed")),
+                OAuthOutcome::TokenExchangeFailed,
+            ),
+            (
+                OAuthError::TokenExchange(Status::permission_denied("domain")),
+                OAuthOutcome::EmailDomainNotAllowed,
+            ),
+            (
+                OAuthError::RequestError(Status::internal("failed")),
+                OAuthOutcome::Error,
+            ),
+        ];
+
+        for (err, want) in errors {
+            // when
+            let got = err.outcome();
+
+            // then
+            assert_eq!(got, want);
+        }
+    }
+
+    #[rstest]
+    #[case::new_account("", Ok(true))]
+    #[case::linked_to_user("user-id", Ok(false))]
+    #[case::linked_to_other_user("other-user-id", Err(StatusCode::CONFLICT))]
+    fn test_needs_link(#[case] linked_user_id: &str, #[case] want: Result<bool, StatusCode>) {
+        // when
+        let got = needs_link(linked_user_id, "user-id");
+
+        // then
+        let got = got.map_err(|err| err.into_response().status());
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn test_oauth_callback_without_error() {
+        // given
+        let uri: Uri = "/auth/google/callback?code=code&state=state"
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<OauthCallbackQuery>::try_from_uri(&uri).unwrap();
+
+        // when
+        let got = query.check_provider_error();
+
+        // then
+        assert!(got.is_ok());
+    }
+}
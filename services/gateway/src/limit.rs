@@ -0,0 +1,298 @@
+//! Load shedding and rate limiting for the gateway.
+//!
+//! Without a cap the gateway forwards any number of concurrent requests
+//! to the upstream services, which can exhaust their database pools.
+//! Requests beyond the cap are rejected with `503 Service Unavailable`
+//! instead of being queued.
+//!
+//! Unauthenticated routes are additionally rate limited per client, which
+//! rejects requests beyond the limit with `429 Too Many Requests`.
+use axum::{
+    BoxError, Json, Router,
+    error_handling::HandleErrorLayer,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header::RETRY_AFTER},
+    middleware::{Next, from_fn_with_state},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tower::ServiceBuilder;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::load_shed::LoadShedLayer;
+
+/// The default maximum number of concurrently handled requests.
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 512;
+
+/// The default number of oauth requests a client may send per minute.
+const DEFAULT_OAUTH_REQUESTS_PER_MINUTE: u32 = 30;
+
+/// The number of tracked clients above which expired windows are dropped.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Reads the maximum number of concurrently handled requests from
+/// `MAX_CONCURRENT_REQUESTS`.
+pub(crate) fn max_concurrent_requests_from_env() -> Result<usize, Box<dyn std::error::Error>> {
+    match std::env::var("MAX_CONCURRENT_REQUESTS") {
+        Ok(max) => Ok(max.parse()?),
+        Err(_) => Ok(DEFAULT_MAX_CONCURRENT_REQUESTS),
+    }
+}
+
+/// Sheds requests once `max` requests are handled concurrently. The cap
+/// is shared across all routes of the router.
+pub(crate) fn with_load_shedding<S>(router: Router<S>, max: usize) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(handle_overload))
+            .layer(LoadShedLayer::new())
+            .layer(GlobalConcurrencyLimitLayer::new(max)),
+    )
+}
+
+async fn handle_overload(err: BoxError) -> StatusCode {
+    if err.is::<tower::load_shed::error::Overloaded>() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+/// Reads the number of oauth requests a client may send per minute from
+/// `OAUTH_RATE_LIMIT_PER_MINUTE`.
+pub(crate) fn oauth_requests_per_minute_from_env() -> Result<u32, Box<dyn std::error::Error>> {
+    match std::env::var("OAUTH_RATE_LIMIT_PER_MINUTE") {
+        Ok(limit) => Ok(limit.parse()?),
+        Err(_) => Ok(DEFAULT_OAUTH_REQUESTS_PER_MINUTE),
+    }
+}
+
+/// Rejects requests of a client once it sent `limit` requests within the
+/// current window. Each route of the router shares the limit.
+pub(crate) fn with_rate_limit<S>(router: Router<S>, limit: RateLimiter) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(from_fn_with_state(limit, rate_limit))
+}
+
+/// Counts requests per client in fixed windows.
+#[derive(Clone, Debug)]
+pub(crate) struct RateLimiter {
+    windows: Arc<Mutex<HashMap<Option<IpAddr>, Window>>>,
+    limit: u32,
+    period: Duration,
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+impl RateLimiter {
+    /// Creates a limiter that allows `limit` requests per `period`.
+    pub(crate) fn new(limit: u32, period: Duration) -> Self {
+        Self {
+            windows: Arc::default(),
+            limit,
+            period,
+        }
+    }
+
+    /// Creates a limiter that allows `limit` requests per minute.
+    pub(crate) fn per_minute(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    /// Counts a request of the client at `now`. Returns the time until the
+    /// window resets if the client exceeded the limit.
+    fn check(&self, client: Option<IpAddr>, now: Instant) -> Result<(), Duration> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, window| now.duration_since(window.started) < self.period);
+        }
+
+        let window = windows.entry(client).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+        if now.duration_since(window.started) >= self.period {
+            window.started = now;
+            window.count = 0;
+        }
+
+        if window.count >= self.limit {
+            return Err(self.period - now.duration_since(window.started));
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
+
+async fn rate_limit(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    let client = client_ip(&req);
+    match limiter.check(client, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            let body = Json(json!({ "error": "too many requests" }));
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, body).into_response();
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1)));
+            response
+        }
+    }
+}
+
+/// Returns the ip of the client, preferring the first `X-Forwarded-For`
+/// entry set by the load balancer over the peer address. Clients can spoof
+/// the header unless the load balancer overwrites it.
+fn client_ip(req: &Request) -> Option<IpAddr> {
+    forwarded_for(req.headers()).or_else(|| {
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-forwarded-for")?
+        .to_str()
+        .ok()?
+        .split(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get};
+    use rstest::rstest;
+    use std::sync::Arc;
+    use tokio::sync::Notify;
+    use tower::ServiceExt as _;
+
+    fn request(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    fn forwarded_request(uri: &str, ip: &str) -> Request<Body> {
+        Request::builder()
+            .uri(uri)
+            .header("x-forwarded-for", ip)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_limit_are_shed() {
+        // given
+        let release = Arc::new(Notify::new());
+        let blocked = release.clone();
+        let router = with_load_shedding(
+            Router::new()
+                .route("/slow", get(|| async move { blocked.notified().await }))
+                .route("/fast", get(|| async {})),
+            1,
+        );
+        let in_flight = tokio::spawn(router.clone().oneshot(request("/slow")));
+        tokio::task::yield_now().await;
+
+        // when
+        let shed = router.clone().oneshot(request("/fast")).await.unwrap();
+        release.notify_one();
+        let in_flight = in_flight.await.unwrap().unwrap();
+        let after = router.oneshot(request("/fast")).await.unwrap();
+
+        // then
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(in_flight.status(), StatusCode::OK);
+        assert_eq!(after.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_rate_limit_are_rejected() {
+        // given
+        let router = with_rate_limit(
+            Router::new().route("/auth/{provider}/login", get(|| async {})),
+            RateLimiter::per_minute(2),
+        );
+        for _ in 0..2 {
+            let resp = router
+                .clone()
+                .oneshot(forwarded_request("/auth/google/login", "203.0.113.1"))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        // when
+        let limited = router
+            .clone()
+            .oneshot(forwarded_request("/auth/github/login", "203.0.113.1"))
+            .await
+            .unwrap();
+        let other_client = router
+            .oneshot(forwarded_request("/auth/google/login", "203.0.113.2"))
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(limited.headers().contains_key(RETRY_AFTER));
+        let body = axum::body::to_bytes(limited.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({ "error": "too many requests" })
+        );
+        assert_eq!(other_client.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_rate_limit_window_resets() {
+        // given
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let client = Some(IpAddr::from([203, 0, 113, 1]));
+        let now = Instant::now();
+        limiter.check(client, now).unwrap();
+
+        // when
+        let limited = limiter.check(client, now + Duration::from_secs(59));
+        let reset = limiter.check(client, now + Duration::from_secs(60));
+
+        // then
+        assert_eq!(limited, Err(Duration::from_secs(1)));
+        assert_eq!(reset, Ok(()));
+    }
+
+    #[rstest]
+    #[case::single("203.0.113.1", Some(IpAddr::from([203, 0, 113, 1])))]
+    #[case::chain("203.0.113.1, 10.0.0.1", Some(IpAddr::from([203, 0, 113, 1])))]
+    #[case::invalid("unknown", None)]
+    fn test_forwarded_for(#[case] header: &str, #[case] want: Option<IpAddr>) {
+        // given
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_str(header).unwrap());
+
+        // when
+        let got = forwarded_for(&headers);
+
+        // then
+        assert_eq!(got, want);
+    }
+}
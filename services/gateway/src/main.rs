@@ -1,51 +1,73 @@
+mod cors;
+mod dev;
 mod error;
 mod handler;
+mod limit;
+mod oauth_state;
 mod utils;
 
 use crate::handler::{
-    Handler, get_current_user, handle_oauth_callback, logout_user, start_oauth_login,
+    Handler, forward_oauth_form_post, get_active_sessions_count, get_connections, get_current_user,
+    handle_oauth_callback, healthz, link_oauth_account, logout_user, readyz, start_oauth_login,
 };
 use auth::client::AuthClient;
 use axum::{
     Router,
-    http::{
-        HeaderValue, Method,
-        header::{AUTHORIZATION, CONTENT_TYPE},
-    },
     routing::{get, post},
 };
 use gateway::{HTTP_PORT, SERVICE_NAME};
 use setup::middleware::{TracingHttpServiceLayer, auth::SessionAuthLayer};
 use setup::tracing::init_tracer;
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let tracer = init_tracer(SERVICE_NAME)?;
 
-    let cors = CorsLayer::new()
-        .allow_origin("http://localhost:5173".parse::<HeaderValue>().unwrap())
-        .allow_credentials(true)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers(vec![AUTHORIZATION, CONTENT_TYPE]);
+    let cors = cors::cors_layer_from_env()?;
 
     let auth_client = AuthClient::new().await?;
 
     let handler = Handler::new().await?;
+    let oauth_routes = limit::with_rate_limit(
+        Router::new()
+            .route("/auth/{provider}/login", get(start_oauth_login))
+            .route(
+                "/auth/{provider}/callback",
+                get(handle_oauth_callback).post(forward_oauth_form_post),
+            )
+            .route("/auth/{provider}/link", post(link_oauth_account)),
+        limit::RateLimiter::per_minute(limit::oauth_requests_per_minute_from_env()?),
+    );
     let mut router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
         .route("/logout", post(logout_user))
         .route("/user/me", get(get_current_user))
-        .route("/auth/{provider}/login", get(start_oauth_login))
-        .route("/auth/{provider}/callback", get(handle_oauth_callback))
+        .route("/user/me/sessions/count", get(get_active_sessions_count))
+        .route("/user/me/connections", get(get_connections))
+        .merge(oauth_routes)
+        .merge(dev::dev_routes())
         .with_state(handler);
-    router = router.layer(SessionAuthLayer::new(
-        auth_client.clone(),
-        vec![
-            String::from("/auth/*/login"),
-            String::from("/auth/*/callback"),
-        ],
-    ));
+    router = router.layer(
+        SessionAuthLayer::new(
+            auth_client.clone(),
+            [
+                vec![
+                    String::from("/healthz"),
+                    String::from("/readyz"),
+                    String::from("/logout"),
+                    String::from("/auth/*/login"),
+                    String::from("/auth/*/callback"),
+                ],
+                dev::no_auth_endpoints(),
+            ]
+            .concat(),
+        )
+        .with_json_errors(true),
+    );
+    router = limit::with_load_shedding(router, limit::max_concurrent_requests_from_env()?);
     router = router.layer(cors).layer(TracingHttpServiceLayer);
 
     let address = format!("0.0.0.0:{HTTP_PORT}");
@@ -53,7 +75,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let listener = TcpListener::bind(address).await?;
     println!("listening on :{}", listener.local_addr()?);
 
-    axum::serve(listener, router).await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     tracer.shutdown()?;
 
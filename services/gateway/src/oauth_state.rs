@@ -0,0 +1,156 @@
+//! Signed oauth state cookies.
+//!
+//! The state cookie carries the time it was issued at, signed with an
+//! HMAC so that it cannot be altered. Callbacks whose state is older
+//! than the configured window are rejected, e.g. when a user resumes
+//! an authorization attempt from a cached tab.
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::error::OAuthError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The default maximum age of an oauth state, matching the cookie's max age.
+const DEFAULT_STATE_MAX_AGE: Duration = Duration::minutes(10);
+
+/// Signs and verifies the value of the oauth state cookie.
+#[derive(Clone)]
+pub(crate) struct OAuthStateSigner {
+    key: Vec<u8>,
+    max_age: Duration,
+}
+
+impl OAuthStateSigner {
+    /// Creates a new [`OAuthStateSigner`].
+    pub(crate) fn new<K: Into<Vec<u8>>>(key: K, max_age: Duration) -> Self {
+        Self {
+            key: key.into(),
+            max_age,
+        }
+    }
+
+    /// Reads the key from `OAUTH_STATE_SECRET` and the window from
+    /// `OAUTH_STATE_MAX_AGE_SECONDS`, which defaults to ten minutes.
+    ///
+    /// Without a secret a random key is used, which only works with a
+    /// single gateway instance.
+    pub(crate) fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let key = match std::env::var("OAUTH_STATE_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+            _ => {
+                tracing::warn!("OAUTH_STATE_SECRET is not set, using a random key");
+                let mut key = vec![0u8; 32];
+                rand::rng().fill_bytes(&mut key);
+                key
+            }
+        };
+
+        let max_age = match std::env::var("OAUTH_STATE_MAX_AGE_SECONDS") {
+            Ok(secs) => Duration::seconds(secs.parse()?),
+            Err(_) => DEFAULT_STATE_MAX_AGE,
+        };
+
+        Ok(Self::new(key, max_age))
+    }
+
+    /// Returns the cookie value for `state`, as `<state>.<issued_at>.<signature>`.
+    pub(crate) fn sign(&self, state: &str, issued_at: DateTime<Utc>) -> String {
+        let payload = format!("{state}.{}", issued_at.timestamp());
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        format!("{payload}.{signature}")
+    }
+
+    /// Verifies the signature and the age of a cookie value and returns the state.
+    ///
+    /// # Errors
+    /// - [`OAuthError::StateMismatch`] if the value is malformed or the signature is invalid
+    /// - [`OAuthError::StateExpired`] if the state is older than the window
+    pub(crate) fn verify(&self, value: &str, now: DateTime<Utc>) -> Result<String, OAuthError> {
+        let (payload, signature) = value.rsplit_once('.').ok_or(OAuthError::StateMismatch)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| OAuthError::StateMismatch)?;
+        self.mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| OAuthError::StateMismatch)?;
+
+        let (state, issued_at) = payload.rsplit_once('.').ok_or(OAuthError::StateMismatch)?;
+        let issued_at = issued_at
+            .parse()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .ok_or(OAuthError::StateMismatch)?;
+
+        if now - issued_at > self.max_age {
+            return Err(OAuthError::StateExpired);
+        }
+
+        Ok(state.to_string())
+    }
+
+    fn mac(&self, payload: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("hmac accepts keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    fn issued_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    #[rstest]
+    #[case::fresh(Duration::minutes(1), Ok(String::from("state")))]
+    #[case::at_max_age(Duration::minutes(10), Ok(String::from("state")))]
+    #[case::stale(Duration::minutes(11), Err(OAuthError::StateExpired))]
+    fn test_verify_state_age(#[case] age: Duration, #[case] want: Result<String, OAuthError>) {
+        // given
+        let signer = OAuthStateSigner::new("secret", DEFAULT_STATE_MAX_AGE);
+        let value = signer.sign("state", issued_at());
+
+        // when
+        let got = signer.verify(&value, issued_at() + age);
+
+        // then
+        assert_eq!(format!("{got:?}"), format!("{want:?}"));
+    }
+
+    #[rstest]
+    #[case::unsigned("state")]
+    #[case::tampered_timestamp("state.1893456000.c2lnbmF0dXJl")]
+    #[case::invalid_signature("state.1577836800.!")]
+    fn test_verify_state_rejects_tampering(#[case] value: &str) {
+        // given
+        let signer = OAuthStateSigner::new("secret", DEFAULT_STATE_MAX_AGE);
+
+        // when
+        let got = signer.verify(value, issued_at());
+
+        // then
+        assert!(matches!(got, Err(OAuthError::StateMismatch)), "{got:?}");
+    }
+
+    #[test]
+    fn test_verify_state_with_other_key() {
+        // given
+        let value =
+            OAuthStateSigner::new("other", DEFAULT_STATE_MAX_AGE).sign("state", issued_at());
+        let signer = OAuthStateSigner::new("secret", DEFAULT_STATE_MAX_AGE);
+
+        // when
+        let got = signer.verify(&value, issued_at());
+
+        // then
+        assert!(matches!(got, Err(OAuthError::StateMismatch)), "{got:?}");
+    }
+}
@@ -1,13 +1,33 @@
 #![allow(clippy::result_large_err)]
-use auth::proto::OauthProvider;
+use auth::proto::{CreateSessionResp, OauthProvider};
 use axum::http::{HeaderMap, StatusCode, header::COOKIE};
-use setup::cookie::extract_cookie_by_name;
+use chrono::Duration;
+use setup::cookie::{
+    Cookie, CookieConfig, create_session_token_cookie, create_session_token_cookie_with_max_age,
+    extract_cookie_from_headers,
+};
 use tonic::Code;
 
 use crate::error::OAuthError;
 
 pub(crate) const OAUTH_STATE: &str = "oauth_state";
 pub(crate) const OAUTH_CODE_VERIFIER: &str = "oauth_code_verifier";
+pub(crate) const OAUTH_NONCE: &str = "oauth_nonce";
+pub(crate) const OAUTH_RETURN_TO: &str = "oauth_return_to";
+
+/// Returns whether `target` is a safe post-login redirect target.
+///
+/// Only same-origin absolute paths such as `/settings?tab=1` are allowed,
+/// which rules out open redirects to other hosts via `//host`,
+/// `/\host` or `https://host`. The target is stored in a cookie, so it
+/// must be url encoded and must not contain `;`.
+pub(crate) fn is_valid_return_to(target: &str) -> bool {
+    target.starts_with('/')
+        && !target.starts_with("//")
+        && target
+            .chars()
+            .all(|c| c.is_ascii_graphic() && c != '\\' && c != ';')
+}
 
 /// Maps grpc codes to http status codes.
 ///
@@ -42,16 +62,37 @@ impl<'a> OauthCookieJar<'a> {
         Ok(Self(headers))
     }
 
+    pub(crate) fn extract_optional(&self, name: &'static str) -> Option<String> {
+        extract_cookie_from_headers(name, self.0).filter(|v| !v.is_empty())
+    }
+
     pub(crate) fn extract(&self, name: &'static str) -> Result<String, OAuthError> {
-        let cookies = self.0.get(COOKIE).unwrap();
-        extract_cookie_by_name(name, cookies).ok_or(OAuthError::MissingCookie(name))
+        extract_cookie_from_headers(name, self.0).ok_or(OAuthError::MissingCookie(name))
     }
 }
 
+/// The supported oauth providers by the name used in routes.
+pub(crate) const SUPPORTED_PROVIDERS: [(&str, OauthProvider); 3] = [
+    ("google", OauthProvider::Google),
+    ("github", OauthProvider::Github),
+    ("apple", OauthProvider::Apple),
+];
+
 pub fn parse_provider<S: AsRef<str>>(provider: S) -> OauthProvider {
-    match provider.as_ref() {
-        "google" => OauthProvider::Google,
-        "github" => OauthProvider::Github,
-        _ => OauthProvider::Unspecified,
+    SUPPORTED_PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == provider.as_ref())
+        .map_or(OauthProvider::Unspecified, |(_, provider)| *provider)
+}
+
+/// Creates the session token cookie for a newly created session.
+///
+/// Falls back to the default expiry if the auth service does not report one.
+pub(crate) fn session_token_cookie(session: CreateSessionResp, config: &CookieConfig) -> Cookie {
+    match session.expires_in_seconds {
+        secs if secs > 0 => {
+            create_session_token_cookie_with_max_age(session.token, Duration::seconds(secs), config)
+        }
+        _ => create_session_token_cookie(session.token, config),
     }
 }
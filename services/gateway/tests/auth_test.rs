@@ -6,7 +6,7 @@ use reqwest::Client;
 #[tokio::test]
 async fn test_get_current_user_authenticated() {
     let containers = get_test_containers().await;
-    let authenticated_user = create_authenticated_user(&containers).await.unwrap();
+    let authenticated_user = create_authenticated_user(containers).await.unwrap();
     let uri = containers.gateway_uri().await;
 
     let resp = Client::new()
@@ -36,7 +36,7 @@ async fn test_get_current_user_unauthenticated() {
 #[tokio::test]
 async fn test_logout_user() {
     let containers = get_test_containers().await;
-    let authenticated_user = create_authenticated_user(&containers).await.unwrap();
+    let authenticated_user = create_authenticated_user(containers).await.unwrap();
     let uri = containers.gateway_uri().await;
 
     let resp = Client::new()
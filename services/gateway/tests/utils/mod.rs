@@ -1,6 +1,10 @@
 pub mod testcontainers;
 
-use std::{error::Error, str::FromStr};
+use std::{
+    error::Error,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use auth::proto::{CreateSessionReq, auth_service_client::AuthServiceClient as AuthClient};
 use axum::http::{HeaderMap, HeaderValue};
@@ -28,6 +32,9 @@ impl AuthenticatedUser {
     }
 }
 
+/// Numbers the users of a test run, since emails must be unique.
+static NEXT_USER: AtomicUsize = AtomicUsize::new(0);
+
 pub(crate) async fn create_authenticated_user(
     containers: &TestContainers,
 ) -> Result<AuthenticatedUser, Box<dyn Error>> {
@@ -45,7 +52,10 @@ pub(crate) async fn create_authenticated_user(
 
     let req = Request::new(CreateUserReq {
         name: "integration-test-name".to_string(),
-        email: "integration-test-email".to_string(),
+        email: format!(
+            "integration-test-{}@example.com",
+            NEXT_USER.fetch_add(1, Ordering::SeqCst)
+        ),
     });
     let resp = user_client.create_user(req).await?;
     let user = resp.into_inner().user.unwrap();
@@ -1,9 +1,20 @@
 use std::{collections::HashMap, time::Duration};
+use testcontainers::core::wait::HttpWaitStrategy;
 use testcontainers::{ContainerAsync, GenericImage, ImageExt, core::WaitFor};
 use testcontainers::{core::ContainerPort, runners::AsyncRunner};
 use tokio::io::AsyncBufReadExt;
 use tokio::sync::OnceCell;
 use tokio::{io::BufReader, time::timeout};
+use tonic::transport::Channel;
+use tonic_health::pb::{
+    HealthCheckRequest, health_check_response::ServingStatus, health_client::HealthClient,
+};
+
+/// How long a service may take to report itself healthy.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the health of a service is polled.
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[allow(dead_code)]
 pub(crate) struct TestContainers {
@@ -91,7 +102,7 @@ impl TestContainers {
 }
 
 async fn run_postgres(pg_host: &str, pg_port: u16) -> ContainerAsync<GenericImage> {
-    GenericImage::new("postgres", "latest")
+    GenericImage::new(String::from("postgres"), testutils::postgres_image_tag())
         .with_exposed_port(ContainerPort::Tcp(pg_port))
         .with_wait_for(WaitFor::message_on_stdout(
             "database system is ready to accept connections",
@@ -122,18 +133,54 @@ async fn run_auth_service(pg_host: &str, pg_port: &str) -> ContainerAsync<Generi
     auth_env_vars.insert("GITHUB_CLIENT_ID", "test");
     auth_env_vars.insert("GITHUB_CLIENT_SECRET", "test");
     auth_env_vars.insert("GITHUB_REDIRECT_URI", "test");
-    let exposed_port = Some(auth::GRPC_PORT);
-    run_service_container("auth", pg_host, pg_port, auth_env_vars, exposed_port).await
+    auth_env_vars.insert("APPLE_CLIENT_ID", "test");
+    auth_env_vars.insert("APPLE_TEAM_ID", "test");
+    auth_env_vars.insert("APPLE_KEY_ID", "test");
+    auth_env_vars.insert("APPLE_PRIVATE_KEY", "test");
+    auth_env_vars.insert("APPLE_REDIRECT_URI", "test");
+    let exposed_port = auth::GRPC_PORT;
+    let container = run_service_container(
+        "auth",
+        pg_host,
+        pg_port,
+        auth_env_vars,
+        exposed_port,
+        WaitFor::Nothing,
+    )
+    .await;
+    wait_until_serving(&container, "auth", exposed_port).await;
+    container
 }
 
 async fn run_user_service(pg_host: &str, pg_port: &str) -> ContainerAsync<GenericImage> {
-    let exposed_port = Some(user::GRPC_PORT);
-    run_service_container("user", pg_host, pg_port, HashMap::new(), exposed_port).await
+    let exposed_port = user::GRPC_PORT;
+    let container = run_service_container(
+        "user",
+        pg_host,
+        pg_port,
+        HashMap::new(),
+        exposed_port,
+        WaitFor::Nothing,
+    )
+    .await;
+    wait_until_serving(&container, "user", exposed_port).await;
+    container
 }
 
 async fn run_gateway_service(pg_host: &str, pg_port: &str) -> ContainerAsync<GenericImage> {
-    let exposed_port = Some(gateway::HTTP_PORT);
-    run_service_container("gateway", pg_host, pg_port, HashMap::new(), exposed_port).await
+    let exposed_port = gateway::HTTP_PORT;
+    let healthz = HttpWaitStrategy::new("/healthz")
+        .with_port(ContainerPort::Tcp(exposed_port))
+        .with_expected_status_code(200u16);
+    run_service_container(
+        "gateway",
+        pg_host,
+        pg_port,
+        HashMap::new(),
+        exposed_port,
+        WaitFor::http(healthz),
+    )
+    .await
 }
 
 async fn run_service_container(
@@ -141,15 +188,13 @@ async fn run_service_container(
     pg_host: &str,
     pg_port: &str,
     env_vars: HashMap<&'static str, &'static str>,
-    exposed_port: Option<u16>,
+    exposed_port: u16,
+    wait_for: WaitFor,
 ) -> ContainerAsync<GenericImage> {
-    let mut container =
-        GenericImage::new(format!("services_{service_name}"), String::from("latest"));
-    if let Some(exposed_port) = exposed_port {
-        container = container.with_exposed_port(ContainerPort::Tcp(exposed_port));
-    }
+    let container = GenericImage::new(format!("services_{service_name}"), String::from("latest"))
+        .with_exposed_port(ContainerPort::Tcp(exposed_port));
     let mut container_request = container
-        .with_wait_for(WaitFor::message_on_stdout("listening on"))
+        .with_wait_for(wait_for)
         .with_container_name(format!("{service_name}-integration-test"))
         .with_network("shared_network")
         .with_env_var("APP_ENV", "integration-test")
@@ -166,13 +211,44 @@ async fn run_service_container(
     let container = container_request
         .start()
         .await
-        .expect(&format!("failed to start {service_name} service"));
+        .unwrap_or_else(|_| panic!("failed to start {service_name} service"));
 
     // read_startup_logs(&container, service_name).await;
 
     container
 }
 
+/// Polls the `grpc.health.v1.Health` service of a container until the
+/// server reports `SERVING`, i.e. until its database is reachable.
+async fn wait_until_serving(
+    container: &ContainerAsync<GenericImage>,
+    service_name: &str,
+    container_port: u16,
+) {
+    let host = container.get_host().await.unwrap();
+    let port = container.get_host_port_ipv4(container_port).await.unwrap();
+    let channel = Channel::from_shared(format!("http://{host}:{port}")).unwrap();
+
+    let serving = async {
+        loop {
+            if let Ok(channel) = channel.connect().await {
+                let req = HealthCheckRequest {
+                    service: String::new(),
+                };
+                let resp = HealthClient::new(channel).check(req).await;
+                if resp.is_ok_and(|r| r.into_inner().status() == ServingStatus::Serving) {
+                    return;
+                }
+            }
+            tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+        }
+    };
+
+    timeout(HEALTH_TIMEOUT, serving)
+        .await
+        .unwrap_or_else(|_| panic!("{service_name} service did not become healthy"));
+}
+
 #[allow(dead_code)]
 async fn read_startup_logs(container: &ContainerAsync<GenericImage>, service_name: &str) {
     let mut stdout = BufReader::new(container.stdout(true)).lines();
@@ -0,0 +1,130 @@
+//! Resolution of the hosts under which services are reachable.
+use std::collections::HashMap;
+
+/// Suffix of the env vars that override a single host.
+const HOST_OVERRIDE_SUFFIX: &str = "HOST_OVERRIDE";
+
+/// Resolves the host of a service depending on the environment.
+///
+/// In order of precedence:
+/// - a `<SERVICE>_HOST_OVERRIDE` env var redirects a single host
+/// - `HOST_SUFFIX` is appended to every host, e.g. `-staging`
+/// - `APP_ENV=local` resolves to `localhost`
+/// - `APP_ENV=integration-test` appends `-integration-test`
+/// - otherwise the host is returned as is
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostResolver {
+    app_env: String,
+    host_suffix: Option<String>,
+    overrides: HashMap<String, String>,
+}
+
+impl HostResolver {
+    /// Creates a resolver for the given `APP_ENV`.
+    pub fn new<S: Into<String>>(app_env: S) -> Self {
+        Self {
+            app_env: app_env.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Appends the suffix to every resolved host.
+    pub fn with_host_suffix<S: Into<String>>(mut self, host_suffix: S) -> Self {
+        self.host_suffix = Some(host_suffix.into()).filter(|s| !s.is_empty());
+        self
+    }
+
+    /// Resolves `host` to `target`, regardless of the environment.
+    pub fn with_override<S: AsRef<str>, T: Into<String>>(mut self, host: S, target: T) -> Self {
+        let target = target.into();
+        if !target.is_empty() {
+            self.overrides
+                .insert(host_override_key(host.as_ref()), target);
+        }
+        self
+    }
+
+    /// Reads `APP_ENV`, `HOST_SUFFIX` and all `<SERVICE>_HOST_OVERRIDE`
+    /// env vars.
+    pub fn from_env() -> Self {
+        let get_env = |key: &str| std::env::var(key).unwrap_or_default();
+        let mut resolver = Self::new(get_env("APP_ENV")).with_host_suffix(get_env("HOST_SUFFIX"));
+        resolver.overrides = std::env::vars()
+            .filter(|(key, value)| key.ends_with(HOST_OVERRIDE_SUFFIX) && !value.is_empty())
+            .collect();
+        resolver
+    }
+
+    /// Returns the resolved host.
+    pub fn resolve(&self, host: &str) -> String {
+        if let Some(host_override) = self.overrides.get(&host_override_key(host)) {
+            return host_override.clone();
+        }
+        if let Some(host_suffix) = &self.host_suffix {
+            return format!("{host}{host_suffix}");
+        }
+        match self.app_env.as_str() {
+            "local" => "localhost".to_string(),
+            "integration-test" => format!("{host}-integration-test"),
+            _ => host.to_string(),
+        }
+    }
+}
+
+/// Returns the name of a per-service env var, e.g. `AUTH_DB_<SUFFIX>`
+/// for `auth-db`.
+pub fn service_env_key(service_name: &str, suffix: &str) -> String {
+    format!("{}_{suffix}", service_name.to_uppercase().replace('-', "_"))
+}
+
+/// Returns the name of the env var that overrides the given host,
+/// e.g. `AUTH_HOST_OVERRIDE` for `auth`.
+fn host_override_key(host: &str) -> String {
+    service_env_key(host, HOST_OVERRIDE_SUFFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::default(HostResolver::new(""), "auth")]
+    #[case::local(HostResolver::new("local"), "localhost")]
+    #[case::integration_test(HostResolver::new("integration-test"), "auth-integration-test")]
+    #[case::custom_suffix(HostResolver::new("").with_host_suffix("-staging"), "auth-staging")]
+    #[case::custom_suffix_before_app_env(
+        HostResolver::new("local").with_host_suffix("-staging"),
+        "auth-staging"
+    )]
+    #[case::empty_suffix(HostResolver::new("local").with_host_suffix(""), "localhost")]
+    #[case::override_default(
+        HostResolver::new("").with_override("auth", "staging-auth"),
+        "staging-auth"
+    )]
+    #[case::override_before_suffix(
+        HostResolver::new("local")
+            .with_host_suffix("-staging")
+            .with_override("auth", "staging-auth"),
+        "staging-auth"
+    )]
+    #[case::override_other_host(
+        HostResolver::new("local").with_override("user", "staging-user"),
+        "localhost"
+    )]
+    #[case::empty_override(HostResolver::new("local").with_override("auth", ""), "localhost")]
+    fn test_resolve_host(#[case] resolver: HostResolver, #[case] want: &str) {
+        // when
+        let got = resolver.resolve("auth");
+
+        // then
+        assert_eq!(got, want);
+    }
+
+    #[rstest]
+    #[case::simple("auth", "AUTH_HOST_OVERRIDE")]
+    #[case::dashed("auth-db", "AUTH_DB_HOST_OVERRIDE")]
+    fn test_host_override_key(#[case] host: &str, #[case] want: &str) {
+        assert_eq!(host_override_key(host), want);
+    }
+}
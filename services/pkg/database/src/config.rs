@@ -1,4 +1,13 @@
-use std::{env, error::Error};
+use crate::{error::DbError, tls::SslMode};
+use common::host::HostResolver;
+use deadpool_postgres::RecyclingMethod;
+use std::{env, path::PathBuf, str::FromStr, time::Duration};
+
+/// The default maximum number of connections of a pool.
+pub const DEFAULT_MAX_POOL_SIZE: usize = 16;
+
+/// The default time to wait for a connection.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct PGConfig {
@@ -7,32 +16,108 @@ pub struct PGConfig {
     pub(super) password: String,
     pub(super) host: String,
     pub(super) port: u16,
+    /// How connections are checked before they are reused.
+    /// Defaults to [`RecyclingMethod::Fast`].
+    pub(super) recycling_method: RecyclingMethod,
+    /// The maximum number of connections of the pool.
+    ///
+    /// Every replica of every service holds up to this many connections,
+    /// so the sum across them should stay below the `max_connections`
+    /// of Postgres (100 by default), leaving room for admin sessions.
+    pub(super) max_pool_size: usize,
+    /// How long to wait for a connection, either a new one or a free
+    /// one from the pool.
+    pub(super) connect_timeout: Duration,
+    /// Whether the connection is encrypted. Defaults to [`SslMode::Disable`].
+    pub(super) sslmode: SslMode,
+    /// The PEM encoded CA certificate to verify the server against with
+    /// [`SslMode::VerifyFull`], instead of the Mozilla roots.
+    pub(super) ssl_root_cert: Option<PathBuf>,
 }
 
 impl PGConfig {
     /// Load PostgreSQL configuration from environment variables.
     ///
+    /// The pool is tuned by the optional `PG_MAX_POOL_SIZE`,
+    /// `PG_CONNECT_TIMEOUT_SECONDS` and `PG_RECYCLING_METHOD`
+    /// (`fast`, `verified` or `clean`).
+    ///
+    /// TLS is configured by the optional `PG_SSLMODE` (`disable`,
+    /// `require` or `verify-full`) and `PG_SSL_ROOT_CERT`.
+    ///
     /// # Errors
     ///
     /// Returns an error if required environment variables are missing
-    /// or if `PG_PORT` cannot be parsed.
-    pub fn from_env(service_name: &str) -> Result<Self, Box<dyn Error>> {
+    /// or if `PG_PORT` or a pool option cannot be parsed.
+    pub fn from_env(service_name: &str) -> Result<Self, DbError> {
+        let connect_timeout = optional_env("PG_CONNECT_TIMEOUT_SECONDS")?
+            .map_or(DEFAULT_CONNECT_TIMEOUT, Duration::from_secs);
+        let recycling_method = match env::var("PG_RECYCLING_METHOD") {
+            Ok(value) => parse_recycling_method(&value)?,
+            Err(_) => RecyclingMethod::Fast,
+        };
+
         Ok(Self {
             dbname: format!("{service_name}_db"),
-            user: env::var("PG_USER")?,
-            password: env::var("PG_PASSWORD")?,
-            host: patched_host(env::var("PG_HOST")?),
-            port: env::var("PG_PORT")?.parse::<u16>()?,
+            user: required_env("PG_USER")?,
+            password: required_env("PG_PASSWORD")?,
+            host: patched_host(required_env("PG_HOST")?),
+            port: parse_env("PG_PORT", required_env("PG_PORT")?)?,
+            recycling_method,
+            max_pool_size: optional_env("PG_MAX_POOL_SIZE")?.unwrap_or(DEFAULT_MAX_POOL_SIZE),
+            connect_timeout,
+            sslmode: optional_env("PG_SSLMODE")?.unwrap_or_default(),
+            ssl_root_cert: env::var("PG_SSL_ROOT_CERT").ok().map(PathBuf::from),
         })
     }
 }
 
+fn required_env(name: &'static str) -> Result<String, DbError> {
+    env::var(name).map_err(|_| DbError::MissingEnv(name))
+}
+
+fn optional_env<T: FromStr>(name: &'static str) -> Result<Option<T>, DbError> {
+    env::var(name)
+        .ok()
+        .map(|value| parse_env(name, value))
+        .transpose()
+}
+
+fn parse_env<T: FromStr>(name: &'static str, value: String) -> Result<T, DbError> {
+    value
+        .parse()
+        .map_err(|_| DbError::InvalidEnv { name, value })
+}
+
+fn parse_recycling_method(value: &str) -> Result<RecyclingMethod, DbError> {
+    match value {
+        "fast" => Ok(RecyclingMethod::Fast),
+        "verified" => Ok(RecyclingMethod::Verified),
+        "clean" => Ok(RecyclingMethod::Clean),
+        _ => Err(DbError::InvalidEnv {
+            name: "PG_RECYCLING_METHOD",
+            value: value.to_string(),
+        }),
+    }
+}
+
 fn patched_host<S: Into<String>>(host: S) -> String {
-    let host = host.into();
-    let app_env = std::env::var("APP_ENV").unwrap_or_default();
-    match app_env.as_str() {
-        "local" => "localhost".to_string(),
-        "integration-test" => format!("{host}-integration-test"),
-        _ => host,
+    HostResolver::from_env().resolve(&host.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recycling_method() {
+        assert!(matches!(
+            parse_recycling_method("verified"),
+            Ok(RecyclingMethod::Verified)
+        ));
+        assert!(matches!(
+            parse_recycling_method("slow"),
+            Err(DbError::InvalidEnv { value, .. }) if value == "slow"
+        ));
     }
 }
@@ -1,31 +1,116 @@
 use std::error::Error;
 
 use super::config::PGConfig;
-use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
-use tokio_postgres::NoTls;
+use crate::{
+    error::DbError,
+    tls::{SslMode, make_tls_connector},
+};
+use deadpool_postgres::{Manager, ManagerConfig, Pool, Runtime};
+use tokio_postgres::{NoTls, config::SslMode as PgSslMode};
 
 /// Create a PostgreSQL connection pool.
 ///
+/// The connection is encrypted unless the [`SslMode`] is `disable`.
+///
 /// # Errors
 ///
 /// Returns an error if the connection pool cannot be created.
-pub fn connect(cfg: &PGConfig) -> Result<Pool, Box<dyn Error>> {
+pub fn connect(cfg: &PGConfig) -> Result<Pool, DbError> {
     let mut pg = tokio_postgres::Config::new();
     pg.dbname(&cfg.dbname)
         .user(&cfg.user)
         .password(&cfg.password)
         .host(&cfg.host)
-        .port(cfg.port);
+        .port(cfg.port)
+        .connect_timeout(cfg.connect_timeout)
+        .ssl_mode(match cfg.sslmode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Require | SslMode::VerifyFull => PgSslMode::Require,
+        });
+
+    let manager_config = ManagerConfig {
+        recycling_method: cfg.recycling_method.clone(),
+    };
+    let manager = match make_tls_connector(cfg.sslmode, cfg.ssl_root_cert.as_deref())? {
+        Some(tls) => Manager::from_config(pg, tls, manager_config),
+        None => Manager::from_config(pg, NoTls, manager_config),
+    };
+
+    let pool = Pool::builder(manager)
+        .max_size(cfg.max_pool_size)
+        .runtime(Runtime::Tokio1)
+        .create_timeout(Some(cfg.connect_timeout))
+        .wait_timeout(Some(cfg.connect_timeout))
+        .build()?;
+
+    Ok(pool)
+}
+
+/// Checks that the database is reachable by running a trivial query.
+///
+/// # Errors
+///
+/// Returns an error if no connection can be established or the query fails.
+pub async fn ping(pool: Pool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let client = pool.get().await?;
+    client.simple_query("SELECT 1").await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deadpool_postgres::RecyclingMethod;
+    use std::time::Duration;
+
+    #[test]
+    fn test_connect_max_pool_size() {
+        // given
+        let cfg = PGConfig {
+            dbname: String::from("test_db"),
+            user: String::from("postgres"),
+            password: String::from("postgres"),
+            host: String::from("localhost"),
+            port: 5432,
+            recycling_method: RecyclingMethod::Fast,
+            max_pool_size: 3,
+            connect_timeout: Duration::from_secs(1),
+            sslmode: SslMode::Disable,
+            ssl_root_cert: None,
+        };
+
+        // when
+        let pool = connect(&cfg).unwrap();
 
-    let manager = Manager::from_config(
-        pg,
-        NoTls,
-        ManagerConfig {
+        // then
+        let status = pool.status();
+        assert_eq!(status.max_size, 3);
+        assert_eq!(status.size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_connect_without_tls() {
+        // given
+        let (host, port) = testutils::get_test_db_address()
+            .await
+            .expect("failed to start test db");
+        let cfg = PGConfig {
+            dbname: String::from("dummy_db"),
+            user: String::from("postgres"),
+            password: String::from("postgres"),
+            host,
+            port,
             recycling_method: RecyclingMethod::Fast,
-        },
-    );
+            max_pool_size: 1,
+            connect_timeout: Duration::from_secs(5),
+            sslmode: SslMode::Disable,
+            ssl_root_cert: None,
+        };
+
+        // when
+        let got = ping(connect(&cfg).unwrap()).await;
 
-    Pool::builder(manager)
-        .build()
-        .map_err(|e| format!("failed to connect to postgres: {e}").into())
+        // then
+        assert!(got.is_ok(), "{got:?}");
+    }
 }
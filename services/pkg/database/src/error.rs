@@ -0,0 +1,20 @@
+use deadpool_postgres::BuildError;
+
+/// An error while configuring or connecting to the database.
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    #[error("missing env var {0}")]
+    MissingEnv(&'static str),
+
+    #[error("invalid env var {name}: {value}")]
+    InvalidEnv { name: &'static str, value: String },
+
+    #[error("failed to connect to postgres: {0}")]
+    Build(#[from] BuildError),
+
+    #[error("failed to load the root certificate: {0}")]
+    RootCert(String),
+
+    #[error("failed to configure tls: {0}")]
+    Tls(#[from] rustls::Error),
+}
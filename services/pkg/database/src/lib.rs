@@ -1,6 +1,10 @@
 pub mod config;
 pub mod connect;
+pub mod error;
 pub mod migration;
+pub mod tls;
 
 pub use config::PGConfig;
-pub use connect::connect;
+pub use connect::{connect, ping};
+pub use error::DbError;
+pub use tls::SslMode;
@@ -1,3 +1,44 @@
+use deadpool_postgres::{Pool, PoolError};
+use refinery::{AsyncMigrate, Error, Migration, Runner};
+use std::{ops::DerefMut, path::Path};
+
+/// The environment variable that makes [`run_migrations!`] only verify
+/// the applied migrations instead of applying pending ones.
+pub const VERIFY_MIGRATIONS_ONLY_ENV: &str = "DB_VERIFY_MIGRATIONS_ONLY";
+
+/// An error while verifying the applied migrations.
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("failed to load migrations: {0}")]
+    Load(#[source] Error),
+
+    #[error("failed to get db connection: {0}")]
+    Connection(#[from] PoolError),
+
+    #[error("failed to read the migration history: {0}")]
+    History(#[source] Error),
+
+    #[error(
+        "migration {name} was changed after it was applied: applied checksum {applied}, local checksum {local}"
+    )]
+    Divergent {
+        name: String,
+        applied: u64,
+        local: u64,
+    },
+
+    #[error("applied migration {0} is missing locally")]
+    Missing(String),
+}
+
+/// Embeds the migrations of `$migrations_folder` and applies them.
+///
+/// If a migration fails, the applied and pending migrations are logged
+/// together with the failing one, and the error is returned.
+///
+/// With [`VERIFY_MIGRATIONS_ONLY_ENV`] set, nothing is applied. Instead
+/// the applied migrations are checked against the embedded ones, e.g.
+/// in production where migrations run as a separate step.
 #[macro_export]
 macro_rules! run_migrations {
     ($pool:expr, $migrations_folder:literal) => {{
@@ -8,14 +49,252 @@ macro_rules! run_migrations {
             .await
             .map_err(|e| format!("failed to apply database migrations: get db connection: {e}"))?;
         let client = conn.deref_mut().deref_mut();
-        let migration_report = migrations::runner().run_async(client).await?;
-
-        for migration in migration_report.applied_migrations() {
-            println!(
-                "Migration Applied: V{}_{}",
-                migration.version(),
-                migration.name(),
-            );
+        let runner = migrations::runner();
+        if $crate::migration::verify_only_from_env() {
+            $crate::migration::verify_applied_migrations(client, runner.get_migrations()).await?;
+        } else {
+            let migration_report = match runner.run_async(client).await {
+                Ok(report) => report,
+                Err(err) => {
+                    $crate::migration::log_migration_error(&runner, client, &err).await;
+                    return Err(err.into());
+                }
+            };
+
+            for migration in migration_report.applied_migrations() {
+                println!(
+                    "Migration Applied: V{}_{}",
+                    migration.version(),
+                    migration.name(),
+                );
+            }
         }
     }};
 }
+
+/// Returns whether [`VERIFY_MIGRATIONS_ONLY_ENV`] is set to `true` or `1`.
+pub fn verify_only_from_env() -> bool {
+    std::env::var(VERIFY_MIGRATIONS_ONLY_ENV).is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// Checks that the migrations applied to the database match the ones
+/// in `path`.
+///
+/// # Errors
+///
+/// Returns an error if an applied migration was changed or removed
+/// locally, or if the migrations or the history cannot be read.
+pub async fn verify_migrations(pool: &Pool, path: impl AsRef<Path>) -> Result<(), MigrationError> {
+    let migrations = refinery::load_sql_migrations(path).map_err(MigrationError::Load)?;
+    let mut conn = pool.get().await?;
+    verify_applied_migrations(conn.deref_mut().deref_mut(), &migrations).await
+}
+
+/// Checks that the migrations in the `refinery_schema_history` table
+/// match `migrations`. Pending migrations are not an error.
+///
+/// # Errors
+///
+/// See [`verify_migrations`].
+pub async fn verify_applied_migrations<C>(
+    client: &mut C,
+    migrations: &[Migration],
+) -> Result<(), MigrationError>
+where
+    C: AsyncMigrate + Send,
+{
+    let applied = Runner::new(migrations)
+        .get_applied_migrations_async(client)
+        .await
+        .map_err(MigrationError::History)?;
+    compare_checksums(migrations, &applied)
+}
+
+/// Compares the checksum of each applied migration with the local one
+/// of the same version.
+fn compare_checksums(
+    migrations: &[Migration],
+    applied: &[Migration],
+) -> Result<(), MigrationError> {
+    for applied in applied {
+        let Some(local) = migrations.iter().find(|m| m.version() == applied.version()) else {
+            return Err(MigrationError::Missing(applied.to_string()));
+        };
+        if local.checksum() != applied.checksum() {
+            return Err(MigrationError::Divergent {
+                name: local.to_string(),
+                applied: applied.checksum(),
+                local: local.checksum(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The migrations of a service after a failed migration run.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// The migrations that are applied to the database.
+    pub applied: Vec<String>,
+    /// The migrations that are not applied yet.
+    pub pending: Vec<String>,
+    /// The migration that failed to apply, if the run got that far.
+    pub failed: Option<String>,
+}
+
+impl MigrationStatus {
+    /// Splits `migrations` into applied and pending ones.
+    ///
+    /// Refinery applies migrations in order, so if `err` was raised while
+    /// applying a migration, the failing one is the first pending one.
+    pub fn new(migrations: &[Migration], applied: &[Migration], err: &Error) -> Self {
+        let (applied, pending): (Vec<_>, Vec<_>) = migrations
+            .iter()
+            .partition(|m| applied.iter().any(|a| a.version() == m.version()));
+        let failed = err
+            .report()
+            .and_then(|_| pending.first())
+            .map(ToString::to_string);
+
+        Self {
+            applied: applied.iter().map(ToString::to_string).collect(),
+            pending: pending.iter().map(ToString::to_string).collect(),
+            failed,
+        }
+    }
+}
+
+/// Logs the [`MigrationStatus`] after `err` aborted a migration run.
+///
+/// The applied migrations are read from the database, falling back to
+/// the ones applied by the aborted run if the database is unreachable.
+pub async fn log_migration_error<C>(runner: &Runner, client: &mut C, err: &Error)
+where
+    C: AsyncMigrate + Send,
+{
+    let applied = match runner.get_applied_migrations_async(client).await {
+        Ok(applied) => applied,
+        Err(_) => err
+            .report()
+            .map(|report| report.applied_migrations().clone())
+            .unwrap_or_default(),
+    };
+    let status = MigrationStatus::new(runner.get_migrations(), &applied, err);
+
+    tracing::error!(
+        applied = ?status.applied,
+        pending = ?status.pending,
+        failed = ?status.failed,
+        "failed to apply database migrations: {err}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use refinery::error::WrapMigrationError;
+
+    const BROKEN_MIGRATIONS: &str = "tests/migrations/broken";
+    const VALID_MIGRATIONS: &str = "tests/migrations/valid";
+
+    /// Returns the error refinery raises when `failed` cannot be applied
+    /// after `applied` were applied in the same run.
+    fn apply_error(failed: &Migration, applied: &[Migration]) -> Error {
+        let err = std::io::Error::other("syntax error at or near \";\"");
+        Err::<(), _>(err)
+            .migration_err(&format!("error applying migration {failed}"), Some(applied))
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_migration_status_failed_migration() {
+        // given
+        let migrations = refinery::load_sql_migrations(BROKEN_MIGRATIONS).unwrap();
+        let applied = &migrations[..1];
+        let err = apply_error(&migrations[1], applied);
+
+        // when
+        let got = MigrationStatus::new(&migrations, applied, &err);
+
+        // then
+        assert_eq!(
+            got,
+            MigrationStatus {
+                applied: vec![String::from("V1__create_items_table")],
+                pending: vec![
+                    String::from("V2__add_items_price"),
+                    String::from("V3__create_items_name_index"),
+                ],
+                failed: Some(String::from("V2__add_items_price")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_migration_status_before_applying() {
+        // given
+        let migrations = refinery::load_sql_migrations(BROKEN_MIGRATIONS).unwrap();
+        let err = Err::<(), _>(std::io::Error::other("connection refused"))
+            .migration_err("error asserting migrations table", None)
+            .unwrap_err();
+
+        // when
+        let got = MigrationStatus::new(&migrations, &[], &err);
+
+        // then
+        assert_eq!(got.applied, Vec::<String>::new());
+        assert_eq!(got.pending.len(), 3);
+        assert_eq!(got.failed, None);
+    }
+
+    #[test]
+    fn test_compare_checksums() {
+        // given
+        let broken = refinery::load_sql_migrations(BROKEN_MIGRATIONS).unwrap();
+        let valid = refinery::load_sql_migrations(VALID_MIGRATIONS).unwrap();
+
+        // when
+        let pending = compare_checksums(&broken, &broken[..1]);
+        let missing = compare_checksums(&broken[..1], &broken);
+        let divergent = compare_checksums(&valid, &broken[..1]);
+
+        // then
+        assert!(pending.is_ok());
+        assert!(
+            matches!(missing, Err(MigrationError::Missing(name)) if name == "V2__add_items_price")
+        );
+        assert!(matches!(
+            divergent,
+            Err(MigrationError::Divergent { name, .. }) if name == "V1__create_notes_table"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_migrations_detects_changed_migration() {
+        // given
+        let migrations = std::fs::canonicalize(VALID_MIGRATIONS).unwrap();
+        let pool = testutils::get_test_db("dummy", &migrations)
+            .await
+            .expect("failed to get connection to test db");
+        verify_migrations(&pool, &migrations)
+            .await
+            .expect("unchanged migrations should verify");
+
+        // when
+        let client = pool.get().await.unwrap();
+        client
+            .execute(
+                "UPDATE refinery_schema_history SET checksum = '0' WHERE version = 1",
+                &[],
+            )
+            .await
+            .unwrap();
+        let got = verify_migrations(&pool, &migrations).await;
+
+        // then
+        assert!(matches!(
+            got,
+            Err(MigrationError::Divergent { applied: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,164 @@
+//! TLS for the connection to Postgres.
+use crate::error::DbError;
+use rustls::{
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, ring, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime, pem::PemObject},
+};
+use std::{path::Path, str::FromStr, sync::Arc};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+/// Whether and how the connection to Postgres is encrypted, named after
+/// the `sslmode` of libpq.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SslMode {
+    /// Connect without TLS.
+    #[default]
+    Disable,
+    /// Require TLS, but accept any server certificate.
+    Require,
+    /// Require TLS and a server certificate that is signed by a trusted
+    /// CA and issued for the host.
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disable" => Ok(Self::Disable),
+            "require" => Ok(Self::Require),
+            "verify-full" => Ok(Self::VerifyFull),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Returns the TLS connector for `mode`, or `None` if TLS is disabled.
+///
+/// With [`SslMode::VerifyFull`] the server certificate is verified
+/// against the PEM encoded `root_cert`, or the Mozilla roots if unset.
+pub(crate) fn make_tls_connector(
+    mode: SslMode,
+    root_cert: Option<&Path>,
+) -> Result<Option<MakeRustlsConnect>, DbError> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let config = match mode {
+        SslMode::Disable => return Ok(None),
+        SslMode::Require => builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+            .with_no_client_auth(),
+        SslMode::VerifyFull => builder
+            .with_root_certificates(root_cert_store(root_cert)?)
+            .with_no_client_auth(),
+    };
+
+    Ok(Some(MakeRustlsConnect::new(config)))
+}
+
+fn root_cert_store(root_cert: Option<&Path>) -> Result<RootCertStore, DbError> {
+    let Some(path) = root_cert else {
+        return Ok(RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        });
+    };
+
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(path).map_err(|e| DbError::RootCert(e.to_string()))? {
+        let cert = cert.map_err(|e| DbError::RootCert(e.to_string()))?;
+        roots.add(cert)?;
+    }
+    Ok(roots)
+}
+
+/// Accepts any server certificate, which still encrypts the connection
+/// but does not protect against a man in the middle.
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::disable("disable", Ok(SslMode::Disable))]
+    #[case::require("require", Ok(SslMode::Require))]
+    #[case::verify_full("verify-full", Ok(SslMode::VerifyFull))]
+    #[case::unknown("prefer", Err(()))]
+    fn test_parse_ssl_mode(#[case] value: &str, #[case] want: Result<SslMode, ()>) {
+        assert_eq!(value.parse::<SslMode>(), want);
+    }
+
+    #[rstest]
+    #[case::disable(SslMode::Disable, false)]
+    #[case::require(SslMode::Require, true)]
+    #[case::verify_full(SslMode::VerifyFull, true)]
+    fn test_make_tls_connector(#[case] mode: SslMode, #[case] want_tls: bool) {
+        // when
+        let got = make_tls_connector(mode, None).unwrap();
+
+        // then
+        assert_eq!(got.is_some(), want_tls);
+    }
+
+    #[test]
+    fn test_make_tls_connector_missing_root_cert() {
+        // when
+        let got = make_tls_connector(SslMode::VerifyFull, Some(Path::new("missing.pem")));
+
+        // then
+        assert!(matches!(got, Err(DbError::RootCert(_))));
+    }
+}
@@ -0,0 +1,4 @@
+CREATE TABLE items (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Deliberately broken: the column type is missing.
+ALTER TABLE items ADD COLUMN price;
@@ -0,0 +1,4 @@
+CREATE TABLE notes (
+    id UUID PRIMARY KEY,
+    body TEXT NOT NULL
+);
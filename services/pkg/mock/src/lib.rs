@@ -19,29 +19,60 @@
 //!
 //! // Generates:
 //! // pub struct MockDBClient {
-//! //     pub get_user: Mutex<Option<Result<User, DBError>>>,
+//! //     pub get_user: Mutex<VecDeque<Result<User, DBError>>>,
 //! //     pub get_user_call_count: AtomicUsize,
-//! //     pub insert_user: Mutex<Option<Result<(), DBError>>>,
+//! //     pub insert_user: Mutex<VecDeque<Result<(), DBError>>>,
 //! //     pub insert_user_call_count: AtomicUsize,
 //! // }
 //! // impl Default for MockDBClient { ... }
+//! // impl MockDBClient { fn expect_get_user(..), fn get_user_calls(..), ... }
 //! // #[async_trait] impl DBClient for MockDBClient { ... }
 //! ```
 //!
+//! ## Configuring Return Values
+//!
+//! Successive calls return successive values. A call beyond the configured
+//! values panics with "<method> called more times than configured".
+//!
+//! ```ignore
+//! let mut mock = MockDBClient::default();
+//! mock.expect_get_user([Ok(user1), Ok(user2)]);
+//! ```
+//!
 //! ## Checking Call Counts in Tests
 //!
 //! ```ignore
 //! assert_eq!(mock.delete_session_calls(), 2);
 //! ```
+//!
+//! ## Falling Back to Default Values
+//!
+//! With `#[mock::db_client(default_on_unset)]`, methods returning `Result<T, E>`
+//! return `Ok(T::default())` instead of panicking when no value is left.
+//! Methods with other return types still panic.
+//!
+//! Methods without a return type, e.g. `async fn touch(&self)`, need no
+//! configuration and only count their calls.
 
 use proc_macro::TokenStream;
 use quote::ToTokens;
 use quote::{format_ident, quote};
-use syn::{FnArg, ItemTrait, ReturnType, TraitItem, parse_macro_input};
+use syn::{FnArg, Ident, ItemTrait, ReturnType, TraitItem, Type, parse_macro_input};
 
 /// Generates a mock implementation for an async trait.
 #[proc_macro_attribute]
-pub fn db_client(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn db_client(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut default_on_unset = false;
+    if !attr.is_empty() {
+        let option = parse_macro_input!(attr as Ident);
+        if option != "default_on_unset" {
+            return syn::Error::new(option.span(), "expected `default_on_unset`")
+                .to_compile_error()
+                .into();
+        }
+        default_on_unset = true;
+    }
+
     let input = parse_macro_input!(item as ItemTrait);
     let trait_name = &input.ident;
     let mock_name = format_ident!("Mock{}", trait_name);
@@ -50,6 +81,7 @@ pub fn db_client(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut field_definitions = Vec::new();
     let mut default_fields = Vec::new();
     let mut impl_methods = Vec::new();
+    let mut expect_methods = Vec::new();
     let mut call_count_methods = Vec::new();
 
     for item in &input.items {
@@ -57,28 +89,51 @@ pub fn db_client(_attr: TokenStream, item: TokenStream) -> TokenStream {
             let method_name = &method.sig.ident;
             let call_count_field = format_ident!("{}_call_count", method_name);
             let call_count_method = format_ident!("{}_calls", method_name);
+            let expect_method = format_ident!("expect_{}", method_name);
+            let exhausted_msg = format!("{method_name} called more times than configured");
 
+            let returns_unit = matches!(method.sig.output, ReturnType::Default);
             let return_type = match &method.sig.output {
                 ReturnType::Default => quote! { () },
                 ReturnType::Type(_, ty) => quote! { #ty },
             };
 
-            field_definitions.push(quote! {
-                pub #method_name: ::tokio::sync::Mutex<::std::option::Option<#return_type>>
-            });
+            let on_unset = if default_on_unset && returns_result(&method.sig.output) {
+                quote! { ::std::result::Result::Ok(::std::default::Default::default()) }
+            } else {
+                quote! { panic!(#exhausted_msg) }
+            };
 
             field_definitions.push(quote! {
                 pub #call_count_field: ::std::sync::atomic::AtomicUsize
             });
 
-            default_fields.push(quote! {
-                #method_name: ::tokio::sync::Mutex::new(::std::option::Option::None)
-            });
-
             default_fields.push(quote! {
                 #call_count_field: ::std::sync::atomic::AtomicUsize::new(0)
             });
 
+            // Methods without a return value need no configuration.
+            if !returns_unit {
+                field_definitions.push(quote! {
+                    pub #method_name: ::tokio::sync::Mutex<::std::collections::VecDeque<#return_type>>
+                });
+
+                default_fields.push(quote! {
+                    #method_name: ::tokio::sync::Mutex::new(::std::collections::VecDeque::new())
+                });
+
+                expect_methods.push(quote! {
+                    /// Sets the values returned by successive calls.
+                    pub fn #expect_method(
+                        &mut self,
+                        values: impl ::std::iter::IntoIterator<Item = #return_type>,
+                    ) -> &mut Self {
+                        *self.#method_name.get_mut() = values.into_iter().collect();
+                        self
+                    }
+                });
+            }
+
             call_count_methods.push(quote! {
                 pub fn #call_count_method(&self) -> usize {
                     self.#call_count_field.load(::std::sync::atomic::Ordering::SeqCst)
@@ -101,10 +156,22 @@ pub fn db_client(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 })
                 .collect();
 
+            let body = if returns_unit {
+                quote! {}
+            } else {
+                quote! {
+                    self.#method_name
+                        .lock()
+                        .await
+                        .pop_front()
+                        .unwrap_or_else(|| #on_unset)
+                }
+            };
+
             impl_methods.push(quote! {
                 async fn #method_name(&self, #(#params),*) -> #return_type {
                     self.#call_count_field.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
-                    self.#method_name.lock().await.take().unwrap()
+                    #body
                 }
             });
         }
@@ -126,6 +193,7 @@ pub fn db_client(_attr: TokenStream, item: TokenStream) -> TokenStream {
         }
 
         impl #mock_name {
+            #(#expect_methods)*
             #(#call_count_methods)*
         }
 
@@ -137,3 +205,17 @@ pub fn db_client(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// Returns whether the return type is a `Result`.
+fn returns_result(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let Type::Path(path) = ty.as_ref() else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Result")
+}
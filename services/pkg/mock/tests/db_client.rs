@@ -0,0 +1,82 @@
+use tonic::async_trait;
+
+#[derive(Debug, PartialEq)]
+struct DBError;
+
+#[mock::db_client]
+#[async_trait]
+trait DBClient: Send + Sync + 'static {
+    async fn get_user(&self, id: &str) -> Result<String, DBError>;
+    async fn touch(&self);
+}
+
+#[tokio::test]
+async fn test_successive_calls_return_successive_values() {
+    // given
+    let mut mock = MockDBClient::default();
+    mock.expect_get_user([Ok(String::from("user1")), Err(DBError)]);
+
+    // when
+    let first = mock.get_user("id").await;
+    let second = mock.get_user("id").await;
+
+    // then
+    assert_eq!(first, Ok(String::from("user1")));
+    assert_eq!(second, Err(DBError));
+    assert_eq!(mock.get_user_calls(), 2);
+}
+
+#[tokio::test]
+#[should_panic(expected = "get_user called more times than configured")]
+async fn test_exhausted_values_panic() {
+    // given
+    let mut mock = MockDBClient::default();
+    mock.expect_get_user([Ok(String::from("user1"))]);
+
+    // when
+    let _ = mock.get_user("id").await;
+    let _ = mock.get_user("id").await;
+}
+
+#[tokio::test]
+async fn test_unit_method_needs_no_configuration() {
+    // given
+    let mock = MockDBClient::default();
+
+    // when
+    mock.touch().await;
+    mock.touch().await;
+
+    // then
+    assert_eq!(mock.touch_calls(), 2);
+}
+
+mod default_on_unset {
+    use super::DBError;
+    use tonic::async_trait;
+
+    #[mock::db_client(default_on_unset)]
+    #[async_trait]
+    trait DBClient: Send + Sync + 'static {
+        async fn get_user(&self, id: &str) -> Result<String, DBError>;
+        async fn delete_user(&self, id: &str) -> Result<(), DBError>;
+    }
+
+    #[tokio::test]
+    async fn test_unset_method_returns_default() {
+        // given
+        let mut mock = MockDBClient::default();
+        mock.expect_get_user([Err(DBError)]);
+
+        // when
+        let first = mock.get_user("id").await;
+        let second = mock.get_user("id").await;
+        let deleted = mock.delete_user("id").await;
+
+        // then
+        assert_eq!(first, Err(DBError));
+        assert_eq!(second, Ok(String::new()));
+        assert_eq!(deleted, Ok(()));
+        assert_eq!(mock.delete_user_calls(), 1);
+    }
+}
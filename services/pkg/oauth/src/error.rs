@@ -11,6 +11,18 @@ pub enum Error {
     #[error("failed to send request")]
     SendRequest(#[from] reqwest::Error),
 
+    #[error("response body exceeds {0} bytes")]
+    ResponseTooLarge(usize),
+
+    #[error("failed to deserialize response: {0}")]
+    DeserializeResponse(#[from] serde_json::Error),
+
+    #[error("failed to deserialize form response: {0}")]
+    DeserializeFormResponse(#[from] serde_urlencoded::de::Error),
+
+    #[error("oauth provider returned {code}: {description}")]
+    OAuthProviderError { code: String, description: String },
+
     #[error("failed to validate authorization code")]
     ValidateAuthorizationCode,
 
@@ -20,6 +32,12 @@ pub enum Error {
     #[error("failed to decode id token")]
     DecodeIdToken(#[from] jsonwebtoken::errors::Error),
 
+    #[error("id token issuer is not expected")]
+    IssuerMismatch,
+
+    #[error("id token nonce does not match")]
+    NonceMismatch,
+
     #[error("missing kid in token")]
     MissingKID,
 
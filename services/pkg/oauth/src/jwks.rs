@@ -0,0 +1,159 @@
+use reqwest::{
+    Client,
+    header::{CACHE_CONTROL, HeaderMap},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{error::Error, models::Jwks};
+
+/// How long a JWKS is cached if the response has no `max-age`.
+pub const DEFAULT_JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A cached key set and the time it goes stale.
+#[derive(Debug)]
+struct CachedJwks {
+    jwks: Arc<Jwks>,
+    expires_at: Instant,
+}
+
+/// Time-bounded in-memory cache of JSON Web Key Sets, keyed by endpoint.
+///
+/// An entry lives as long as the `Cache-Control: max-age` of the response
+/// says, or for the default TTL if the response has none. Cloning is cheap
+/// and shares the cache.
+#[derive(Debug, Clone)]
+pub struct JwksCache {
+    entries: Arc<Mutex<HashMap<String, CachedJwks>>>,
+    default_ttl: Duration,
+}
+
+impl Default for JwksCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_JWKS_TTL)
+    }
+}
+
+impl JwksCache {
+    /// Creates an empty cache that falls back to `default_ttl`.
+    #[must_use]
+    pub fn new(default_ttl: Duration) -> Self {
+        Self {
+            entries: Arc::default(),
+            default_ttl,
+        }
+    }
+
+    /// Returns the key set of an endpoint, fetching it if it is not
+    /// cached, stale, or if `force_refresh` is set.
+    pub(crate) async fn get(
+        &self,
+        client: &Client,
+        endpoint: &str,
+        force_refresh: bool,
+    ) -> Result<Arc<Jwks>, Error> {
+        if !force_refresh && let Some(jwks) = self.cached(endpoint, Instant::now()) {
+            return Ok(jwks);
+        }
+
+        let response = client.get(endpoint).send().await?;
+        let ttl = max_age(response.headers()).unwrap_or(self.default_ttl);
+        let jwks = Arc::new(response.json::<Jwks>().await?);
+
+        self.store(endpoint, Arc::clone(&jwks), Instant::now() + ttl);
+        Ok(jwks)
+    }
+
+    /// Returns the cached key set of an endpoint if it is still fresh at `now`.
+    fn cached(&self, endpoint: &str, now: Instant) -> Option<Arc<Jwks>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(endpoint)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| Arc::clone(&entry.jwks))
+    }
+
+    /// Caches the key set of an endpoint until `expires_at`.
+    fn store(&self, endpoint: &str, jwks: Arc<Jwks>, expires_at: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(endpoint.to_string(), CachedJwks { jwks, expires_at });
+    }
+}
+
+/// Parses the `max-age` directive of a `Cache-Control` header.
+///
+/// `no-cache` and `no-store` yield a zero duration.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(CACHE_CONTROL)?.to_str().ok()?;
+    let mut max_age = None;
+    for directive in value.split(',').map(str::trim) {
+        if directive.eq_ignore_ascii_case("no-cache") || directive.eq_ignore_ascii_case("no-store")
+        {
+            return Some(Duration::ZERO);
+        }
+        if let Some((name, secs)) = directive.split_once('=')
+            && name.trim().eq_ignore_ascii_case("max-age")
+        {
+            max_age = secs.trim().trim_matches('"').parse().ok();
+        }
+    }
+    max_age.map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::max_age("public, max-age=19937, must-revalidate, no-transform", Some(19937))]
+    #[case::quoted("max-age=\"60\"", Some(60))]
+    #[case::case_insensitive("Max-Age=60", Some(60))]
+    #[case::no_store("no-store, max-age=60", Some(0))]
+    #[case::no_cache("no-cache", Some(0))]
+    #[case::no_max_age("public", None)]
+    #[case::invalid_max_age("max-age=abc", None)]
+    fn test_max_age(#[case] header: &str, #[case] want: Option<u64>) {
+        // given
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_str(header).unwrap());
+
+        // when
+        let got = max_age(&headers);
+
+        // then
+        assert_eq!(got, want.map(Duration::from_secs));
+    }
+
+    #[test]
+    fn test_max_age_missing_header() {
+        assert_eq!(max_age(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_cached_until_expiry() {
+        // given
+        let cache = JwksCache::default();
+        let now = Instant::now();
+        let jwks = Arc::new(Jwks { keys: Vec::new() });
+        cache.store(
+            "https://example.com/jwks",
+            jwks,
+            now + Duration::from_secs(60),
+        );
+
+        // when
+        let fresh = cache.cached("https://example.com/jwks", now);
+        let stale = cache.cached("https://example.com/jwks", now + Duration::from_secs(60));
+        let other = cache.cached("https://example.com/other", now);
+
+        // then
+        assert!(fresh.is_some());
+        assert!(stale.is_none());
+        assert!(other.is_none());
+    }
+}
@@ -1,12 +1,19 @@
 mod error;
+mod jwks;
 mod models;
 mod oauth;
 mod random;
 pub use error::Error;
+pub use jwks::DEFAULT_JWKS_TTL;
+pub use jwks::JwksCache;
+pub use models::OAuth2Token;
+pub use oauth::ClientAuthMethod;
 pub use oauth::OAuth;
 pub use oauth::OAuthProvider;
 pub use random::RandomSource;
 pub use random::SecureRandom;
+#[cfg(feature = "seeded")]
+pub use random::SeededRandom;
 
 #[cfg(feature = "mock")]
 pub use random::mock;
@@ -1,5 +1,49 @@
 use serde::Deserialize;
 
+use crate::error::Error;
+
+/// A token endpoint response.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OAuth2Token {
+    pub access_token: Option<String>,
+    /// Lifetime of the access token in seconds.
+    pub expires_in: Option<u64>,
+    /// Only returned by some providers, and often only on the first login.
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    pub token_type: Option<String>,
+    pub id_token: Option<String>,
+}
+
+/// An error response of a token endpoint, see RFC 6749, section 5.2.
+#[derive(Debug, Deserialize)]
+pub(crate) struct OAuthErrorResponse {
+    /// Error code such as `invalid_grant`.
+    pub(crate) error: String,
+    /// Human readable description of the error.
+    pub(crate) error_description: Option<String>,
+}
+
+/// A token endpoint response, which is either the token or an error.
+#[derive(Debug)]
+pub(crate) enum TokenResponse<T> {
+    Token(T),
+    Error(OAuthErrorResponse),
+}
+
+impl<T> TokenResponse<T> {
+    /// Returns the token, or the provider's error.
+    pub(crate) fn into_result(self) -> Result<T, Error> {
+        match self {
+            Self::Token(token) => Ok(token),
+            Self::Error(e) => Err(Error::OAuthProviderError {
+                code: e.error,
+                description: e.error_description.unwrap_or_default(),
+            }),
+        }
+    }
+}
+
 /// Represents the possible formats for the `aud` (audience) claim.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
@@ -32,6 +76,9 @@ pub struct OidcTokenClaims {
     /// Issued-at time (UNIX timestamp).
     pub iat: Option<u64>,
 
+    /// The nonce sent with the authorization request, to prevent replays.
+    pub nonce: Option<String>,
+
     /// Authorized party (client_id of the relying party).
     pub azp: Option<String>,
 
@@ -1,37 +1,112 @@
 use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, errors::ErrorKind};
 use reqwest::{
-    Client,
+    Client, Response,
     header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
     redirect::Policy,
 };
 use serde::de::DeserializeOwned;
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, marker::PhantomData};
+use std::{collections::HashMap, marker::PhantomData, time::Duration};
 use tonic::async_trait;
 use url::Url;
 
 use crate::{
     error::Error,
-    models::{Jwks, OidcTokenClaims},
+    jwks::JwksCache,
+    models::{Jwk, Jwks, OAuth2Token, OAuthErrorResponse, OidcTokenClaims, TokenResponse},
     random::RandomSource,
 };
 
+/// Maximum size of a token endpoint response body.
+const MAX_RESPONSE_BODY_SIZE: usize = 256 * 1024;
+
+/// Timeout for requests to a token endpoint.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How the client authenticates at a token endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientAuthMethod {
+    /// Client id and secret in the `Authorization` header (`client_secret_basic`).
+    #[default]
+    Basic,
+    /// Client id and secret in the request body (`client_secret_post`).
+    Post,
+}
+
 /// Generic OAuth 2.0 helper that abstracts PKCE, authorization URL creation, and token validation.
-#[derive(Default, Clone)]
+///
+/// Holds a single http client so that connections to the provider are
+/// pooled across requests, and a cache of the provider's signing keys.
+/// Cloning is cheap and shares both.
 pub struct OAuth<R> {
+    client: Client,
+    auth_method: ClientAuthMethod,
+    jwks: JwksCache,
     _phantom: PhantomData<R>,
 }
 
-impl<R: RandomSource> OAuth<R> {
+impl<R> Clone for OAuth<R> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            auth_method: self.auth_method,
+            jwks: self.jwks.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<R> Default for OAuth<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> OAuth<R> {
     /// Creates a new `OAuth` helper for a given random source.
-    #[inline]
+    ///
+    /// Redirects are not followed, so that a token endpoint cannot
+    /// forward the client credentials elsewhere.
+    ///
+    /// # Panics
+    /// - the TLS backend cannot be initialized, like [`Client::new`]
     pub fn new() -> Self {
+        let client = Client::builder()
+            .redirect(Policy::none())
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build http client");
         Self {
+            client,
+            auth_method: ClientAuthMethod::default(),
+            jwks: JwksCache::default(),
             _phantom: PhantomData,
         }
     }
 
+    /// Sets how the client authenticates at the token endpoint.
+    #[must_use]
+    pub fn with_client_auth(mut self, auth_method: ClientAuthMethod) -> Self {
+        self.auth_method = auth_method;
+        self
+    }
+
+    /// Sets the cache used for the provider's signing keys.
+    #[must_use]
+    pub fn with_jwks_cache(mut self, jwks: JwksCache) -> Self {
+        self.jwks = jwks;
+        self
+    }
+
+    /// Returns the shared http client, e.g. to call a provider's user api.
+    #[must_use]
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl<R: RandomSource> OAuth<R> {
     /// Generates the OAuth `state` (CSRF protection token).
     #[must_use]
     pub fn generate_state() -> String {
@@ -44,6 +119,12 @@ impl<R: RandomSource> OAuth<R> {
         R::base64_url(32)
     }
 
+    /// Generates an OpenID Connect `nonce`, which the provider echoes in the ID token.
+    #[must_use]
+    pub fn generate_nonce() -> String {
+        R::base64_url(32)
+    }
+
     /// Creates an S256 code challenge from a given PKCE code verifier.
     #[must_use]
     pub fn create_s256_code_challenge(code_verifier: &str) -> String {
@@ -59,6 +140,7 @@ impl<R: RandomSource> OAuth<R> {
         scopes: Vec<&str>,
         state: &str,
         code_challenge: &str,
+        nonce: &str,
     ) -> Result<String, Error> {
         let mut params = vec![
             ("response_type", "code"),
@@ -72,6 +154,10 @@ impl<R: RandomSource> OAuth<R> {
             params.push(("code_challenge", code_challenge));
         }
 
+        if !nonce.is_empty() {
+            params.push(("nonce", nonce));
+        }
+
         let scopes = scopes.join(" ");
         if !scopes.is_empty() {
             params.push(("scope", scopes.as_str()));
@@ -83,6 +169,7 @@ impl<R: RandomSource> OAuth<R> {
 
     /// Exchanges an authorization code for a token response.
     pub async fn validate_authorization_code<T: DeserializeOwned>(
+        &self,
         token_endpoint: &str,
         client_id: &str,
         client_secret: &str,
@@ -98,57 +185,191 @@ impl<R: RandomSource> OAuth<R> {
             params.insert("code_verifier".into(), code_verifier.into());
         }
 
+        self.request_token(token_endpoint, client_id, client_secret, params)
+            .await
+    }
+
+    /// Exchanges a refresh token for a new token response.
+    pub async fn refresh_access_token<T: DeserializeOwned>(
+        &self,
+        token_endpoint: &str,
+        client_id: &str,
+        client_secret: &str,
+        refresh_token: &str,
+    ) -> Result<T, Error> {
+        let mut params: HashMap<String, String> = HashMap::new();
+        params.insert("grant_type".into(), "refresh_token".into());
+        params.insert("refresh_token".into(), refresh_token.into());
+
+        self.request_token(token_endpoint, client_id, client_secret, params)
+            .await
+    }
+
+    /// Posts a token request with the given form parameters.
+    async fn request_token<T: DeserializeOwned>(
+        &self,
+        token_endpoint: &str,
+        client_id: &str,
+        client_secret: &str,
+        mut params: HashMap<String, String>,
+    ) -> Result<T, Error> {
+        if self.auth_method == ClientAuthMethod::Post {
+            params.insert("client_id".into(), client_id.into());
+            params.insert("client_secret".into(), client_secret.into());
+        }
+
         let body = serde_urlencoded::to_string(&params)?;
-        let client = Client::builder()
-            .redirect(Policy::none())
-            .build()
-            .map_err(|_| Error::BuildHttpClient)?;
 
-        let response = client
-            .post(token_endpoint)
-            .basic_auth(client_id, Some(client_secret))
+        let mut request = self.client.post(token_endpoint);
+        if self.auth_method == ClientAuthMethod::Basic {
+            request = request.basic_auth(client_id, Some(client_secret));
+        }
+
+        let response = request
             .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
             .header(ACCEPT, "application/json")
             .header(CONTENT_LENGTH, body.len().to_string())
             .body(body)
             .send()
-            .await?
-            .json::<T>()
             .await?;
 
-        Ok(response)
+        read_response(response, MAX_RESPONSE_BODY_SIZE).await
     }
 
     /// Verifies an OpenID Connect ID token using the provider's JWKS.
+    ///
+    /// The token must be issued by one of `issuers` for `client_id`
+    /// and must not be expired. If `nonce` is not empty, the token
+    /// must carry the same nonce.
+    ///
+    /// The key set is served from the cache. If it has no key matching
+    /// the token, e.g. because the provider rotated its keys, it is
+    /// refetched once before giving up.
     pub async fn verify_oidc_token(
+        &self,
         endpoint: &str,
         id_token: &str,
         client_id: &str,
+        issuers: &[&str],
+        nonce: &str,
     ) -> Result<OidcTokenClaims, Error> {
         let header = decode_header(id_token)?;
         let kid = header.kid.ok_or(Error::MissingKID)?;
 
-        let jwks = Client::new()
-            .get(endpoint)
-            .send()
-            .await?
-            .json::<Jwks>()
-            .await?;
-
-        let jwk = jwks
-            .keys
-            .iter()
-            .find(|key| key.kid == kid)
-            .ok_or(Error::NoMatchingJWKS)?;
+        let mut jwks = self.jwks.get(&self.client, endpoint, false).await?;
+        if find_key(&jwks, &kid).is_none() {
+            jwks = self.jwks.get(&self.client, endpoint, true).await?;
+        }
+        let jwk = find_key(&jwks, &kid).ok_or(Error::NoMatchingJWKS)?;
 
         let decoding_key = DecodingKey::from_rsa_components(&jwk.n, &jwk.e)?;
 
-        let mut validation = Validation::new(Algorithm::RS256);
-        validation.set_audience(&[client_id.to_string()]);
+        decode_id_token(
+            id_token,
+            &decoding_key,
+            Algorithm::RS256,
+            client_id,
+            issuers,
+            nonce,
+        )
+    }
+}
+
+/// Decodes an ID token and validates its signature, audience,
+/// issuer, expiry and nonce.
+fn decode_id_token(
+    id_token: &str,
+    decoding_key: &DecodingKey,
+    algorithm: Algorithm,
+    client_id: &str,
+    issuers: &[&str],
+    nonce: &str,
+) -> Result<OidcTokenClaims, Error> {
+    let mut validation = Validation::new(algorithm);
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(issuers);
+    validation.set_required_spec_claims(&["exp", "aud", "iss"]);
+
+    let claims = match decode::<OidcTokenClaims>(id_token, decoding_key, &validation) {
+        Ok(token_data) => token_data.claims,
+        Err(e) if *e.kind() == ErrorKind::InvalidIssuer => return Err(Error::IssuerMismatch),
+        Err(e) => return Err(e.into()),
+    };
 
-        let token_data = decode::<OidcTokenClaims>(id_token, &decoding_key, &validation)?;
-        Ok(token_data.claims)
+    if !nonce.is_empty() && claims.nonce.as_deref() != Some(nonce) {
+        return Err(Error::NonceMismatch);
     }
+
+    Ok(claims)
+}
+
+/// Returns the key with the given key id.
+fn find_key<'a>(jwks: &'a Jwks, kid: &str) -> Option<&'a Jwk> {
+    jwks.keys.iter().find(|key| key.kid == kid)
+}
+
+/// Reads a token endpoint response body of at most `limit` bytes.
+///
+/// The body is parsed as form data if the content type is
+/// `application/x-www-form-urlencoded` and as JSON otherwise, because
+/// some token endpoints ignore the `Accept: application/json` header.
+/// An error object in the body is returned as
+/// [`Error::OAuthProviderError`], whatever the status code.
+///
+/// The body is read chunk by chunk so that an oversized response
+/// is rejected without buffering it entirely.
+async fn read_response<T: DeserializeOwned>(
+    mut response: Response,
+    limit: usize,
+) -> Result<T, Error> {
+    let is_form = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|v| {
+            v.trim()
+                .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        });
+
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit as u64)
+    {
+        return Err(Error::ResponseTooLarge(limit));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Err(Error::ResponseTooLarge(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    parse_token_response::<T>(&body, is_form)?.into_result()
+}
+
+/// Parses a token endpoint response body.
+///
+/// A body is taken as an error if it has the required `error` field,
+/// because a token type with only optional fields would accept it too.
+fn parse_token_response<T: DeserializeOwned>(
+    body: &[u8],
+    is_form: bool,
+) -> Result<TokenResponse<T>, Error> {
+    if let Ok(error) = parse_body::<OAuthErrorResponse>(body, is_form) {
+        return Ok(TokenResponse::Error(error));
+    }
+    parse_body(body, is_form).map(TokenResponse::Token)
+}
+
+/// Parses a body as form data or JSON.
+fn parse_body<T: DeserializeOwned>(body: &[u8], is_form: bool) -> Result<T, Error> {
+    if is_form {
+        return Ok(serde_urlencoded::from_bytes(body)?);
+    }
+    Ok(serde_json::from_slice(body)?)
 }
 
 /// Generic trait implemented by all OAuth 2.0 providers (e.g., Polar, Strava, etc.).
@@ -165,12 +386,284 @@ pub trait OAuthProvider: Send + Sync {
         &self,
         state: &str,
         code_challenge: &str,
+        nonce: &str,
     ) -> Result<String, Self::Error>;
 
     /// Exchanges an authorization code for tokens and account information.
+    ///
+    /// `nonce` is the value sent with the authorization request, or empty
+    /// if the provider does not support OpenID Connect.
     async fn exchange_code(
         &self,
         code: &str,
         code_verifier: &str,
+        nonce: &str,
     ) -> Result<Self::Account, Self::Error>;
+
+    /// Exchanges a refresh token for a new access token, so that an expired
+    /// access token can be renewed without another login.
+    async fn refresh_access_token(&self, refresh_token: &str) -> Result<OAuth2Token, Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::SecureRandom;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Token {
+        access_token: String,
+        expires_in: Option<u64>,
+    }
+
+    fn response_with_content_type(content_type: &str, body: &'static str) -> Response {
+        let response = http::Response::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(body)
+            .unwrap();
+        Response::from(response)
+    }
+
+    const CLIENT_ID: &str = "client-id";
+    const ISSUERS: &[&str] = &["https://accounts.google.com", "accounts.google.com"];
+    const SECRET: &[u8] = b"secret";
+
+    /// Signs an ID token with the given claims.
+    fn id_token(claims: serde_json::Value) -> String {
+        let key = jsonwebtoken::EncodingKey::from_secret(SECRET);
+        jsonwebtoken::encode(&jsonwebtoken::Header::new(Algorithm::HS256), &claims, &key).unwrap()
+    }
+
+    fn decode_hs256(id_token: &str) -> Result<OidcTokenClaims, Error> {
+        decode_hs256_with_nonce(id_token, "")
+    }
+
+    fn decode_hs256_with_nonce(id_token: &str, nonce: &str) -> Result<OidcTokenClaims, Error> {
+        let key = DecodingKey::from_secret(SECRET);
+        decode_id_token(id_token, &key, Algorithm::HS256, CLIENT_ID, ISSUERS, nonce)
+    }
+
+    #[test]
+    fn test_decode_id_token() {
+        // given
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+        let token = id_token(serde_json::json!({
+            "sub": "user", "aud": CLIENT_ID, "iss": "accounts.google.com", "exp": exp,
+        }));
+
+        // when
+        let got = decode_hs256(&token).unwrap();
+
+        // then
+        assert_eq!(got.sub, "user");
+        assert_eq!(got.iss.as_deref(), Some("accounts.google.com"));
+    }
+
+    #[test]
+    fn test_decode_id_token_issuer_mismatch() {
+        // given
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+        let token = id_token(serde_json::json!({
+            "sub": "user", "aud": CLIENT_ID, "iss": "https://evil.example.com", "exp": exp,
+        }));
+
+        // when
+        let got = decode_hs256(&token);
+
+        // then
+        assert!(matches!(got, Err(Error::IssuerMismatch)), "{got:?}");
+    }
+
+    #[test]
+    fn test_decode_id_token_missing_issuer() {
+        // given
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+        let token = id_token(serde_json::json!({ "sub": "user", "aud": CLIENT_ID, "exp": exp }));
+
+        // when
+        let got = decode_hs256(&token);
+
+        // then
+        assert!(matches!(got, Err(Error::DecodeIdToken(_))), "{got:?}");
+    }
+
+    #[test]
+    fn test_decode_id_token_expired() {
+        // given
+        let exp = jsonwebtoken::get_current_timestamp() - 3600;
+        let token = id_token(serde_json::json!({
+            "sub": "user", "aud": CLIENT_ID, "iss": "accounts.google.com", "exp": exp,
+        }));
+
+        // when
+        let got = decode_hs256(&token);
+
+        // then
+        assert!(matches!(got, Err(Error::DecodeIdToken(_))), "{got:?}");
+    }
+
+    #[test]
+    fn test_decode_id_token_missing_expiry() {
+        // given
+        let token = id_token(serde_json::json!({
+            "sub": "user", "aud": CLIENT_ID, "iss": "accounts.google.com",
+        }));
+
+        // when
+        let got = decode_hs256(&token);
+
+        // then
+        assert!(matches!(got, Err(Error::DecodeIdToken(_))), "{got:?}");
+    }
+
+    #[rstest::rstest]
+    #[case::matching(Some("nonce"), "nonce", true)]
+    #[case::mismatch(Some("other"), "nonce", false)]
+    #[case::missing_claim(None, "nonce", false)]
+    #[case::not_expected(None, "", true)]
+    fn test_decode_id_token_nonce(
+        #[case] claim: Option<&str>,
+        #[case] nonce: &str,
+        #[case] want_ok: bool,
+    ) {
+        // given
+        let exp = jsonwebtoken::get_current_timestamp() + 60;
+        let token = id_token(serde_json::json!({
+            "sub": "user", "aud": CLIENT_ID, "iss": "accounts.google.com", "exp": exp,
+            "nonce": claim,
+        }));
+
+        // when
+        let got = decode_hs256_with_nonce(&token, nonce);
+
+        // then
+        if want_ok {
+            assert!(got.is_ok(), "{got:?}");
+        } else {
+            assert!(matches!(got, Err(Error::NonceMismatch)), "{got:?}");
+        }
+    }
+
+    #[test]
+    fn test_authorization_url_with_nonce() {
+        // when
+        let url = OAuth::<SecureRandom>::generate_authorization_url(
+            "https://example.com/auth",
+            CLIENT_ID,
+            "https://example.com/callback",
+            vec!["openid"],
+            "state",
+            "",
+            "nonce",
+        )
+        .unwrap();
+
+        // then
+        assert!(url.contains("&nonce=nonce"), "{url}");
+    }
+
+    #[tokio::test]
+    async fn test_read_json() {
+        // given
+        let body = r#"{"access_token":"token"}"#;
+        let response = Response::from(http::Response::new(body));
+
+        // when
+        let got = read_response::<Token>(response, MAX_RESPONSE_BODY_SIZE).await;
+
+        // then
+        assert_eq!(got.unwrap().access_token, "token");
+    }
+
+    #[tokio::test]
+    async fn test_read_json_with_content_type() {
+        // given
+        let body = r#"{"access_token":"token","expires_in":3600}"#;
+        let response = response_with_content_type("application/json; charset=utf-8", body);
+
+        // when
+        let got = read_response::<Token>(response, MAX_RESPONSE_BODY_SIZE)
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(got.access_token, "token");
+        assert_eq!(got.expires_in, Some(3600));
+    }
+
+    #[tokio::test]
+    async fn test_read_form_urlencoded() {
+        // given
+        let body = "access_token=token&expires_in=3600&scope=user%3Aemail";
+        let response = response_with_content_type("application/x-www-form-urlencoded", body);
+
+        // when
+        let got = read_response::<Token>(response, MAX_RESPONSE_BODY_SIZE)
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(got.access_token, "token");
+        assert_eq!(got.expires_in, Some(3600));
+    }
+
+    #[tokio::test]
+    async fn test_read_json_error() {
+        // given
+        let body = r#"{"error":"invalid_grant","error_description":"Bad Request"}"#;
+        let response = response_with_content_type("application/json", body);
+
+        // when
+        let got = read_response::<OAuth2Token>(response, MAX_RESPONSE_BODY_SIZE).await;
+
+        // then
+        assert!(
+            matches!(
+                &got,
+                Err(Error::OAuthProviderError { code, description })
+                    if code == "invalid_grant" && description == "Bad Request"
+            ),
+            "{got:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_form_urlencoded_error() {
+        // given
+        let body =
+            "error=bad_verification_code&error_description=The+code+is+incorrect+or+expired.";
+        let response = response_with_content_type("application/x-www-form-urlencoded", body);
+
+        // when
+        let got = read_response::<Token>(response, MAX_RESPONSE_BODY_SIZE).await;
+
+        // then
+        assert!(
+            matches!(
+                &got,
+                Err(Error::OAuthProviderError { code, description })
+                    if code == "bad_verification_code"
+                        && description == "The code is incorrect or expired."
+            ),
+            "{got:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_json_rejects_oversized_body() {
+        // given
+        let token = "a".repeat(MAX_RESPONSE_BODY_SIZE);
+        let body = format!(r#"{{"access_token":"{token}"}}"#);
+        let response = Response::from(http::Response::new(body));
+
+        // when
+        let got = read_response::<Token>(response, MAX_RESPONSE_BODY_SIZE).await;
+
+        // then
+        assert!(matches!(
+            got,
+            Err(Error::ResponseTooLarge(MAX_RESPONSE_BODY_SIZE))
+        ));
+    }
 }
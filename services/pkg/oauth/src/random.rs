@@ -59,6 +59,58 @@ impl RandomSource for SecureRandom {
     }
 }
 
+/// Deterministic random generator backed by a seedable PRNG, for
+/// property-based tests and reproducible load tests.
+///
+/// **Not** cryptographically secure. Only compiled with the `seeded`
+/// feature, so that it cannot end up in production binaries.
+///
+/// [`RandomSource`] has no receiver, so the generator state lives in a
+/// thread local. Every thread starts with seed `0`; use
+/// [`SeededRandom::seed`] to restart the sequence.
+#[cfg(feature = "seeded")]
+#[derive(Debug, Clone, Default)]
+pub struct SeededRandom;
+
+#[cfg(feature = "seeded")]
+mod seeded {
+    use super::*;
+    use rand::{SeedableRng as _, rngs::StdRng};
+    use std::cell::RefCell;
+
+    thread_local! {
+        static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(0));
+    }
+
+    impl SeededRandom {
+        /// Reseeds the generator of the current thread.
+        pub fn seed(seed: u64) {
+            RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+        }
+
+        fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+            RNG.with(|rng| f(&mut rng.borrow_mut()))
+        }
+    }
+
+    impl RandomSource for SeededRandom {
+        fn alphanumeric(len: usize) -> String {
+            Self::with_rng(|rng| Alphanumeric.sample_string(rng, len))
+        }
+
+        fn base64_url(num_bytes: usize) -> String {
+            let random_bytes: Vec<u8> =
+                Self::with_rng(|rng| (0..num_bytes).map(|_| rng.random()).collect());
+            BASE64_URL_SAFE_NO_PAD.encode(&random_bytes)
+        }
+
+        fn uuid() -> Uuid {
+            let bytes = Self::with_rng(|rng| rng.random());
+            uuid::Builder::from_random_bytes(bytes).into_uuid()
+        }
+    }
+}
+
 #[cfg(feature = "mock")]
 pub mod mock {
     use super::*;
@@ -81,3 +133,46 @@ pub mod mock {
         }
     }
 }
+
+#[cfg(all(test, feature = "seeded"))]
+mod tests {
+    use super::*;
+
+    fn tokens() -> (String, String, Uuid) {
+        (
+            SeededRandom::alphanumeric(16),
+            SeededRandom::base64_url(32),
+            SeededRandom::uuid(),
+        )
+    }
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        // given
+        SeededRandom::seed(42);
+        let first = [tokens(), tokens()];
+
+        // when
+        SeededRandom::seed(42);
+        let second = [tokens(), tokens()];
+
+        // then
+        assert_eq!(first, second);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(first[0].2.get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_different_seed_different_sequence() {
+        // given
+        SeededRandom::seed(1);
+        let first = tokens();
+
+        // when
+        SeededRandom::seed(2);
+        let second = tokens();
+
+        // then
+        assert_ne!(first, second);
+    }
+}
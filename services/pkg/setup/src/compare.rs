@@ -0,0 +1,38 @@
+//! Comparisons of secrets that do not leak timing information.
+
+/// Compares two byte slices for equality in constant time to prevent timing attacks.
+///
+/// Only the length of the inputs may leak.
+#[must_use]
+pub fn constant_time_equal(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut c = 0u8;
+    for (&x, &y) in a.iter().zip(b.iter()) {
+        c |= x ^ y;
+    }
+    c == 0
+}
+
+/// Compares two strings for equality in constant time, e.g. a CSRF token.
+#[must_use]
+pub fn constant_time_str_equal(a: &str, b: &str) -> bool {
+    constant_time_equal(a.as_bytes(), b.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::equal("state", "state", true)]
+    #[case::empty("", "", true)]
+    #[case::different("state", "stale", false)]
+    #[case::different_length("state", "states", false)]
+    #[case::one_empty("state", "", false)]
+    fn test_constant_time_str_equal(#[case] a: &str, #[case] b: &str, #[case] want: bool) {
+        assert_eq!(constant_time_str_equal(a, b), want);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::middleware::csrf::CSRF_TOKEN_COOKIE_KEY;
 use crate::session::{SESSION_TOKEN_COOKIE_KEY, SESSION_TOKEN_EXPIRY_DURATION};
 use chrono::Duration;
 use http::HeaderValue;
@@ -18,6 +19,9 @@ pub struct Cookie {
     /// The cookie's path domain, if any.
     path: String,
 
+    /// The cookie's domain, if any.
+    domain: Option<String>,
+
     /// Whether this cookie was marked Secure.
     secure: bool,
 
@@ -28,6 +32,15 @@ pub struct Cookie {
     same_site: SameSite,
 }
 
+impl Cookie {
+    /// Sets the `Domain` attribute, e.g. to share a cookie across subdomains.
+    #[must_use]
+    pub fn with_domain<D: Into<String>>(mut self, domain: D) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+}
+
 impl fmt::Display for Cookie {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}={}", self.name, self.value)?;
@@ -40,6 +53,10 @@ impl fmt::Display for Cookie {
             write!(f, "; Path={}", self.path)?;
         }
 
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+
         if self.secure {
             write!(f, "; Secure")?;
         }
@@ -54,52 +71,109 @@ impl fmt::Display for Cookie {
     }
 }
 
+/// Configuration of the `Secure` and `SameSite` attributes of cookies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CookieConfig {
+    /// Whether cookies are only sent over HTTPS.
+    pub secure: bool,
+
+    /// The `SameSite` attribute of cookies.
+    pub same_site: SameSite,
+}
+
+impl CookieConfig {
+    /// Returns the config for the current `APP_ENV`. Local and dev
+    /// environments are served over HTTP and use non-secure `Lax` cookies,
+    /// everything else uses the [`Default`] config.
+    pub fn from_env() -> Self {
+        let app_env = std::env::var("APP_ENV").unwrap_or_default();
+        match app_env.to_lowercase().as_str() {
+            "local" | "integration-test" | "dev" => Self {
+                secure: false,
+                same_site: SameSite::Lax,
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+impl Default for CookieConfig {
+    /// Secure cookies with `SameSite=None` for cross-site auth over HTTPS.
+    fn default() -> Self {
+        Self {
+            secure: true,
+            same_site: SameSite::None,
+        }
+    }
+}
+
 /// Creates a new session token cookie.
-pub fn create_session_token_cookie<T: Into<String>>(token: T) -> Cookie {
-    build_cookie(
-        SESSION_TOKEN_COOKIE_KEY,
-        token,
-        SESSION_TOKEN_EXPIRY_DURATION,
-    )
+pub fn create_session_token_cookie<T: Into<String>>(token: T, config: &CookieConfig) -> Cookie {
+    create_session_token_cookie_with_max_age(token, SESSION_TOKEN_EXPIRY_DURATION, config)
+}
+
+/// Creates a new session token cookie that expires after `max_age`.
+pub fn create_session_token_cookie_with_max_age<T: Into<String>>(
+    token: T,
+    max_age: Duration,
+    config: &CookieConfig,
+) -> Cookie {
+    build_cookie(SESSION_TOKEN_COOKIE_KEY, token, max_age, config)
 }
 
 /// Expires a session token cookie.
-pub fn expire_session_token_cookie() -> Cookie {
-    build_cookie(SESSION_TOKEN_COOKIE_KEY, "", Duration::zero())
+pub fn expire_session_token_cookie(config: &CookieConfig) -> Cookie {
+    build_cookie(SESSION_TOKEN_COOKIE_KEY, "", Duration::zero(), config)
+}
+
+/// Creates a new csrf token cookie.
+///
+/// Unlike the other cookies it is not `HttpOnly`, so that the frontend
+/// can read the token and echo it in the `X-CSRF-Token` header.
+pub fn create_csrf_token_cookie<T: Into<String>>(token: T, config: &CookieConfig) -> Cookie {
+    Cookie {
+        http_only: false,
+        ..build_cookie(
+            CSRF_TOKEN_COOKIE_KEY,
+            token,
+            SESSION_TOKEN_EXPIRY_DURATION,
+            config,
+        )
+    }
 }
 
 /// Creates a new oauth cookie.
-pub fn create_oauth_cookie<S, T>(name: S, value: T) -> Cookie
+pub fn create_oauth_cookie<S, T>(name: S, value: T, config: &CookieConfig) -> Cookie
 where
     S: Into<String>,
     T: Into<String>,
 {
-    build_cookie(name, value, Duration::minutes(10))
+    build_cookie(name, value, Duration::minutes(10), config)
 }
 
 /// Creates a cookie that instructs the browser to delete it.
-pub fn create_expired_oauth_cookie<S>(name: S) -> Cookie
+pub fn create_expired_oauth_cookie<S>(name: S, config: &CookieConfig) -> Cookie
 where
     S: Into<String>,
 {
-    build_cookie(name, "", Duration::zero())
+    build_cookie(name, "", Duration::zero(), config)
 }
 
-fn build_cookie<N: Into<String>, V: Into<String>>(name: N, value: V, max_age: Duration) -> Cookie {
-    let app_env = std::env::var("APP_ENV").unwrap_or_default();
-    let (secure, same_site) = match app_env.to_lowercase().as_str() {
-        "local" | "integration-test" | "dev" => (false, SameSite::Lax),
-        _ => (true, SameSite::None),
-    };
-
+fn build_cookie<N: Into<String>, V: Into<String>>(
+    name: N,
+    value: V,
+    max_age: Duration,
+    config: &CookieConfig,
+) -> Cookie {
     Cookie {
         name: name.into(),
         value: value.into(),
         max_age,
         path: String::from("/"),
-        secure, // TODO: Enable on production
+        domain: None,
+        secure: config.secure,
         http_only: true,
-        same_site,
+        same_site: config.same_site,
     }
 }
 
@@ -107,9 +181,10 @@ fn build_cookie<N: Into<String>, V: Into<String>>(name: N, value: V, max_age: Du
 pub(crate) fn set_session_token_cookie<B, T: Into<String>>(
     response: &mut http::Response<B>,
     token: T,
+    config: &CookieConfig,
 ) {
     use http::header::{HeaderValue, SET_COOKIE};
-    let cookie = create_session_token_cookie(token);
+    let cookie = create_session_token_cookie(token, config);
     response.headers_mut().append(
         SET_COOKIE,
         HeaderValue::from_str(&cookie.to_string()).unwrap(),
@@ -123,27 +198,58 @@ pub fn extract_session_token_cookie(value: &HeaderValue) -> Option<String> {
 
 /// Extracts a cookie by name from a cookie header value.
 pub fn extract_cookie_by_name(name: &str, value: &HeaderValue) -> Option<String> {
+    find_cookie(value, |k| k == name)
+}
+
+/// Extracts a cookie by name from all `Cookie` headers.
+///
+/// Browsers send a single `Cookie` header, but proxies may split the
+/// cookies across several.
+pub fn extract_cookie_from_headers(name: &str, headers: &http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .find_map(|value| extract_cookie_by_name(name, value))
+}
+
+/// Extracts a cookie by name from a cookie header value, ignoring the
+/// ASCII case of the cookie name.
+///
+/// Cookie names are case-sensitive, so prefer [`extract_cookie_by_name`].
+/// This is an opt-in for clients or proxies that alter the name's casing.
+/// Note that the lookup of the `Cookie` header itself is always
+/// case-insensitive.
+pub fn extract_cookie_by_name_ignore_case(name: &str, value: &HeaderValue) -> Option<String> {
+    find_cookie(value, |k| k.eq_ignore_ascii_case(name))
+}
+
+fn find_cookie(value: &HeaderValue, matches: impl Fn(&str) -> bool) -> Option<String> {
     value
         .to_str()
         .ok()?
         .split(';')
         .map(str::trim)
         .filter_map(|cookie| cookie.split_once('='))
-        .find_map(|(k, v)| (k == name).then(|| v.to_string()))
+        .find_map(|(k, v)| matches(k).then(|| v.to_string()))
 }
 
+/// The `SameSite` attribute of a cookie.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[allow(dead_code)]
-enum SameSite {
-    None,
+pub enum SameSite {
+    /// Only sent with same-site requests.
+    Strict,
+    /// Also sent with top-level cross-site navigations.
     Lax,
+    /// Sent with all requests, requires `Secure`.
+    None,
 }
 
 impl fmt::Display for SameSite {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            SameSite::None => write!(f, "None"),
+            SameSite::Strict => write!(f, "Strict"),
             SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
         }
     }
 }
@@ -178,13 +284,14 @@ impl ResponseCookies for http::response::Builder {
 mod tests {
     use axum::response::Response;
     use http::header::SET_COOKIE;
+    use rstest::rstest;
 
     use super::*;
 
     #[test]
     fn test_session_token_cookie() {
         // when
-        let cookie = create_session_token_cookie("session-token");
+        let cookie = create_session_token_cookie("session-token", &CookieConfig::default());
 
         // then
         assert_eq!(
@@ -193,10 +300,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_csrf_token_cookie() {
+        // when
+        let cookie = create_csrf_token_cookie("csrf-token", &CookieConfig::default());
+
+        // then
+        assert_eq!(
+            cookie.to_string(),
+            "csrf_token=csrf-token; Max-Age=604800; Path=/; Secure; SameSite=None"
+        );
+    }
+
+    #[test]
+    fn test_session_token_cookie_with_max_age() {
+        // when
+        let cookie = create_session_token_cookie_with_max_age(
+            "session-token",
+            Duration::hours(1),
+            &CookieConfig::default(),
+        );
+
+        // then
+        assert_eq!(
+            cookie.to_string(),
+            "session_token=session-token; Max-Age=3600; Path=/; Secure; HttpOnly; SameSite=None"
+        );
+    }
+
     #[test]
     fn test_oauth_cookie() {
         // when
-        let cookie = create_oauth_cookie("name", "value");
+        let cookie = create_oauth_cookie("name", "value", &CookieConfig::default());
 
         // then
         assert_eq!(
@@ -208,7 +343,7 @@ mod tests {
     #[test]
     fn test_expired_cookie() {
         // when
-        let cookie = create_expired_oauth_cookie("name");
+        let cookie = create_expired_oauth_cookie("name", &CookieConfig::default());
 
         // then
         assert_eq!(
@@ -217,10 +352,50 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case::strict(
+        false,
+        SameSite::Strict,
+        "name=value; Max-Age=600; Path=/; HttpOnly; SameSite=Strict"
+    )]
+    #[case::lax(
+        false,
+        SameSite::Lax,
+        "name=value; Max-Age=600; Path=/; HttpOnly; SameSite=Lax"
+    )]
+    #[case::none(
+        true,
+        SameSite::None,
+        "name=value; Max-Age=600; Path=/; Secure; HttpOnly; SameSite=None"
+    )]
+    fn test_cookie_config(#[case] secure: bool, #[case] same_site: SameSite, #[case] want: &str) {
+        // given
+        let config = CookieConfig { secure, same_site };
+
+        // when
+        let cookie = create_oauth_cookie("name", "value", &config);
+
+        // then
+        assert_eq!(cookie.to_string(), want);
+    }
+
+    #[test]
+    fn test_cookie_with_domain() {
+        // when
+        let cookie = create_oauth_cookie("name", "value", &CookieConfig::default())
+            .with_domain("example.com");
+
+        // then
+        assert_eq!(
+            cookie.to_string(),
+            "name=value; Max-Age=600; Path=/; Domain=example.com; Secure; HttpOnly; SameSite=None"
+        );
+    }
+
     #[test]
     fn test_extract_cookie() {
         // given
-        let cookie = build_cookie("name", "value", Duration::zero());
+        let cookie = build_cookie("name", "value", Duration::zero(), &CookieConfig::default());
         let header = HeaderValue::from_str(&cookie.to_string()).unwrap();
 
         // when
@@ -230,10 +405,46 @@ mod tests {
         assert_eq!(cookie, Some("value".to_string()));
     }
 
+    #[test]
+    fn test_extract_cookie_from_multiple_headers() {
+        // given
+        let mut headers = http::HeaderMap::new();
+        headers.append(http::header::COOKIE, HeaderValue::from_static("other=1"));
+        headers.append(
+            http::header::COOKIE,
+            HeaderValue::from_static("theme=dark; session_token=value"),
+        );
+
+        // when
+        let cookie = extract_cookie_from_headers(SESSION_TOKEN_COOKIE_KEY, &headers);
+
+        // then
+        assert_eq!(cookie, Some("value".to_string()));
+    }
+
+    #[test]
+    fn test_extract_cookie_ignore_case() {
+        // given
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "Cookie",
+            HeaderValue::from_static("Other=1; Session_Token=value"),
+        );
+        let header = headers.get(http::header::COOKIE).unwrap();
+
+        // when
+        let exact = extract_cookie_by_name("session_token", header);
+        let ignore_case = extract_cookie_by_name_ignore_case("session_token", header);
+
+        // then
+        assert_eq!(exact, None);
+        assert_eq!(ignore_case, Some("value".to_string()));
+    }
+
     #[test]
     fn test_response_with_cookie() {
         // given
-        let cookie = build_cookie("name", "value", Duration::zero());
+        let cookie = build_cookie("name", "value", Duration::zero(), &CookieConfig::default());
 
         // when
         let response = Response::builder().with_cookie(cookie).body(()).unwrap();
@@ -248,8 +459,18 @@ mod tests {
     #[test]
     fn test_response_with_cookies() {
         // given
-        let cookie1 = build_cookie("name1", "value1", Duration::zero());
-        let cookie2 = build_cookie("name2", "value2", Duration::zero());
+        let cookie1 = build_cookie(
+            "name1",
+            "value1",
+            Duration::zero(),
+            &CookieConfig::default(),
+        );
+        let cookie2 = build_cookie(
+            "name2",
+            "value2",
+            Duration::zero(),
+            &CookieConfig::default(),
+        );
 
         // when
         let response = Response::builder()
@@ -276,7 +497,7 @@ mod tests {
         let mut response = Response::builder().body(()).unwrap();
 
         // when
-        set_session_token_cookie(&mut response, token);
+        set_session_token_cookie(&mut response, token, &CookieConfig::default());
 
         // then
         assert_eq!(
@@ -0,0 +1,271 @@
+//! Construction of gRPC client endpoints.
+//!
+//! Services are reached over plaintext inside the cluster. Setting
+//! `<SERVICE>_GRPC_TLS=true` switches a client to `https`, e.g. to call
+//! a service in another cluster. `<SERVICE>_GRPC_TLS_CA_CERT` points to
+//! a PEM file with an additional CA and `<SERVICE>_GRPC_TLS_DOMAIN`
+//! overrides the server name used for SNI and certificate validation.
+//!
+//! Connecting is retried with exponential backoff, configured through
+//! `GRPC_CONNECT_MAX_ATTEMPTS` and `GRPC_CONNECT_BASE_DELAY_MS`.
+use crate::patched_host;
+use common::host::service_env_key;
+use std::{error::Error, time::Duration};
+use tonic::{
+    Status, TimeoutExpired,
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint},
+};
+
+/// The timeout of a single request if a client is not given one.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The upper bound of the delay between two connection attempts.
+const MAX_CONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// How often and how fast connecting to a service is retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectRetry {
+    /// The total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay after the first failed attempt, doubled after each retry.
+    pub base_delay: Duration,
+}
+
+impl Default for ConnectRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl ConnectRetry {
+    /// Reads `GRPC_CONNECT_MAX_ATTEMPTS` and `GRPC_CONNECT_BASE_DELAY_MS`,
+    /// falling back to the defaults if they are unset.
+    ///
+    /// # Errors
+    /// - a variable is not a valid number
+    pub fn from_env() -> Result<Self, std::num::ParseIntError> {
+        let mut retry = Self::default();
+        if let Ok(attempts) = std::env::var("GRPC_CONNECT_MAX_ATTEMPTS") {
+            retry.max_attempts = attempts.parse()?;
+        }
+        if let Ok(delay) = std::env::var("GRPC_CONNECT_BASE_DELAY_MS") {
+            retry.base_delay = Duration::from_millis(delay.parse()?);
+        }
+        Ok(retry)
+    }
+
+    /// Returns the delay after the given failed attempt, starting at 1.
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay
+            .saturating_mul(factor)
+            .min(MAX_CONNECT_DELAY)
+    }
+}
+
+/// Connects to the endpoint, retrying failed attempts with exponential backoff.
+///
+/// # Errors
+/// - the last connection attempt failed
+pub async fn connect_with_retry(
+    endpoint: &Endpoint,
+    retry: ConnectRetry,
+) -> Result<Channel, tonic::transport::Error> {
+    let mut attempt = 1;
+    loop {
+        match endpoint.connect().await {
+            Ok(channel) => return Ok(channel),
+            Err(err) if attempt < retry.max_attempts => {
+                let delay = retry.delay(attempt);
+                tracing::warn!(
+                    uri = %endpoint.uri(),
+                    attempt,
+                    ?delay,
+                    "failed to connect: {err}, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// TLS options of a gRPC client.
+#[derive(Clone, Debug, Default)]
+pub struct GrpcTls {
+    /// A PEM encoded CA certificate trusted in addition to the native roots.
+    pub ca_cert: Option<Vec<u8>>,
+    /// The server name used for SNI, defaults to the host.
+    pub domain: Option<String>,
+}
+
+impl GrpcTls {
+    /// Reads the TLS options of a service from the environment.
+    ///
+    /// Returns `None` unless `<SERVICE>_GRPC_TLS` is set to `true` or `1`.
+    ///
+    /// # Errors
+    /// - the CA certificate file cannot be read
+    pub fn from_env(service_name: &str) -> Result<Option<Self>, std::io::Error> {
+        let get_env = |suffix: &str| {
+            std::env::var(service_env_key(service_name, suffix))
+                .ok()
+                .filter(|v| !v.is_empty())
+        };
+
+        if !matches!(get_env("GRPC_TLS").as_deref(), Some("true" | "1")) {
+            return Ok(None);
+        }
+
+        let ca_cert = get_env("GRPC_TLS_CA_CERT").map(std::fs::read).transpose()?;
+
+        Ok(Some(Self {
+            ca_cert,
+            domain: get_env("GRPC_TLS_DOMAIN"),
+        }))
+    }
+
+    fn client_config(self) -> ClientTlsConfig {
+        let mut config = ClientTlsConfig::new().with_native_roots();
+        if let Some(ca_cert) = self.ca_cert {
+            config = config.ca_certificate(Certificate::from_pem(ca_cert));
+        }
+        if let Some(domain) = self.domain {
+            config = config.domain_name(domain);
+        }
+        config
+    }
+}
+
+/// Returns the endpoint of a service, configured from the environment.
+///
+/// # Errors
+/// - the TLS options cannot be read
+/// - the endpoint is invalid
+pub fn service_endpoint(service_name: &str, port: u16) -> Result<Endpoint, Box<dyn Error>> {
+    let host = patched_host(service_name);
+    let tls = GrpcTls::from_env(service_name)?;
+    Ok(build_endpoint(&host, port, tls)?)
+}
+
+/// Builds a plaintext endpoint, or an `https` endpoint if TLS options are given.
+///
+/// # Errors
+/// - the endpoint uri or the TLS config is invalid
+pub fn build_endpoint(
+    host: &str,
+    port: u16,
+    tls: Option<GrpcTls>,
+) -> Result<Endpoint, tonic::transport::Error> {
+    let Some(tls) = tls else {
+        return Endpoint::from_shared(format!("http://{host}:{port}"));
+    };
+
+    Endpoint::from_shared(format!("https://{host}:{port}"))?.tls_config(tls.client_config())
+}
+
+/// Maps a request that ran into the client timeout to `DeadlineExceeded`.
+///
+/// Tonic reports an expired [`Endpoint::timeout`] as `Cancelled`, which
+/// cannot be distinguished from a request cancelled by the server.
+pub fn deadline_exceeded(status: Status) -> Status {
+    let mut source = status.source();
+    while let Some(err) = source {
+        if err.is::<TimeoutExpired>() {
+            return Status::deadline_exceeded(status.message());
+        }
+        source = err.source();
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_plaintext_endpoint() {
+        // when
+        let endpoint = build_endpoint("auth", 50051, None).unwrap();
+
+        // then
+        assert_eq!(endpoint.uri().to_string(), "http://auth:50051/");
+    }
+
+    #[test]
+    fn test_build_tls_endpoint() {
+        // given
+        let tls = GrpcTls {
+            ca_cert: None,
+            domain: Some(String::from("auth.example.com")),
+        };
+
+        // when
+        let endpoint = build_endpoint("auth", 50051, Some(tls)).unwrap();
+
+        // then
+        assert_eq!(endpoint.uri().to_string(), "https://auth:50051/");
+    }
+
+    #[test]
+    fn test_deadline_exceeded_on_timeout() {
+        // given
+        let status = Status::from_error(Box::new(TimeoutExpired(())));
+
+        // when
+        let got = deadline_exceeded(status);
+
+        // then
+        assert_eq!(got.code(), tonic::Code::DeadlineExceeded);
+    }
+
+    #[test]
+    fn test_deadline_exceeded_keeps_other_errors() {
+        // given
+        let status = Status::cancelled("cancelled by server");
+
+        // when
+        let got = deadline_exceeded(status);
+
+        // then
+        assert_eq!(got.code(), tonic::Code::Cancelled);
+    }
+
+    #[test]
+    fn test_connect_retry_delay() {
+        // given
+        let retry = ConnectRetry {
+            max_attempts: 10,
+            base_delay: Duration::from_secs(1),
+        };
+
+        // then
+        assert_eq!(retry.delay(1), Duration::from_secs(1));
+        assert_eq!(retry.delay(2), Duration::from_secs(2));
+        assert_eq!(retry.delay(3), Duration::from_secs(4));
+        assert_eq!(retry.delay(64), MAX_CONNECT_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_retry_gives_up() {
+        // given
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let endpoint = build_endpoint("127.0.0.1", port, None).unwrap();
+        let retry = ConnectRetry {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+        };
+
+        // when
+        let got = connect_with_retry(&endpoint, retry).await;
+
+        // then
+        assert!(got.is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Readiness reporting for the grpc services via the standard
+//! `grpc.health.v1.Health` service.
+use std::{fmt::Display, time::Duration};
+use tonic_health::{
+    ServingStatus,
+    pb::health_server::{Health, HealthServer},
+    server::{HealthReporter, health_reporter},
+};
+
+/// How long to wait before checking an unreachable dependency again.
+const READINESS_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Returns the `grpc.health.v1.Health` service.
+///
+/// Both `service_name` and the server as a whole (`""`) report
+/// `NOT_SERVING` until `ready` succeeds, e.g. once the database is
+/// reachable. `ready` is retried in the background.
+pub async fn grpc_health_service<F, Fut, E>(
+    service_name: &'static str,
+    ready: F,
+) -> HealthServer<impl Health>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Display + 'static,
+{
+    let (reporter, service) = health_reporter();
+    set_status(&reporter, service_name, ServingStatus::NotServing).await;
+
+    tokio::spawn(report_serving_when_ready(
+        reporter,
+        service_name,
+        ready,
+        READINESS_RETRY_INTERVAL,
+    ));
+
+    service
+}
+
+/// Retries `ready` every `interval` and reports the service as serving
+/// once it succeeds.
+async fn report_serving_when_ready<F, Fut, E>(
+    reporter: HealthReporter,
+    service_name: &'static str,
+    mut ready: F,
+    interval: Duration,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+{
+    loop {
+        match ready().await {
+            Ok(()) => break,
+            Err(err) => tracing::warn!("{service_name} is not ready: {err}"),
+        }
+        tokio::time::sleep(interval).await;
+    }
+    set_status(&reporter, service_name, ServingStatus::Serving).await;
+}
+
+/// Sets the status of a service and of the server as a whole.
+async fn set_status(reporter: &HealthReporter, service_name: &str, status: ServingStatus) {
+    reporter.set_service_status("", status).await;
+    reporter.set_service_status(service_name, status).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use tonic::Request;
+    use tonic_health::{
+        pb::{HealthCheckRequest, health_check_response},
+        server::HealthService,
+    };
+
+    async fn status(
+        reporter: &HealthReporter,
+        service: &str,
+    ) -> health_check_response::ServingStatus {
+        let health = HealthService::from_health_reporter(reporter.clone());
+        let req = Request::new(HealthCheckRequest {
+            service: service.to_string(),
+        });
+        health.check(req).await.unwrap().into_inner().status()
+    }
+
+    #[tokio::test]
+    async fn test_report_serving_when_ready() {
+        // given
+        let (reporter, _) = health_reporter();
+        set_status(&reporter, "test.Service", ServingStatus::NotServing).await;
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        // when
+        let counter = Arc::clone(&attempts);
+        report_serving_when_ready(
+            reporter.clone(),
+            "test.Service",
+            move || {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt < 2 {
+                        Err("unreachable")
+                    } else {
+                        Ok(())
+                    }
+                }
+            },
+            Duration::ZERO,
+        )
+        .await;
+
+        // then
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        for service in ["", "test.Service"] {
+            assert_eq!(
+                status(&reporter, service).await,
+                health_check_response::ServingStatus::Serving
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_serving_until_ready() {
+        // given
+        let (reporter, _) = health_reporter();
+
+        // when
+        set_status(&reporter, "test.Service", ServingStatus::NotServing).await;
+
+        // then
+        assert_eq!(
+            status(&reporter, "").await,
+            health_check_response::ServingStatus::NotServing
+        );
+    }
+}
@@ -1,16 +1,19 @@
+pub mod compare;
 pub mod cookie;
+pub mod endpoint;
+pub mod health;
+pub mod metrics;
 pub mod middleware;
 pub mod session;
 pub mod tracing;
 mod validate;
-pub use validate::validate_user_id;
+pub use compare::{constant_time_equal, constant_time_str_equal};
+pub use validate::{validate_email, validate_user_id};
 
+use common::host::HostResolver;
+
+/// Returns the host under which a service is reachable, see
+/// [`HostResolver`] for how it is resolved.
 pub fn patched_host<S: Into<String>>(host: S) -> String {
-    let host = host.into();
-    let app_env = std::env::var("APP_ENV").unwrap_or_default();
-    match app_env.as_str() {
-        "local" => "localhost".to_string(),
-        "integration-test" => format!("{host}-integration-test"),
-        _ => host,
-    }
+    HostResolver::from_env().resolve(&host.into())
 }
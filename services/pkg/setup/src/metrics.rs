@@ -0,0 +1,137 @@
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Counter, Meter},
+};
+
+/// The outcome of an OAuth login callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthOutcome {
+    /// The user was logged in.
+    Success,
+    /// The provider redirected back with an error, e.g. the user denied consent.
+    ProviderDenied,
+    /// The oauth state or code verifier cookie was missing.
+    MissingCookie,
+    /// The state returned by the provider did not match the stored state.
+    StateMismatch,
+    /// The stored state was issued too long ago.
+    StateExpired,
+    /// The authorization code could not be exchanged for tokens.
+    TokenExchangeFailed,
+    /// The user's email domain is not allowed to sign in.
+    EmailDomainNotAllowed,
+    /// Any other failure, e.g. creating the user or the session.
+    Error,
+}
+
+impl OAuthOutcome {
+    /// Returns the label value of the outcome.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OAuthOutcome::Success => "success",
+            OAuthOutcome::ProviderDenied => "provider_denied",
+            OAuthOutcome::MissingCookie => "missing_cookie",
+            OAuthOutcome::StateMismatch => "state_mismatch",
+            OAuthOutcome::StateExpired => "state_expired",
+            OAuthOutcome::TokenExchangeFailed => "token_exchange_failed",
+            OAuthOutcome::EmailDomainNotAllowed => "email_domain_not_allowed",
+            OAuthOutcome::Error => "error",
+        }
+    }
+}
+
+/// Counters for the OAuth login flow, labeled by provider and outcome.
+///
+/// The default instance records on the global OpenTelemetry meter, which
+/// is a no-op until a meter provider is installed.
+#[derive(Clone, Debug)]
+pub struct OAuthMetrics {
+    callbacks: Counter<u64>,
+}
+
+impl OAuthMetrics {
+    /// The name of the oauth callback counter.
+    pub const CALLBACKS: &str = "oauth_callbacks";
+
+    /// Creates the oauth counters on the given meter.
+    pub fn new(meter: &Meter) -> Self {
+        let callbacks = meter
+            .u64_counter(Self::CALLBACKS)
+            .with_description("Number of handled oauth callbacks by provider and outcome.")
+            .build();
+
+        Self { callbacks }
+    }
+
+    /// Records the outcome of an oauth callback.
+    pub fn record(&self, provider: &'static str, outcome: OAuthOutcome) {
+        self.callbacks.add(
+            1,
+            &[
+                KeyValue::new("provider", provider),
+                KeyValue::new("outcome", outcome.as_str()),
+            ],
+        );
+    }
+}
+
+impl Default for OAuthMetrics {
+    fn default() -> Self {
+        Self::new(&global::meter("oauth"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::{
+        InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+        data::{AggregatedMetrics, MetricData},
+    };
+
+    /// Returns the value of the callback counter for the given outcome.
+    fn callback_count(exporter: &InMemoryMetricExporter, outcome: OAuthOutcome) -> u64 {
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let Some(resource_metrics) = metrics.last() else {
+            return 0;
+        };
+        resource_metrics
+            .scope_metrics()
+            .flat_map(|scope| scope.metrics())
+            .filter(|metric| metric.name() == OAuthMetrics::CALLBACKS)
+            .filter_map(|metric| match metric.data() {
+                AggregatedMetrics::U64(MetricData::Sum(sum)) => Some(sum),
+                _ => None,
+            })
+            .flat_map(|sum| sum.data_points())
+            .filter(|point| {
+                point
+                    .attributes()
+                    .any(|kv| kv == &KeyValue::new("outcome", outcome.as_str()))
+            })
+            .map(|point| point.value())
+            .sum()
+    }
+
+    #[test]
+    fn test_record_oauth_outcomes() {
+        // given
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let metrics = OAuthMetrics::new(&provider.meter("test"));
+
+        // when
+        metrics.record("google", OAuthOutcome::Success);
+        metrics.record("google", OAuthOutcome::StateMismatch);
+        metrics.record("github", OAuthOutcome::StateMismatch);
+        provider.force_flush().unwrap();
+
+        // then
+        assert_eq!(callback_count(&exporter, OAuthOutcome::Success), 1);
+        assert_eq!(callback_count(&exporter, OAuthOutcome::StateMismatch), 2);
+        assert_eq!(callback_count(&exporter, OAuthOutcome::ProviderDenied), 0);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::cookie::{extract_session_token_cookie, set_session_token_cookie};
-use crate::session::SessionState;
+use crate::cookie::{CookieConfig, extract_cookie_from_headers, set_session_token_cookie};
+use crate::session::{SESSION_TOKEN_COOKIE_KEY, SessionState, SessionToken};
 use axum::body::Body;
+use chrono::{DateTime, Utc};
 use core::pin::Pin;
-use http::{Method, Request, Response, StatusCode, header::COOKIE};
+use http::{
+    Method, Request, Response, StatusCode,
+    header::{CONTENT_TYPE, COOKIE},
+};
 use std::task::{Context, Poll};
 use thiserror::Error;
 use tonic::async_trait;
@@ -33,12 +37,22 @@ pub struct SessionAuthService<S, V> {
 
     /// Request uri paths for which authentication should be skipped.
     pub no_auth: Vec<String>,
+
+    /// The config of refreshed session cookies.
+    pub cookie_config: CookieConfig,
+
+    /// Whether rejections have a JSON body instead of plain text.
+    pub json_errors: bool,
 }
 
 /// Authentication layer that validates a session token from incoming requests.
 ///
 /// After successful authentication the middleware inserts the user id
-/// into the request's extensions allowing handlers to access the user.
+/// and the [`SessionToken`] into the request's extensions allowing
+/// handlers to access the user.
+/// The [`AuthenticatedSession`] is stored as well, so that the session is
+/// validated at most once per request, even if the layer is applied
+/// multiple times.
 #[derive(Clone)]
 pub struct SessionAuthLayer<A> {
     /// The session validator used to check authentication.
@@ -46,16 +60,40 @@ pub struct SessionAuthLayer<A> {
 
     /// Request uri paths for which authentication should be skipped.
     pub no_auth_endpoints: Vec<String>,
+
+    /// The config of refreshed session cookies.
+    pub cookie_config: CookieConfig,
+
+    /// Whether rejections have a JSON body instead of plain text.
+    pub json_errors: bool,
 }
 
 impl<A> SessionAuthLayer<A> {
-    /// Creates a new [`SessionAuthLayer`].
+    /// Creates a new [`SessionAuthLayer`] with the cookie config of the
+    /// current environment.
     pub fn new(session_auth_client: A, no_auth_endpoints: Vec<String>) -> Self {
         Self {
             session_auth_client,
             no_auth_endpoints,
+            cookie_config: CookieConfig::from_env(),
+            json_errors: false,
         }
     }
+
+    /// Sets the config of refreshed session cookies.
+    #[must_use]
+    pub fn with_cookie_config(mut self, cookie_config: CookieConfig) -> Self {
+        self.cookie_config = cookie_config;
+        self
+    }
+
+    /// Rejects requests with a JSON body such as
+    /// `{"error":"unauthenticated","reason":"missing session token"}`.
+    #[must_use]
+    pub fn with_json_errors(mut self, json_errors: bool) -> Self {
+        self.json_errors = json_errors;
+        self
+    }
 }
 
 /// The result of a successful session authentication.
@@ -65,6 +103,8 @@ pub struct AuthenticatedSession {
     pub session_state: SessionState,
     /// Whether the session cookie should be refreshed.
     pub should_refresh_cookie: bool,
+    /// When the session expires, taking a refresh into account.
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl<S, V: Clone> Layer<S> for SessionAuthLayer<V> {
@@ -75,6 +115,8 @@ impl<S, V: Clone> Layer<S> for SessionAuthLayer<V> {
             inner,
             auth_client: self.session_auth_client.clone(),
             no_auth: self.no_auth_endpoints.clone(),
+            cookie_config: self.cookie_config,
+            json_errors: self.json_errors,
         }
     }
 }
@@ -106,46 +148,63 @@ where
             return Box::pin(self.inner.call(request));
         }
 
+        // Skip if the session was already validated for this request
+        if request.extensions().get::<AuthenticatedSession>().is_some() {
+            return Box::pin(self.inner.call(request));
+        }
+
         let clone = self.inner.clone();
         let mut inner = std::mem::replace(&mut self.inner, clone);
         let mut validator = self.auth_client.clone();
+        let cookie_config = self.cookie_config;
+        let json_errors = self.json_errors;
 
         // Extract session token from cookies and authenticate the session
         Box::pin(async move {
-            let Some(cookie) = request.headers().get(COOKIE) else {
-                return Ok(Response::builder()
-                    .status(StatusCode::UNAUTHORIZED)
-                    .body(Body::from("missing cookies"))
-                    .unwrap());
-            };
-            let Some(token) = extract_session_token_cookie(cookie) else {
-                return Ok(Response::builder()
-                    .status(StatusCode::UNAUTHORIZED)
-                    .body(Body::from("missing session token"))
-                    .unwrap());
+            if !request.headers().contains_key(COOKIE) {
+                return Ok(unauthorized("missing cookies", json_errors));
+            }
+            let Some(token) =
+                extract_cookie_from_headers(SESSION_TOKEN_COOKIE_KEY, request.headers())
+            else {
+                return Ok(unauthorized("missing session token", json_errors));
             };
 
             match validator.authenticate_session(&token).await {
                 Ok(s) => {
-                    request.extensions_mut().insert(s.session_state);
+                    request.extensions_mut().insert(s.session_state.clone());
+                    request.extensions_mut().insert(SessionToken(token.clone()));
+                    request.extensions_mut().insert(s.clone());
 
                     let mut resp = inner.call(request).await?;
 
                     if s.should_refresh_cookie {
-                        set_session_token_cookie(&mut resp, &token);
+                        set_session_token_cookie(&mut resp, &token, &cookie_config);
                     }
 
                     Ok(resp)
                 }
-                Err(err) => Ok(Response::builder()
-                    .status(StatusCode::UNAUTHORIZED)
-                    .body(Body::from(err.to_string()))
-                    .unwrap()),
+                Err(err) => Ok(unauthorized(&err.to_string(), json_errors)),
             }
         })
     }
 }
 
+/// Returns a `401 Unauthorized` response with the reason as plain text
+/// or as a JSON error envelope.
+fn unauthorized(reason: &str, json: bool) -> Response<Body> {
+    let builder = Response::builder().status(StatusCode::UNAUTHORIZED);
+    let response = if json {
+        let body = serde_json::json!({ "error": "unauthenticated", "reason": reason });
+        builder
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+    } else {
+        builder.body(Body::from(reason.to_string()))
+    };
+    response.unwrap()
+}
+
 pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
 /// Error for [`SessionAuthClient::authenticate_session`].
@@ -159,11 +218,25 @@ pub enum AuthenticateSessionErr {
     Internal,
 }
 
-fn matches_pattern(pattern: &str, path: &str) -> bool {
-    let pattern_parts: Vec<&str> = pattern.split('/').collect();
+/// Matches a request path against a pattern.
+///
+/// A `*` segment matches exactly one segment, a trailing `**` segment
+/// matches any number of remaining segments.
+pub(crate) fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let mut pattern_parts: Vec<&str> = pattern.split('/').collect();
     let path_parts: Vec<&str> = path.split('/').collect();
 
-    if pattern_parts.len() != path_parts.len() {
+    let subtree = pattern_parts.last() == Some(&"**");
+    if subtree {
+        pattern_parts.pop();
+    }
+
+    let segment_count_matches = if subtree {
+        path_parts.len() >= pattern_parts.len()
+    } else {
+        path_parts.len() == pattern_parts.len()
+    };
+    if !segment_count_matches {
         return false;
     }
 
@@ -183,6 +256,8 @@ mod tests {
     use crate::session::SESSION_TOKEN_COOKIE_KEY;
     use std::future::Ready;
     use std::future::ready;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use http::header::SET_COOKIE;
     use rstest::rstest;
@@ -209,11 +284,23 @@ mod tests {
         Ok(AuthenticatedSession {
             session_state: SessionState::default(),
             should_refresh_cookie: true,
+            ..Default::default()
         }),
         Vec::new(),
         StatusCode::OK,
         Some("session_token=token; Max-Age=604800; Path=/; Secure; HttpOnly; SameSite=None")
     )]
+    #[case::session_token_in_second_cookie_header(
+        Request::builder()
+            .header("Cookie", "other=1")
+            .header("Cookie", format!("{}={}", SESSION_TOKEN_COOKIE_KEY, "token"))
+            .body(())
+            .unwrap(),
+        Ok(AuthenticatedSession::default()),
+        Vec::new(),
+        StatusCode::OK,
+        None
+    )]
     #[case::skip_preflight_requests(
         Request::builder().method("OPTIONS").body(()).unwrap(),
         Ok(AuthenticatedSession::default()),
@@ -270,11 +357,14 @@ mod tests {
     ) {
         // given
         let mut service = SessionAuthService {
-            inner: MockService::default(),
+            inner: MockService,
             auth_client: MockAuthClient {
                 response: validation_result,
+                calls: Arc::default(),
             },
             no_auth,
+            cookie_config: CookieConfig::default(),
+            json_errors: false,
         };
 
         // when
@@ -286,6 +376,122 @@ mod tests {
         assert_eq!(resp_set_cookies, want_set_cookies);
     }
 
+    #[rstest]
+    #[case::exact("/logout", "/logout", true)]
+    #[case::exact_mismatch("/logout", "/login", false)]
+    #[case::wildcard("/a/*/c", "/a/b/c", true)]
+    #[case::wildcard_too_many_segments("/a/*/c", "/a/b/b/c", false)]
+    #[case::wildcard_too_few_segments("/a/*/c", "/a/c", false)]
+    #[case::subtree("/public/**", "/public/a/b/c", true)]
+    #[case::subtree_single_segment("/public/**", "/public/a", true)]
+    #[case::subtree_root("/public/**", "/public", true)]
+    #[case::subtree_other_prefix("/public/**", "/private/a", false)]
+    #[case::subtree_partial_segment("/public/**", "/publicity/a", false)]
+    #[case::subtree_with_wildcard("/auth/*/**", "/auth/google/a/b", true)]
+    fn test_matches_pattern(#[case] pattern: &str, #[case] path: &str, #[case] want: bool) {
+        assert_eq!(matches_pattern(pattern, path), want);
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_inserts_session_token() {
+        // given
+        let mut service = SessionAuthService {
+            inner: EchoSessionTokenService,
+            auth_client: MockAuthClient {
+                response: Ok(AuthenticatedSession::default()),
+                calls: Arc::default(),
+            },
+            no_auth: Vec::new(),
+            cookie_config: CookieConfig::default(),
+            json_errors: false,
+        };
+        let cookie = format!("{}={}", SESSION_TOKEN_COOKIE_KEY, "token");
+        let request = Request::builder()
+            .header("Cookie", cookie)
+            .body(())
+            .unwrap();
+
+        // when
+        let resp = service.call(request).await.unwrap();
+
+        // then
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "token");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_json_errors() {
+        // given
+        let mut service = SessionAuthService {
+            inner: MockService,
+            auth_client: MockAuthClient {
+                response: Ok(AuthenticatedSession::default()),
+                calls: Arc::default(),
+            },
+            no_auth: Vec::new(),
+            cookie_config: CookieConfig::default(),
+            json_errors: true,
+        };
+        let request = Request::builder().body(()).unwrap();
+
+        // when
+        let resp = service.call(request).await.unwrap();
+
+        // then
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "unauthenticated", "reason": "missing cookies" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_validates_once_per_request() {
+        // given
+        let auth_client = MockAuthClient {
+            response: Ok(AuthenticatedSession::default()),
+            calls: Arc::default(),
+        };
+        let calls = auth_client.calls.clone();
+        let inner = SessionAuthService {
+            inner: MockService,
+            auth_client: auth_client.clone(),
+            no_auth: Vec::new(),
+            cookie_config: CookieConfig::default(),
+            json_errors: false,
+        };
+        let mut service = SessionAuthService {
+            inner,
+            auth_client,
+            no_auth: Vec::new(),
+            cookie_config: CookieConfig::default(),
+            json_errors: false,
+        };
+        let cookie = format!("{}={}", SESSION_TOKEN_COOKIE_KEY, "token");
+        let request = Request::builder()
+            .header("Cookie", cookie)
+            .body(())
+            .unwrap();
+
+        // when
+        let resp = service.call(request).await.unwrap();
+
+        // then
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
     #[derive(Clone, Default)]
     struct MockService;
 
@@ -309,9 +515,35 @@ mod tests {
         }
     }
 
+    /// Responds with the session token from the request extensions.
+    #[derive(Clone)]
+    struct EchoSessionTokenService;
+
+    impl<ReqBody> Service<Request<ReqBody>> for EchoSessionTokenService
+    where
+        ReqBody: Send + 'static,
+    {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+            let token = req.extensions().get::<SessionToken>().cloned();
+            ready(Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(Body::from(token.map(|t| t.0).unwrap_or_default()))
+                .unwrap()))
+        }
+    }
+
     #[derive(Clone)]
     struct MockAuthClient {
         response: Result<AuthenticatedSession, AuthenticateSessionErr>,
+        calls: Arc<AtomicUsize>,
     }
 
     #[async_trait]
@@ -320,6 +552,7 @@ mod tests {
             &mut self,
             _: &str,
         ) -> Result<AuthenticatedSession, AuthenticateSessionErr> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
             return self.response.clone();
         }
     }
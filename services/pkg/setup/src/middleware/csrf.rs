@@ -0,0 +1,303 @@
+use crate::cookie::extract_cookie_from_headers;
+use crate::middleware::auth::{BoxFuture, matches_pattern};
+use axum::body::Body;
+use http::{Method, Request, Response, StatusCode, header::CONTENT_TYPE};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// The name of the cookie that holds the csrf token.
+pub const CSRF_TOKEN_COOKIE_KEY: &str = "csrf_token";
+
+/// The header in which clients echo the csrf token.
+pub const CSRF_TOKEN_HEADER: &str = "x-csrf-token";
+
+/// Service produced by [`CsrfLayer`] that checks the csrf token of
+/// state-changing requests.
+#[derive(Clone)]
+pub struct CsrfService<S> {
+    /// The inner service.
+    pub inner: S,
+
+    /// Request uri paths for which the check should be skipped.
+    pub exempt: Vec<String>,
+
+    /// Whether rejections have a JSON body instead of plain text.
+    pub json_errors: bool,
+}
+
+/// Double-submit csrf layer.
+///
+/// Requests other than `GET`, `HEAD` and `OPTIONS` must send the
+/// [`CSRF_TOKEN_HEADER`] with the same value as the
+/// [`CSRF_TOKEN_COOKIE_KEY`] cookie. A cross-site page can make the
+/// browser send the cookie, but it cannot read it to set the header.
+#[derive(Clone)]
+pub struct CsrfLayer {
+    /// Request uri paths for which the check should be skipped, e.g. the
+    /// oauth callback, which is protected by the oauth state instead.
+    pub exempt_endpoints: Vec<String>,
+
+    /// Whether rejections have a JSON body instead of plain text.
+    pub json_errors: bool,
+}
+
+impl CsrfLayer {
+    /// Creates a new [`CsrfLayer`].
+    pub fn new(exempt_endpoints: Vec<String>) -> Self {
+        Self {
+            exempt_endpoints,
+            json_errors: false,
+        }
+    }
+
+    /// Rejects requests with a JSON body such as
+    /// `{"error":"forbidden","reason":"missing csrf token"}`.
+    #[must_use]
+    pub fn with_json_errors(mut self, json_errors: bool) -> Self {
+        self.json_errors = json_errors;
+        self
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CsrfService {
+            inner,
+            exempt: self.exempt_endpoints.clone(),
+            json_errors: self.json_errors,
+        }
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for CsrfService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // Safe methods do not change state
+        if matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS
+        ) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        // Allow certain paths without a csrf token
+        let req_path = request.uri().path();
+        if self.exempt.iter().any(|p| matches_pattern(p, req_path)) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        if let Err(reason) = check_csrf_token(request.headers()) {
+            let json_errors = self.json_errors;
+            return Box::pin(async move { Ok(forbidden(reason, json_errors)) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// Checks that the csrf header matches the csrf cookie.
+fn check_csrf_token(headers: &http::HeaderMap) -> Result<(), &'static str> {
+    let Some(header) = headers
+        .get(CSRF_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+    else {
+        return Err("missing csrf token");
+    };
+    let Some(cookie) = extract_cookie_from_headers(CSRF_TOKEN_COOKIE_KEY, headers) else {
+        return Err("missing csrf cookie");
+    };
+    if !constant_time_eq(header.as_bytes(), cookie.as_bytes()) {
+        return Err("csrf token mismatch");
+    }
+    Ok(())
+}
+
+/// Compares two byte slices in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Returns a `403 Forbidden` response with the reason as plain text
+/// or as a JSON error envelope.
+fn forbidden(reason: &str, json: bool) -> Response<Body> {
+    let builder = Response::builder().status(StatusCode::FORBIDDEN);
+    let response = if json {
+        let body = serde_json::json!({ "error": "forbidden", "reason": reason });
+        builder
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+    } else {
+        builder.body(Body::from(reason.to_string()))
+    };
+    response.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Ready;
+    use std::future::ready;
+
+    use rstest::rstest;
+    use tower::Service;
+
+    use super::*;
+
+    #[rstest]
+    #[case::valid_token(
+        Request::builder()
+            .method("POST")
+            .header("Cookie", format!("{CSRF_TOKEN_COOKIE_KEY}=token"))
+            .header(CSRF_TOKEN_HEADER, "token")
+            .body(())
+            .unwrap(),
+        Vec::new(),
+        StatusCode::OK,
+        None
+    )]
+    #[case::skip_get_requests(
+        Request::builder().method("GET").body(()).unwrap(),
+        Vec::new(),
+        StatusCode::OK,
+        None
+    )]
+    #[case::skip_preflight_requests(
+        Request::builder().method("OPTIONS").body(()).unwrap(),
+        Vec::new(),
+        StatusCode::OK,
+        None
+    )]
+    #[case::skip_exempt_endpoints_with_wildcard(
+        Request::builder()
+            .method("POST")
+            .uri("/auth/apple/callback")
+            .body(())
+            .unwrap(),
+        vec![String::from("/auth/*/callback")],
+        StatusCode::OK,
+        None
+    )]
+    #[case::forbidden_missing_token(
+        Request::builder()
+            .method("POST")
+            .header("Cookie", format!("{CSRF_TOKEN_COOKIE_KEY}=token"))
+            .body(())
+            .unwrap(),
+        Vec::new(),
+        StatusCode::FORBIDDEN,
+        Some("missing csrf token")
+    )]
+    #[case::forbidden_missing_cookie(
+        Request::builder()
+            .method("POST")
+            .header(CSRF_TOKEN_HEADER, "token")
+            .body(())
+            .unwrap(),
+        Vec::new(),
+        StatusCode::FORBIDDEN,
+        Some("missing csrf cookie")
+    )]
+    #[case::forbidden_mismatched_token(
+        Request::builder()
+            .method("DELETE")
+            .header("Cookie", format!("{CSRF_TOKEN_COOKIE_KEY}=token"))
+            .header(CSRF_TOKEN_HEADER, "other")
+            .body(())
+            .unwrap(),
+        Vec::new(),
+        StatusCode::FORBIDDEN,
+        Some("csrf token mismatch")
+    )]
+    #[tokio::test]
+    async fn test_csrf_middleware(
+        #[case] request: Request<()>,
+        #[case] exempt: Vec<String>,
+        #[case] want_status: StatusCode,
+        #[case] want_reason: Option<&str>,
+    ) {
+        // given
+        let mut service = CsrfService {
+            inner: MockService,
+            exempt,
+            json_errors: false,
+        };
+
+        // when
+        let resp = service.call(request).await.unwrap();
+
+        // then
+        assert_eq!(resp.status(), want_status);
+        if let Some(want_reason) = want_reason {
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, want_reason);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_csrf_middleware_json_errors() {
+        // given
+        let mut service = CsrfService {
+            inner: MockService,
+            exempt: Vec::new(),
+            json_errors: true,
+        };
+        let request = Request::builder().method("POST").body(()).unwrap();
+
+        // when
+        let resp = service.call(request).await.unwrap();
+
+        // then
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "forbidden", "reason": "missing csrf token" })
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct MockService;
+
+    impl<ReqBody> Service<Request<ReqBody>> for MockService
+    where
+        ReqBody: Send + 'static,
+    {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<ReqBody>) -> Self::Future {
+            ready(Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(Body::empty())
+                .unwrap()))
+        }
+    }
+}
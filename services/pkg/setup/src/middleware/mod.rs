@@ -1,5 +1,7 @@
 pub mod auth;
+pub mod csrf;
 pub mod tracing;
 pub use auth::SessionAuthClient;
+pub use csrf::CsrfLayer;
 pub use tracing::TracingGrpcServiceLayer;
 pub use tracing::TracingHttpServiceLayer;
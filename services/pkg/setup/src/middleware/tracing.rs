@@ -1,18 +1,64 @@
-use http::{Request, Response};
-use opentelemetry::{global, trace::TraceContextExt as _};
+use http::{HeaderMap, HeaderValue, Request, Response};
+use opentelemetry::{Context as OtelContext, global, trace::TraceContextExt as _};
 use opentelemetry_http::{HeaderExtractor, HeaderInjector};
 use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::{Code, Status};
 use tower::{Layer, Service, ServiceBuilder};
 use tower_http::classify::{GrpcErrorsAsFailures, ServerErrorsAsFailures, SharedClassifier};
-use tower_http::trace::{Trace, TraceLayer};
-use tracing::{Span, field, info_span};
+use tower_http::trace::{DefaultOnBodyChunk, DefaultOnEos, DefaultOnRequest, Trace, TraceLayer};
+use tracing::{Span, field, info, info_span};
 use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+use uuid::Uuid;
 
-type GrpcTraceService<S> =
-    Trace<TracePropagationService<S>, SharedClassifier<GrpcErrorsAsFailures>, MakeSpan>;
+/// The header and grpc metadata key that correlates a request across services.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
-type HttpTraceService<S> =
-    Trace<TracePropagationService<S>, SharedClassifier<ServerErrorsAsFailures>, MakeSpan>;
+/// The id of the request that is currently handled.
+///
+/// It is stored in the OpenTelemetry context of the request span, so that
+/// [`TracingServiceClient`] can forward it to downstream services.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Returns the request id of the current span, if any.
+    pub fn current() -> Option<Self> {
+        Span::current().context().get::<RequestId>().cloned()
+    }
+
+    /// Reuses the request id of an inbound request or generates a new one.
+    fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty())
+            .map(|id| Self(id.to_string()))
+            .unwrap_or_else(|| Self(Uuid::new_v4().to_string()))
+    }
+}
+
+type GrpcTraceService<S> = Trace<
+    TracePropagationService<S>,
+    SharedClassifier<GrpcErrorsAsFailures>,
+    MakeSpan,
+    DefaultOnRequest,
+    OnGrpcResponse,
+    DefaultOnBodyChunk,
+    OnGrpcEos,
+    (),
+>;
+
+type HttpTraceService<S> = Trace<
+    TracePropagationService<S>,
+    SharedClassifier<ServerErrorsAsFailures>,
+    MakeSpan,
+    DefaultOnRequest,
+    OnHttpResponse,
+    DefaultOnBodyChunk,
+    DefaultOnEos,
+    (),
+>;
 
 // A gRPC tracing layer. Extracts trace context and starts a span per request.
 #[derive(Clone)]
@@ -23,8 +69,15 @@ impl<S> Layer<S> for TracingGrpcServiceLayer {
 
     fn layer(&self, inner: S) -> Self::Service {
         ServiceBuilder::new()
-            .layer(TraceLayer::new_for_grpc().make_span_with(MakeSpan)) // creates request span
-            .layer(TracePropagationLayer::new()) // extracts trace context and sets trace id
+            .layer(
+                // creates request span and logs the outcome once per request
+                TraceLayer::new_for_grpc()
+                    .make_span_with(MakeSpan)
+                    .on_response(OnGrpcResponse)
+                    .on_eos(OnGrpcEos)
+                    .on_failure(()),
+            )
+            .layer(TracePropagationLayer::new()) // extracts trace context, sets trace and request id
             .service(inner)
     }
 }
@@ -38,8 +91,14 @@ impl<S> Layer<S> for TracingHttpServiceLayer {
 
     fn layer(&self, inner: S) -> Self::Service {
         ServiceBuilder::new()
-            .layer(TraceLayer::new_for_http().make_span_with(MakeSpan)) // creates request span
-            .layer(TracePropagationLayer::new()) // extracts trace context and sets trace id
+            .layer(
+                // creates request span and logs the outcome once per request
+                TraceLayer::new_for_http()
+                    .make_span_with(MakeSpan)
+                    .on_response(OnHttpResponse)
+                    .on_failure(()),
+            )
+            .layer(TracePropagationLayer::new()) // extracts trace context, sets trace and request id
             .service(inner)
     }
 }
@@ -86,9 +145,13 @@ where
             propagator.extract(&HeaderExtractor(req.headers()))
         });
 
+        // Reuse the inbound request id or start a new one
+        let request_id = RequestId::from_headers(req.headers());
+
         // Make the current span a child of the extracted context
         let span = Span::current();
-        span.set_parent(parent_context);
+        span.record("request_id", request_id.0.as_str());
+        span.set_parent(parent_context.with_value(request_id));
 
         // Sets the trace ID in the current span
         let trace_id = span.context().span().span_context().trace_id();
@@ -108,17 +171,77 @@ impl<B> tower_http::trace::MakeSpan<B> for MakeSpan {
         let method = req.method().as_str();
         let path = req.uri().path();
         let span_name = format!("{method} {path}");
+        let is_grpc = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .is_some_and(|value| value.as_bytes().starts_with(b"application/grpc"));
         info_span!(
             "request",
             otel.name = span_name.as_str(),
             http.request.method = method,
             url.path = path,
-            trace_id = field::Empty
+            grpc.method = is_grpc.then_some(path),
+            grpc.status = field::Empty,
+            http.status = field::Empty,
+            elapsed_ms = field::Empty,
+            trace_id = field::Empty,
+            request_id = field::Empty
         )
     }
 }
 
-/// A client side interceptor that injects the current trace context into outgoing HTTP requests.
+/// Records the status of a grpc request that failed before sending a body.
+///
+/// Such responses carry the status in the headers instead of the trailers.
+#[derive(Debug, Clone, Copy)]
+pub struct OnGrpcResponse;
+
+impl<B> tower_http::trace::OnResponse<B> for OnGrpcResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        if let Some(status) = Status::from_header_map(response.headers()) {
+            record_grpc_status(status.code(), latency, span);
+        }
+    }
+}
+
+/// Records the status of a grpc request once its body is sent.
+#[derive(Debug, Clone, Copy)]
+pub struct OnGrpcEos;
+
+impl tower_http::trace::OnEos for OnGrpcEos {
+    fn on_eos(self, trailers: Option<&HeaderMap>, stream_duration: Duration, span: &Span) {
+        let code = trailers
+            .and_then(Status::from_header_map)
+            .map_or(Code::Unknown, |status| status.code());
+        record_grpc_status(code, stream_duration, span);
+    }
+}
+
+/// Records the status of a http request.
+#[derive(Debug, Clone, Copy)]
+pub struct OnHttpResponse;
+
+impl<B> tower_http::trace::OnResponse<B> for OnHttpResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        let status = response.status().as_u16();
+        let elapsed_ms = latency.as_millis();
+        span.record("http.status", status);
+        span.record("elapsed_ms", elapsed_ms);
+        info!(parent: span, http.status = status, elapsed_ms, "finished request");
+    }
+}
+
+/// Records the grpc status and latency on the request span and logs them.
+fn record_grpc_status(code: Code, latency: Duration, span: &Span) {
+    let status = code as i32;
+    let elapsed_ms = latency.as_millis();
+    span.record("grpc.status", status);
+    span.record("elapsed_ms", elapsed_ms);
+    info!(parent: span, grpc.status = status, elapsed_ms, "finished grpc request");
+}
+
+/// A client side interceptor that injects the current trace context and
+/// request id into outgoing HTTP requests.
 #[derive(Clone, Copy)]
 pub struct TracingServiceClient<S> {
     inner: S,
@@ -145,11 +268,261 @@ where
     }
 
     fn call(&mut self, mut req: http::Request<ReqBody>) -> Self::Future {
+        let context = Span::current().context();
         global::get_text_map_propagator(|propagator| {
-            let context = Span::current().context();
             propagator.inject_context(&context, &mut HeaderInjector(req.headers_mut()));
         });
+        inject_request_id(&context, req.headers_mut());
 
         self.inner.call(req)
     }
 }
+
+/// Sets the request id of the context on outgoing headers.
+fn inject_request_id(context: &OtelContext, headers: &mut HeaderMap) {
+    let Some(RequestId(id)) = context.get::<RequestId>() else {
+        return;
+    };
+    if let Ok(value) = HeaderValue::from_str(id) {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceFlags, TraceId, TraceState, TracerProvider as _,
+    };
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::convert::Infallible;
+    use tower::{ServiceExt as _, service_fn};
+    use tracing::Instrument as _;
+    use tracing::subscriber::DefaultGuard;
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    /// Installs a subscriber that tracks the OpenTelemetry context of spans.
+    fn otel_subscriber() -> DefaultGuard {
+        let tracer = SdkTracerProvider::builder().build().tracer("test");
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::set_default(subscriber)
+    }
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    #[test]
+    fn test_request_id_from_headers() {
+        // given
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("abc"));
+
+        // when
+        let reused = RequestId::from_headers(&headers);
+        let generated = RequestId::from_headers(&HeaderMap::new());
+
+        // then
+        assert_eq!(reused, RequestId(String::from("abc")));
+        assert!(Uuid::parse_str(&generated.0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_client_injects_request_id() {
+        // given
+        let _guard = otel_subscriber();
+        let inner = service_fn(|req: Request<()>| async move {
+            let id = req.headers().get(REQUEST_ID_HEADER).cloned();
+            Ok::<_, Infallible>(id)
+        });
+        let span = info_span!("request");
+        span.set_parent(OtelContext::new().with_value(RequestId(String::from("abc"))));
+
+        // when
+        let got = TracingServiceClient::new(inner)
+            .oneshot(Request::new(()))
+            .instrument(info_span!(parent: &span, "handler"))
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(got, Some(HeaderValue::from_static("abc")));
+    }
+
+    #[tokio::test]
+    async fn test_server_reads_request_id() {
+        // given
+        let _guard = otel_subscriber();
+        let inner = service_fn(|_: Request<String>| async move {
+            let mut resp = Response::new(String::new());
+            resp.extensions_mut().insert(RequestId::current());
+            Ok::<_, Infallible>(resp)
+        });
+        let mut req = Request::new(String::new());
+        req.headers_mut()
+            .insert(REQUEST_ID_HEADER, HeaderValue::from_static("abc"));
+
+        // when
+        let got = TracingGrpcServiceLayer
+            .layer(inner)
+            .oneshot(req)
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(
+            got.extensions().get::<Option<RequestId>>(),
+            Some(&Some(RequestId(String::from("abc"))))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_extracts_traceparent() {
+        // given
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let _guard = otel_subscriber();
+        let inner = service_fn(|_: Request<String>| async move {
+            let mut resp = Response::new(String::new());
+            let context = Span::current().context();
+            resp.extensions_mut()
+                .insert(context.span().span_context().clone());
+            Ok::<_, Infallible>(resp)
+        });
+        let mut req = Request::new(String::new());
+        let traceparent = format!("00-{TRACE_ID}-00f067aa0ba902b7-01");
+        req.headers_mut()
+            .insert("traceparent", HeaderValue::from_str(&traceparent).unwrap());
+
+        // when
+        let got = TracingGrpcServiceLayer
+            .layer(inner)
+            .oneshot(req)
+            .await
+            .unwrap();
+
+        // then
+        let span_context = got.extensions().get::<SpanContext>().unwrap();
+        assert!(span_context.is_valid());
+        assert_eq!(
+            span_context.trace_id(),
+            TraceId::from_hex(TRACE_ID).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_injects_traceparent() {
+        // given
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let _guard = otel_subscriber();
+        let inner = service_fn(|req: Request<()>| async move {
+            let traceparent = req.headers().get("traceparent").cloned();
+            Ok::<_, Infallible>(traceparent)
+        });
+        let parent = OtelContext::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_hex(TRACE_ID).unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+        let span = info_span!("request");
+        span.set_parent(parent);
+
+        // when
+        let got = TracingServiceClient::new(inner)
+            .oneshot(Request::new(()))
+            .instrument(span)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // then
+        let traceparent = got.to_str().unwrap();
+        assert!(traceparent.starts_with(&format!("00-{TRACE_ID}-")));
+    }
+
+    /// Collects everything the fmt layer writes.
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogBuffer {
+        /// Installs a subscriber that logs into the buffer.
+        fn subscriber(&self) -> DefaultGuard {
+            let buffer = self.clone();
+            let subscriber = tracing_subscriber::registry().with(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(move || buffer.clone()),
+            );
+            tracing::subscriber::set_default(subscriber)
+        }
+
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_layer_records_status() {
+        // given
+        let logs = LogBuffer::default();
+        let _guard = logs.subscriber();
+        let inner = service_fn(|_: Request<String>| async move {
+            Ok::<_, Infallible>(Status::unavailable("down").into_http::<String>())
+        });
+        let mut req = Request::new(String::new());
+        *req.uri_mut() = "/auth.AuthService/Ping".parse().unwrap();
+        req.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/grpc"),
+        );
+
+        // when
+        TracingGrpcServiceLayer
+            .layer(inner)
+            .oneshot(req)
+            .await
+            .unwrap();
+
+        // then
+        let logs = logs.contents();
+        assert_eq!(logs.matches("finished grpc request").count(), 1);
+        assert!(logs.contains("grpc.method=\"/auth.AuthService/Ping\""));
+        assert!(logs.contains(&format!("grpc.status={}", Code::Unavailable as i32)));
+        assert!(logs.contains("elapsed_ms="));
+    }
+
+    #[tokio::test]
+    async fn test_http_layer_records_status() {
+        // given
+        let logs = LogBuffer::default();
+        let _guard = logs.subscriber();
+        let inner = service_fn(|_: Request<String>| async move {
+            let mut resp = Response::new(String::new());
+            *resp.status_mut() = http::StatusCode::NOT_FOUND;
+            Ok::<_, Infallible>(resp)
+        });
+
+        // when
+        TracingHttpServiceLayer
+            .layer(inner)
+            .oneshot(Request::new(String::new()))
+            .await
+            .unwrap();
+
+        // then
+        let logs = logs.contents();
+        assert_eq!(logs.matches("finished request").count(), 1);
+        assert!(logs.contains("http.status=404"));
+    }
+}
@@ -1,8 +1,13 @@
 use chrono::Duration;
+use tonic::metadata::{MetadataMap, MetadataValue};
 
 /// The session token cookie key.
 pub const SESSION_TOKEN_COOKIE_KEY: &str = "session_token";
 
+/// The grpc metadata key that carries the authenticated user id from the
+/// gateway to the services behind it.
+pub const USER_ID_METADATA_KEY: &str = "x-user-id";
+
 /// The session token expiry duration.
 pub const SESSION_TOKEN_EXPIRY_DURATION: Duration = Duration::days(7);
 
@@ -18,4 +23,63 @@ impl SessionState {
     pub fn new(user_id: String) -> Self {
         Self { user_id }
     }
+
+    /// Reads the session from the [`USER_ID_METADATA_KEY`] metadata of
+    /// a grpc request, if it is set.
+    ///
+    /// The metadata is trusted, so services must only be reachable
+    /// through the gateway, which sets it after authenticating the
+    /// session.
+    pub fn from_metadata(metadata: &MetadataMap) -> Option<Self> {
+        let user_id = metadata.get(USER_ID_METADATA_KEY)?.to_str().ok()?;
+        (!user_id.is_empty()).then(|| Self::new(user_id.to_string()))
+    }
+
+    /// Sets the [`USER_ID_METADATA_KEY`] metadata of a grpc request.
+    ///
+    /// # Panics
+    /// If the user id is not a valid metadata value, which a uuid always is.
+    pub fn insert_into(&self, metadata: &mut MetadataMap) {
+        let value = MetadataValue::try_from(self.user_id.as_str()).expect("valid user id");
+        metadata.insert(USER_ID_METADATA_KEY, value);
+    }
+}
+
+/// The raw session token of an authenticated request.
+///
+/// Inserted into the request extensions by the auth middleware, so that
+/// handlers don't need to parse the `Cookie` header again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionToken(pub String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_state_metadata_round_trip() {
+        // given
+        let mut metadata = MetadataMap::new();
+
+        // when
+        SessionState::new(String::from("user-id")).insert_into(&mut metadata);
+
+        // then
+        let got = SessionState::from_metadata(&metadata).map(|s| s.user_id);
+        assert_eq!(got.as_deref(), Some("user-id"));
+    }
+
+    #[test]
+    fn test_session_state_missing_metadata() {
+        // given
+        let mut metadata = MetadataMap::new();
+        metadata.insert(USER_ID_METADATA_KEY, MetadataValue::from_static(""));
+
+        // when
+        let got = SessionState::from_metadata(&metadata);
+
+        // then
+        assert!(got.is_none());
+        assert!(SessionState::from_metadata(&MetadataMap::new()).is_none());
+    }
 }
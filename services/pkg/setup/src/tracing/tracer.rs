@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::str::FromStr;
 
 use opentelemetry::global;
 use opentelemetry::trace::TracerProvider;
@@ -6,39 +7,179 @@ use opentelemetry_otlp::{SpanExporter, WithExportConfig as _};
 use opentelemetry_sdk::trace::SdkTracerProvider;
 use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator};
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::ParseError;
 use tracing_subscriber::layer::SubscriberExt as _;
 use tracing_subscriber::util::SubscriberInitExt as _;
 
+/// The log filter if `RUST_LOG` is unset.
+pub const DEFAULT_LOG_FILTER: &str = "info,h2=error,tonic=error,tower=error,tower_http=error";
+
+/// Where spans are exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TracingExporter {
+    /// Spans are not exported, only logs are printed.
+    None,
+    /// Spans are printed to stdout.
+    Stdout,
+    /// Spans are exported to the OpenTelemetry collector.
+    Otlp,
+}
+
+impl FromStr for TracingExporter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "stdout" => Ok(Self::Stdout),
+            "otlp" => Ok(Self::Otlp),
+            _ => Err(format!(
+                "invalid TRACING_EXPORTER '{s}', expected none, stdout or otlp"
+            )),
+        }
+    }
+}
+
+impl TracingExporter {
+    /// Reads `TRACING_EXPORTER`, falling back to `none` for `APP_ENV=local`
+    /// and to `otlp` otherwise.
+    ///
+    /// # Errors
+    /// - the variable is not one of none, stdout or otlp
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("TRACING_EXPORTER") {
+            Ok(exporter) => exporter.parse(),
+            Err(_) if std::env::var("APP_ENV").unwrap_or_default() == "local" => Ok(Self::None),
+            Err(_) => Ok(Self::Otlp),
+        }
+    }
+}
+
 /// Initializes OpenTelemetry tracing.
 ///
-/// It allows tracing spans to be exported to backends like Jaeger.
+/// It allows tracing spans to be exported to backends like Jaeger, see
+/// [`TracingExporter::from_env`]. Spans carry the trace context even if
+/// they are not exported, so shutting down the returned provider is a
+/// no-op without an exporter.
 pub fn init_tracer(service_name: &'static str) -> Result<SdkTracerProvider, Box<dyn Error>> {
-    let mut endpoint = "http://otel-collector:4317";
-    if std::env::var("APP_ENV").unwrap_or_default() == "local" {
-        endpoint = "http://localhost:4317";
-    }
-    let span_exporter = SpanExporter::builder()
-        .with_tonic()
-        .with_endpoint(endpoint)
-        .build()
-        .map_err(|e| format!("failed to build span exporter: {e}"))?;
-    let tracer_provider = SdkTracerProvider::builder()
-        .with_resource(Resource::builder().with_service_name(service_name).build())
-        .with_batch_exporter(span_exporter)
-        .build();
+    let exporter = TracingExporter::from_env()?;
+    let env_filter = env_filter(std::env::var("RUST_LOG").ok().as_deref())
+        .map_err(|e| format!("invalid RUST_LOG: {e}"))?;
+    let tracer_provider = tracer_provider(service_name, exporter)?;
 
     global::set_text_map_propagator(TraceContextPropagator::new());
     global::set_tracer_provider(tracer_provider.clone());
 
-    let env_filter = EnvFilter::new("info,h2=error,tonic=error,tower=error,tower_http=error");
-
     let tracer = tracer_provider.tracer(service_name);
     let otel_layer = tracing_opentelemetry::layer().with_tracer(tracer);
+    let fmt_layer = (exporter == TracingExporter::None).then(tracing_subscriber::fmt::layer);
 
     tracing_subscriber::registry()
         .with(env_filter)
         .with(otel_layer)
+        .with(fmt_layer)
         .init();
 
     Ok(tracer_provider)
 }
+
+/// Parses the log filter, e.g. `info,auth=debug`, falling back to the
+/// [`DEFAULT_LOG_FILTER`]. It applies to spans as well as to logs.
+fn env_filter(directives: Option<&str>) -> Result<EnvFilter, ParseError> {
+    EnvFilter::builder().parse(directives.unwrap_or(DEFAULT_LOG_FILTER))
+}
+
+/// Builds the tracer provider that exports spans with the given exporter.
+fn tracer_provider(
+    service_name: &'static str,
+    exporter: TracingExporter,
+) -> Result<SdkTracerProvider, Box<dyn Error>> {
+    let builder = SdkTracerProvider::builder()
+        .with_resource(Resource::builder().with_service_name(service_name).build());
+
+    let tracer_provider = match exporter {
+        TracingExporter::None => builder.build(),
+        TracingExporter::Stdout => builder
+            .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+            .build(),
+        TracingExporter::Otlp => {
+            let mut endpoint = "http://otel-collector:4317";
+            if std::env::var("APP_ENV").unwrap_or_default() == "local" {
+                endpoint = "http://localhost:4317";
+            }
+            let span_exporter = SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint)
+                .build()
+                .map_err(|e| format!("failed to build span exporter: {e}"))?;
+            builder.with_batch_exporter(span_exporter).build()
+        }
+    };
+
+    Ok(tracer_provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tracing::Level;
+
+    #[rstest]
+    #[case::none("none", Ok(TracingExporter::None))]
+    #[case::stdout("stdout", Ok(TracingExporter::Stdout))]
+    #[case::otlp("otlp", Ok(TracingExporter::Otlp))]
+    #[case::invalid(
+        "jaeger",
+        Err(String::from("invalid TRACING_EXPORTER 'jaeger', expected none, stdout or otlp"))
+    )]
+    fn test_parse_tracing_exporter(
+        #[case] value: &str,
+        #[case] want: Result<TracingExporter, String>,
+    ) {
+        assert_eq!(value.parse::<TracingExporter>(), want);
+    }
+
+    #[rstest]
+    #[case::none(TracingExporter::None)]
+    #[case::stdout(TracingExporter::Stdout)]
+    fn test_shutdown_without_collector(#[case] exporter: TracingExporter) {
+        // given
+        let tracer_provider = tracer_provider("test", exporter).unwrap();
+
+        // when
+        let got = tracer_provider.shutdown();
+
+        // then
+        assert!(got.is_ok());
+    }
+
+    #[rstest]
+    #[case::default(None, Level::INFO, true)]
+    #[case::default_debug(None, Level::DEBUG, false)]
+    #[case::custom(Some("warn,setup=debug"), Level::DEBUG, true)]
+    #[case::custom_quiet(Some("info,setup=warn"), Level::INFO, false)]
+    fn test_env_filter(
+        #[case] directives: Option<&str>,
+        #[case] level: Level,
+        #[case] want_enabled: bool,
+    ) {
+        // given
+        let filter = env_filter(directives).unwrap();
+        let subscriber = tracing_subscriber::registry().with(filter);
+
+        // when
+        let got = tracing::subscriber::with_default(subscriber, || match level {
+            Level::DEBUG => tracing::span_enabled!(Level::DEBUG),
+            _ => tracing::span_enabled!(Level::INFO),
+        });
+
+        // then
+        assert_eq!(got, want_enabled);
+    }
+
+    #[test]
+    fn test_env_filter_invalid() {
+        assert!(env_filter(Some("info,setup=loud")).is_err());
+    }
+}
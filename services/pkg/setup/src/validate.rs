@@ -3,6 +3,14 @@ use std::str::FromStr;
 use tonic::{Code, Status};
 use uuid::Uuid;
 
+/// Parses a user id and records it on the current span.
+///
+/// # Errors
+/// - [`ValidateUserError::MissingUserId`] if the id is empty
+/// - [`ValidateUserError::InvalidUserId`] if the id is not a UUID
+///
+/// Both map to `InvalidArgument`, with different messages so that
+/// clients can tell them apart.
 pub fn validate_user_id(user_id: &str) -> Result<Uuid, ValidateUserError> {
     if user_id.is_empty() {
         return Err(ValidateUserError::MissingUserId);
@@ -17,6 +25,55 @@ pub fn validate_user_id(user_id: &str) -> Result<Uuid, ValidateUserError> {
     Ok(user_uuid)
 }
 
+/// Checks that an email is syntactically valid.
+///
+/// The check is conservative rather than complete: it requires a
+/// single `@`, a local part without whitespace or empty dot-separated
+/// parts, and a domain of at least two labels made of letters, digits
+/// and inner hyphens. Quoted local parts and ip literals are rejected.
+///
+/// # Errors
+/// - [`ValidateUserError::MissingEmail`] if the email is empty
+/// - [`ValidateUserError::InvalidEmail`] if the email is malformed
+pub fn validate_email(email: &str) -> Result<&str, ValidateUserError> {
+    if email.is_empty() {
+        return Err(ValidateUserError::MissingEmail);
+    }
+
+    let invalid = || ValidateUserError::InvalidEmail(email.to_string());
+    if email.len() > 254 {
+        return Err(invalid());
+    }
+    let Some((local, domain)) = email.split_once('@') else {
+        return Err(invalid());
+    };
+
+    let valid_local = !local.is_empty()
+        && local.len() <= 64
+        && local.split('.').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_graphic() && !"@\"(),:;<>[\\]".contains(c))
+        });
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let valid_domain = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if !valid_local || !valid_domain {
+        return Err(invalid());
+    }
+
+    Ok(email)
+}
+
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum ValidateUserError {
@@ -24,15 +81,84 @@ pub enum ValidateUserError {
     MissingUserId,
     #[error("invalid user id: {0}")]
     InvalidUserId(String),
+    #[error("missing email")]
+    MissingEmail,
+    #[error("invalid email: {0}")]
+    InvalidEmail(String),
 }
 
 impl From<ValidateUserError> for Status {
     fn from(err: ValidateUserError) -> Self {
         let code = match err {
-            ValidateUserError::MissingUserId | ValidateUserError::InvalidUserId(_) => {
-                Code::InvalidArgument
-            }
+            ValidateUserError::MissingUserId
+            | ValidateUserError::InvalidUserId(_)
+            | ValidateUserError::MissingEmail
+            | ValidateUserError::InvalidEmail(_) => Code::InvalidArgument,
         };
         Status::new(code, err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::missing_id("", "missing user id")]
+    #[case::not_a_uuid("not-uuid", "invalid user id: not-uuid")]
+    fn test_validate_user_id_error(#[case] user_id: &str, #[case] want_message: &str) {
+        // when
+        let got = validate_user_id(user_id).map_err(Status::from);
+
+        // then
+        let err = got.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), want_message);
+    }
+
+    #[rstest]
+    #[case::simple("user@example.com")]
+    #[case::subdomain("user@mail.example.co.uk")]
+    #[case::plus_tag("first.last+tag@example.com")]
+    #[case::hyphenated_domain("user@my-company.com")]
+    fn test_validate_email(#[case] email: &str) {
+        // when
+        let got = validate_email(email);
+
+        // then
+        assert_eq!(got.unwrap(), email);
+    }
+
+    #[rstest]
+    #[case::empty("", "missing email")]
+    #[case::missing_at("user.example.com", "invalid email: user.example.com")]
+    #[case::two_ats("user@host@example.com", "invalid email: user@host@example.com")]
+    #[case::missing_local("@example.com", "invalid email: @example.com")]
+    #[case::trailing_dot_domain("user@example.com.", "invalid email: user@example.com.")]
+    #[case::single_label_domain("user@localhost", "invalid email: user@localhost")]
+    #[case::consecutive_dots("first..last@example.com", "invalid email: first..last@example.com")]
+    #[case::whitespace("first last@example.com", "invalid email: first last@example.com")]
+    #[case::hyphen_label("user@-example.com", "invalid email: user@-example.com")]
+    fn test_validate_email_error(#[case] email: &str, #[case] want_message: &str) {
+        // when
+        let got = validate_email(email).map_err(Status::from);
+
+        // then
+        let err = got.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(err.message(), want_message);
+    }
+
+    #[test]
+    fn test_validate_user_id() {
+        // given
+        let user_id = "6f8e5a8c-2f41-4b4e-9d43-3c5c8c1b1d6e";
+
+        // when
+        let got = validate_user_id(user_id);
+
+        // then
+        assert_eq!(got.unwrap(), Uuid::from_str(user_id).unwrap());
+    }
+}
@@ -1,5 +1,6 @@
 use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod, tokio_postgres};
 use refinery::Runner;
+use std::collections::HashMap;
 use std::error::Error;
 use std::ops::DerefMut;
 use std::path::Path;
@@ -9,62 +10,112 @@ use testcontainers::{
     core::{ContainerPort, WaitFor},
     runners::AsyncRunner,
 };
-use tokio::sync::OnceCell;
+use tokio::sync::{Mutex, OnceCell};
 use tonic::{Code, Response, Status};
 
+/// The port postgres listens on inside the container.
+const PG_PORT: u16 = 5432;
+
+/// The postgres image tag used unless `TEST_PG_TAG` is set.
+pub const DEFAULT_POSTGRES_IMAGE_TAG: &str = "16";
+
+/// Returns the tag of the postgres image to test against, read from
+/// `TEST_PG_TAG` so CI can match the postgres version of production.
+pub fn postgres_image_tag() -> String {
+    std::env::var("TEST_PG_TAG")
+        .ok()
+        .filter(|tag| !tag.is_empty())
+        .unwrap_or_else(|| String::from(DEFAULT_POSTGRES_IMAGE_TAG))
+}
+
 /// Represents a test database running in a container.
 struct TestDb {
-    /// The underlying PostgreSQL container.
-    postgres: ContainerAsync<GenericImage>,
+    /// The underlying PostgreSQL container, taken when it is removed
+    /// on shutdown.
+    postgres: std::sync::Mutex<Option<ContainerAsync<GenericImage>>>,
+    /// The host under which the container is reachable.
+    host: String,
+    /// The host port mapped to [`PG_PORT`].
+    port: u16,
+    /// The migrated pool of each service's database, keyed by service name.
+    pools: Mutex<HashMap<String, Pool>>,
 }
 
 /// A global singleton holding the test database.
 /// OnceCell ensures the DB is started only once across all tests.
 static TEST_DB: OnceCell<TestDb> = OnceCell::const_new();
 
-/// Returns a connection pool to the test database.
+/// Returns a connection pool to the `{service_name}_db` test database.
 ///
-/// If the test database hasn’t been started yet, it will start it first.
+/// All services share one container, which is started on the first
+/// call. Each service's database is created and migrated on its first
+/// call, later calls return the cached pool.
 pub async fn get_test_db(
     service_name: &str,
     migrations: impl AsRef<Path>,
 ) -> Result<Pool, Box<dyn Error>> {
-    let db = TEST_DB
-        .get_or_init(|| async { start_test_db(service_name, migrations).await.unwrap() })
-        .await;
-    let pool = create_connection_pool(service_name, &db.postgres).await?;
+    let db = test_db().await;
+    let mut pools = db.pools.lock().await;
+    if let Some(pool) = pools.get(service_name) {
+        return Ok(pool.clone());
+    }
+
+    let dbname = format!("{service_name}_db");
+    create_database(db, &dbname).await?;
+    let pool = create_connection_pool(db, &dbname)?;
+    run_migrations(&pool, migrations).await?;
+
+    pools.insert(service_name.to_string(), pool.clone());
     Ok(pool)
 }
 
-/// Shutdown postgres container when the process exits.
+/// Returns the host and port of the test database.
+///
+/// If the test database hasn’t been started yet, it will start it first.
+pub async fn get_test_db_address() -> Result<(String, u16), Box<dyn Error>> {
+    let db = test_db().await;
+    Ok((db.host.clone(), db.port))
+}
+
+/// Removes the postgres container when the process exits.
 ///
 /// Note:
 /// A static OnceCell does not automatically Drop when the program is
 /// terminated. That means test containers won’t be cleaned up
-/// automatically thus we explicitly stop the postgres container here.
+/// automatically thus we explicitly remove the postgres container here.
+/// It is removed through the testcontainers client, so this works with
+/// whatever container runtime started it, e.g. rootless Podman.
 ///
 /// For more context, see:  
 /// <https://github.com/testcontainers/testcontainers-rs/issues/707>
 #[dtor::dtor]
 fn on_shutdown() {
-    let Some(test_db) = TEST_DB.get() else {
+    let Some(postgres) = TEST_DB
+        .get()
+        .and_then(|db| db.postgres.lock().unwrap_or_else(|e| e.into_inner()).take())
+    else {
         return;
     };
-    let container_id = test_db.postgres.id();
 
-    std::process::Command::new("docker")
-        .args(["container", "rm", "-f", container_id])
-        .output()
-        .expect("failed to stop testcontainer");
+    // The runtime of the tests is gone by now.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build runtime to remove testcontainer");
+    if let Err(e) = runtime.block_on(postgres.rm()) {
+        eprintln!("failed to remove testcontainer: {e}");
+    }
 }
 
-async fn start_test_db(
-    service_name: &str,
-    migrations: impl AsRef<Path>,
-) -> Result<TestDb, Box<dyn Error>> {
-    let pg_port = 5432;
-    let postgres = GenericImage::new("postgres", "latest")
-        .with_exposed_port(ContainerPort::Tcp(pg_port))
+async fn test_db() -> &'static TestDb {
+    TEST_DB
+        .get_or_init(|| async { start_test_db().await.unwrap() })
+        .await
+}
+
+async fn start_test_db() -> Result<TestDb, Box<dyn Error>> {
+    let postgres = GenericImage::new(String::from("postgres"), postgres_image_tag())
+        .with_exposed_port(ContainerPort::Tcp(PG_PORT))
         .with_wait_for(WaitFor::message_on_stdout(
             "database system is ready to accept connections",
         ))
@@ -76,39 +127,57 @@ async fn start_test_db(
             "/docker-entrypoint-initdb.d/init.sql",
             include_bytes!("../../../../infrastructure/db/init.sql").to_vec(),
         )
-        .with_env_var("PGPORT", pg_port.to_string())
+        .with_env_var("PGPORT", PG_PORT.to_string())
         .with_env_var("POSTGRES_USER", "postgres")
         .with_env_var("POSTGRES_PASSWORD", "postgres")
         .with_env_var("POSTGRES_DB", "postgres")
         .start()
         .await
         .expect("Failed to start postgres");
+    let host = postgres.get_host().await?.to_string();
+    let port = postgres.get_host_port_ipv4(PG_PORT).await?;
 
-    let pool = create_connection_pool(service_name, &postgres).await?;
+    Ok(TestDb {
+        postgres: std::sync::Mutex::new(Some(postgres)),
+        host,
+        port,
+        pools: Mutex::default(),
+    })
+}
 
+/// Creates the database unless it exists, e.g. from `init.sql`.
+async fn create_database(db: &TestDb, dbname: &str) -> Result<(), Box<dyn Error>> {
+    let pool = create_connection_pool(db, "postgres")?;
+    let client = pool.get().await?;
+    let exists = client
+        .query_opt("SELECT 1 FROM pg_database WHERE datname = $1", &[&dbname])
+        .await?
+        .is_some();
+    if !exists {
+        client
+            .batch_execute(&format!("CREATE DATABASE \"{dbname}\""))
+            .await?;
+    }
+    Ok(())
+}
+
+async fn run_migrations(pool: &Pool, migrations: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
     let mut connection = pool.get().await?;
     let migrations = refinery::load_sql_migrations(migrations)?;
     let _ = Runner::new(&migrations)
         .run_async(connection.deref_mut().deref_mut())
         .await?;
-
-    Ok(TestDb { postgres })
+    Ok(())
 }
 
-async fn create_connection_pool(
-    service_name: &str,
-    postgres: &ContainerAsync<GenericImage>,
-) -> Result<Pool, Box<dyn Error>> {
-    let host = postgres.get_host().await?;
-    let port = postgres.get_host_port_ipv4(5432).await?;
-
+fn create_connection_pool(db: &TestDb, dbname: &str) -> Result<Pool, Box<dyn Error>> {
     let mut config = tokio_postgres::Config::new();
     config
-        .dbname(format!("{service_name}_db"))
+        .dbname(dbname)
         .user("postgres")
         .password("postgres")
-        .host(host.to_string())
-        .port(port);
+        .host(&db.host)
+        .port(db.port);
 
     let pool = Pool::builder(Manager::from_config(
         config,
@@ -124,14 +193,99 @@ async fn create_connection_pool(
 }
 
 /// Asserts that a gRPC response matches the expected result.
+///
+/// Mismatching responses or codes are printed as a diff.
 pub fn assert_response<T: PartialEq + std::fmt::Debug>(
     got: Result<Response<T>, Status>,
     want: Result<T, Code>,
 ) {
     match (got, want) {
-        (Ok(got), Ok(want)) => assert_eq!(got.into_inner(), want),
-        (Err(got), Err(want)) => assert_eq!(got.code(), want),
+        (Ok(got), Ok(want)) => pretty_assertions::assert_eq!(got.into_inner(), want),
+        (Err(got), Err(want)) => pretty_assertions::assert_eq!(got.code(), want),
         (Ok(got), Err(want)) => panic!("left: {got:?}\nright: {want}"),
         (Err(got), Ok(want)) => panic!("left: {got}\nright: {want:?}"),
     }
 }
+
+/// Asserts that a gRPC call failed with `want_code` and a message that
+/// contains `want_message_contains`.
+pub fn assert_status<T: std::fmt::Debug>(
+    got: Result<Response<T>, Status>,
+    want_code: Code,
+    want_message_contains: &str,
+) {
+    let got = match got {
+        Ok(got) => panic!("left: {got:?}\nright: {want_code}"),
+        Err(got) => got,
+    };
+    pretty_assertions::assert_eq!(got.code(), want_code);
+    assert!(
+        got.message().contains(want_message_contains),
+        "message {:?} does not contain {want_message_contains:?}",
+        got.message(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assert_response_matches() {
+        assert_response(Ok(Response::new(1)), Ok(1));
+        assert_response::<i32>(Err(Status::not_found("")), Err(Code::NotFound));
+    }
+
+    #[test]
+    fn test_assert_status_matches() {
+        assert_status::<()>(
+            Err(Status::invalid_argument("missing user email")),
+            Code::InvalidArgument,
+            "user email",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "does not contain")]
+    fn test_assert_status_message_mismatch() {
+        assert_status::<()>(
+            Err(Status::invalid_argument("missing user name")),
+            Code::InvalidArgument,
+            "user email",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Diff")]
+    fn test_assert_response_mismatch() {
+        assert_response(Ok(Response::new(vec![1, 2])), Ok(vec![1, 3]));
+    }
+
+    #[tokio::test]
+    async fn test_get_test_db_per_service() {
+        // given
+        let auth_migrations = std::fs::canonicalize("../../auth/migrations").unwrap();
+        let user_migrations = std::fs::canonicalize("../../user/migrations").unwrap();
+
+        // when
+        let auth = get_test_db("auth", auth_migrations).await.unwrap();
+        let user = get_test_db("user", &user_migrations).await.unwrap();
+        let user_again = get_test_db("user", &user_migrations).await.unwrap();
+
+        // then
+        let auth = auth.get().await.unwrap();
+        let user = user.get().await.unwrap();
+        let user_again = user_again.get().await.unwrap();
+        let dbname = |row: tokio_postgres::Row| row.get::<_, String>(0);
+        let query = "SELECT current_database()";
+        assert_eq!(dbname(auth.query_one(query, &[]).await.unwrap()), "auth_db");
+        assert_eq!(dbname(user.query_one(query, &[]).await.unwrap()), "user_db");
+        auth.query("SELECT 1 FROM sessions", &[]).await.unwrap();
+        user.query("SELECT 1 FROM users", &[]).await.unwrap();
+        assert!(user.query("SELECT 1 FROM sessions", &[]).await.is_err());
+        assert_eq!(
+            dbname(user_again.query_one(query, &[]).await.unwrap()),
+            "user_db"
+        );
+    }
+}
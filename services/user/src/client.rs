@@ -3,26 +3,66 @@ use crate::GRPC_PORT;
 use crate::SERVICE_NAME;
 use crate::proto::CreateUserReq;
 use crate::proto::CreateUserResp;
+use crate::proto::DeleteUserReq;
+use crate::proto::DeleteUserResp;
+use crate::proto::GetUserByEmailReq;
+use crate::proto::GetUserByEmailResp;
 use crate::proto::GetUserReq;
 use crate::proto::GetUserResp;
+use crate::proto::GetUsersReq;
+use crate::proto::GetUsersResp;
+use crate::proto::ListUsersReq;
+use crate::proto::ListUsersResp;
+use crate::proto::PingReq;
+use crate::proto::PingResp;
+use crate::proto::UpdateUserReq;
+use crate::proto::UpdateUserResp;
 use crate::proto::user_service_client::UserServiceClient;
-use setup::{middleware::tracing::TracingServiceClient, patched_host};
-use std::{error::Error, str::FromStr as _};
-use tonic::transport::{Channel, Endpoint};
+use setup::endpoint::{
+    ConnectRetry, DEFAULT_REQUEST_TIMEOUT, connect_with_retry, deadline_exceeded, service_endpoint,
+};
+use setup::middleware::tracing::TracingServiceClient;
+use std::error::Error;
+use std::time::Duration;
+use tonic::transport::Channel;
 use tonic::{Request, Response, Status, async_trait};
 
 #[derive(Clone)]
 pub struct UserClient(UserServiceClient<TracingServiceClient<Channel>>);
 
 impl UserClient {
+    /// Creates a client with the [`DEFAULT_REQUEST_TIMEOUT`].
     pub async fn new() -> Result<Self, Box<dyn Error>> {
-        let host = patched_host(String::from(SERVICE_NAME));
-        let endpoint = Endpoint::from_str(&format!("http://{host}:{GRPC_PORT}"))?;
-        let channel = endpoint.connect().await?;
-        let client = TracingServiceClient::new(channel);
-        let client = UserServiceClient::new(client);
+        Self::new_with_timeout(DEFAULT_REQUEST_TIMEOUT).await
+    }
+
+    /// Creates a client whose requests fail with `DeadlineExceeded`
+    /// if they take longer than `timeout`.
+    ///
+    /// Connecting is retried as configured by [`ConnectRetry::from_env`].
+    pub async fn new_with_timeout(timeout: Duration) -> Result<Self, Box<dyn Error>> {
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(timeout);
+        let channel = connect_with_retry(&endpoint, ConnectRetry::from_env()?).await?;
+
+        Ok(Self::from_channel(channel))
+    }
 
-        Ok(Self(client))
+    /// Creates a client that connects on its first request.
+    ///
+    /// Unlike [`Self::new`], this does not wait for the service to be up,
+    /// so callers can start in any order and recover once it becomes
+    /// reachable. The tradeoff is that a misconfigured or unreachable
+    /// service is only noticed when the first request fails.
+    pub fn lazy() -> Result<Self, Box<dyn Error>> {
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(DEFAULT_REQUEST_TIMEOUT);
+        let channel = endpoint.connect_lazy();
+
+        Ok(Self::from_channel(channel))
+    }
+
+    fn from_channel(channel: Channel) -> Self {
+        let client = TracingServiceClient::new(channel);
+        Self(UserServiceClient::new(client))
     }
 }
 
@@ -31,54 +71,268 @@ impl UserClient {
 pub trait IUserClient: Send + Sync + 'static {
     async fn create_user(&self, req: Request<CreateUserReq>) -> Result<Response<CreateUserResp>, Status>;
     async fn get_user(&self, req: Request<GetUserReq>) -> Result<Response<GetUserResp>, Status>;
+    async fn get_user_by_email(&self, req: Request<GetUserByEmailReq>) -> Result<Response<GetUserByEmailResp>, Status>;
+    async fn get_users(&self, req: Request<GetUsersReq>) -> Result<Response<GetUsersResp>, Status>;
+    async fn update_user(&self, req: Request<UpdateUserReq>) -> Result<Response<UpdateUserResp>, Status>;
+    async fn delete_user(&self, req: Request<DeleteUserReq>) -> Result<Response<DeleteUserResp>, Status>;
+    async fn list_users(&self, req: Request<ListUsersReq>) -> Result<Response<ListUsersResp>, Status>;
+    async fn ping(&self, req: Request<PingReq>) -> Result<Response<PingResp>, Status>;
 }
 
 #[rustfmt::skip]
 #[async_trait]
 impl IUserClient for UserClient {
     async fn create_user(&self, req: Request<CreateUserReq>) -> Result<Response<CreateUserResp>, Status> {
-        self.0.clone().create_user(req).await
+        self.0.clone().create_user(req).await.map_err(deadline_exceeded)
     }
     async fn get_user(&self, req: Request<GetUserReq>) -> Result<Response<GetUserResp>, Status> {
-        self.0.clone().get_user(req).await
+        self.0.clone().get_user(req).await.map_err(deadline_exceeded)
+    }
+    async fn get_user_by_email(&self, req: Request<GetUserByEmailReq>) -> Result<Response<GetUserByEmailResp>, Status> {
+        self.0.clone().get_user_by_email(req).await.map_err(deadline_exceeded)
+    }
+    async fn get_users(&self, req: Request<GetUsersReq>) -> Result<Response<GetUsersResp>, Status> {
+        self.0.clone().get_users(req).await.map_err(deadline_exceeded)
+    }
+    async fn update_user(&self, req: Request<UpdateUserReq>) -> Result<Response<UpdateUserResp>, Status> {
+        self.0.clone().update_user(req).await.map_err(deadline_exceeded)
+    }
+    async fn delete_user(&self, req: Request<DeleteUserReq>) -> Result<Response<DeleteUserResp>, Status> {
+        self.0.clone().delete_user(req).await.map_err(deadline_exceeded)
+    }
+    async fn list_users(&self, req: Request<ListUsersReq>) -> Result<Response<ListUsersResp>, Status> {
+        self.0.clone().list_users(req).await.map_err(deadline_exceeded)
+    }
+    async fn ping(&self, req: Request<PingReq>) -> Result<Response<PingResp>, Status> {
+        self.0.clone().ping(req).await.map_err(deadline_exceeded)
     }
 }
 
 #[cfg(feature = "testutils")]
 pub mod testutils {
     use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tokio::sync::Mutex;
     use tonic::{Request, Response, Status};
 
     #[rustfmt::skip]
     pub struct MockUserClient {
         pub create_user_req: Mutex<Option<CreateUserReq>>,
-        pub create_user_resp: Mutex<Option<Result<CreateUserResp, Status>>>,
+        pub create_user_resp: Mutex<VecDeque<Result<CreateUserResp, Status>>>,
+        create_user_call_count: AtomicUsize,
         pub get_user_req: Mutex<Option<GetUserReq>>,
-        pub get_user_resp: Mutex<Option<Result<GetUserResp, Status>>>,
+        pub get_user_resp: Mutex<VecDeque<Result<GetUserResp, Status>>>,
+        get_user_call_count: AtomicUsize,
+        pub get_user_by_email_req: Mutex<Option<GetUserByEmailReq>>,
+        pub get_user_by_email_resp: Mutex<VecDeque<Result<GetUserByEmailResp, Status>>>,
+        get_user_by_email_call_count: AtomicUsize,
+        pub get_users_req: Mutex<Option<GetUsersReq>>,
+        pub get_users_resp: Mutex<VecDeque<Result<GetUsersResp, Status>>>,
+        get_users_call_count: AtomicUsize,
+        pub update_user_req: Mutex<Option<UpdateUserReq>>,
+        pub update_user_resp: Mutex<VecDeque<Result<UpdateUserResp, Status>>>,
+        update_user_call_count: AtomicUsize,
+        pub delete_user_req: Mutex<Option<DeleteUserReq>>,
+        pub delete_user_resp: Mutex<VecDeque<Result<DeleteUserResp, Status>>>,
+        delete_user_call_count: AtomicUsize,
+        pub list_users_req: Mutex<Option<ListUsersReq>>,
+        pub list_users_resp: Mutex<VecDeque<Result<ListUsersResp, Status>>>,
+        list_users_call_count: AtomicUsize,
+        pub ping_req: Mutex<Option<PingReq>>,
+        pub ping_resp: Mutex<VecDeque<Result<PingResp, Status>>>,
+        ping_call_count: AtomicUsize,
     }
 
     impl Default for MockUserClient {
         fn default() -> Self {
             Self {
                 create_user_req: Mutex::new(None),
-                create_user_resp: Mutex::new(None),
+                create_user_resp: Mutex::new(VecDeque::new()),
+                create_user_call_count: AtomicUsize::new(0),
                 get_user_req: Mutex::new(None),
-                get_user_resp: Mutex::new(None),
+                get_user_resp: Mutex::new(VecDeque::new()),
+                get_user_call_count: AtomicUsize::new(0),
+                get_user_by_email_req: Mutex::new(None),
+                get_user_by_email_resp: Mutex::new(VecDeque::new()),
+                get_user_by_email_call_count: AtomicUsize::new(0),
+                get_users_req: Mutex::new(None),
+                get_users_resp: Mutex::new(VecDeque::new()),
+                get_users_call_count: AtomicUsize::new(0),
+                update_user_req: Mutex::new(None),
+                update_user_resp: Mutex::new(VecDeque::new()),
+                update_user_call_count: AtomicUsize::new(0),
+                delete_user_req: Mutex::new(None),
+                delete_user_resp: Mutex::new(VecDeque::new()),
+                delete_user_call_count: AtomicUsize::new(0),
+                list_users_req: Mutex::new(None),
+                list_users_resp: Mutex::new(VecDeque::new()),
+                list_users_call_count: AtomicUsize::new(0),
+                ping_req: Mutex::new(None),
+                ping_resp: Mutex::new(VecDeque::new()),
+                ping_call_count: AtomicUsize::new(0),
             }
         }
     }
 
+    #[rustfmt::skip]
+    impl MockUserClient {
+        /// Queues the result of the next unanswered `create_user` call.
+        pub fn push_create_user_resp(&mut self, resp: Result<CreateUserResp, Status>) -> &mut Self {
+            self.create_user_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `create_user` was called.
+        pub fn create_user_calls(&self) -> usize {
+            self.create_user_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `get_user` call.
+        pub fn push_get_user_resp(&mut self, resp: Result<GetUserResp, Status>) -> &mut Self {
+            self.get_user_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `get_user` was called.
+        pub fn get_user_calls(&self) -> usize {
+            self.get_user_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `get_user_by_email` call.
+        pub fn push_get_user_by_email_resp(&mut self, resp: Result<GetUserByEmailResp, Status>) -> &mut Self {
+            self.get_user_by_email_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `get_user_by_email` was called.
+        pub fn get_user_by_email_calls(&self) -> usize {
+            self.get_user_by_email_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `get_users` call.
+        pub fn push_get_users_resp(&mut self, resp: Result<GetUsersResp, Status>) -> &mut Self {
+            self.get_users_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `get_users` was called.
+        pub fn get_users_calls(&self) -> usize {
+            self.get_users_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `update_user` call.
+        pub fn push_update_user_resp(&mut self, resp: Result<UpdateUserResp, Status>) -> &mut Self {
+            self.update_user_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `update_user` was called.
+        pub fn update_user_calls(&self) -> usize {
+            self.update_user_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `delete_user` call.
+        pub fn push_delete_user_resp(&mut self, resp: Result<DeleteUserResp, Status>) -> &mut Self {
+            self.delete_user_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `delete_user` was called.
+        pub fn delete_user_calls(&self) -> usize {
+            self.delete_user_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `list_users` call.
+        pub fn push_list_users_resp(&mut self, resp: Result<ListUsersResp, Status>) -> &mut Self {
+            self.list_users_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `list_users` was called.
+        pub fn list_users_calls(&self) -> usize {
+            self.list_users_call_count.load(Ordering::SeqCst)
+        }
+        /// Queues the result of the next unanswered `ping` call.
+        pub fn push_ping_resp(&mut self, resp: Result<PingResp, Status>) -> &mut Self {
+            self.ping_resp.get_mut().push_back(resp);
+            self
+        }
+        /// Returns how often `ping` was called.
+        pub fn ping_calls(&self) -> usize {
+            self.ping_call_count.load(Ordering::SeqCst)
+        }
+    }
+
     #[rustfmt::skip]
     #[async_trait]
     impl IUserClient for MockUserClient {
         async fn create_user(&self, req: Request<CreateUserReq>) -> Result<Response<CreateUserResp>, Status> {
+            self.create_user_call_count.fetch_add(1, Ordering::SeqCst);
             *self.create_user_req.lock().await = Some(req.into_inner());
-            self.create_user_resp.lock().await.take().unwrap().map(Response::new)
+            self.create_user_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("create_user called more times than configured"))
+                .map(Response::new)
         }
         async fn get_user(&self, req: Request<GetUserReq>) -> Result<Response<GetUserResp>, Status> {
+            self.get_user_call_count.fetch_add(1, Ordering::SeqCst);
             *self.get_user_req.lock().await = Some(req.into_inner());
-            self.get_user_resp.lock().await.take().unwrap().map(Response::new)
+            self.get_user_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("get_user called more times than configured"))
+                .map(Response::new)
+        }
+        async fn get_user_by_email(&self, req: Request<GetUserByEmailReq>) -> Result<Response<GetUserByEmailResp>, Status> {
+            self.get_user_by_email_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.get_user_by_email_req.lock().await = Some(req.into_inner());
+            self.get_user_by_email_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("get_user_by_email called more times than configured"))
+                .map(Response::new)
+        }
+        async fn get_users(&self, req: Request<GetUsersReq>) -> Result<Response<GetUsersResp>, Status> {
+            self.get_users_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.get_users_req.lock().await = Some(req.into_inner());
+            self.get_users_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("get_users called more times than configured"))
+                .map(Response::new)
+        }
+        async fn update_user(&self, req: Request<UpdateUserReq>) -> Result<Response<UpdateUserResp>, Status> {
+            self.update_user_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.update_user_req.lock().await = Some(req.into_inner());
+            self.update_user_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("update_user called more times than configured"))
+                .map(Response::new)
+        }
+        async fn delete_user(&self, req: Request<DeleteUserReq>) -> Result<Response<DeleteUserResp>, Status> {
+            self.delete_user_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.delete_user_req.lock().await = Some(req.into_inner());
+            self.delete_user_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("delete_user called more times than configured"))
+                .map(Response::new)
+        }
+        async fn list_users(&self, req: Request<ListUsersReq>) -> Result<Response<ListUsersResp>, Status> {
+            self.list_users_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.list_users_req.lock().await = Some(req.into_inner());
+            self.list_users_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("list_users called more times than configured"))
+                .map(Response::new)
+        }
+        async fn ping(&self, req: Request<PingReq>) -> Result<Response<PingResp>, Status> {
+            self.ping_call_count.fetch_add(1, Ordering::SeqCst);
+            *self.ping_req.lock().await = Some(req.into_inner());
+            self.ping_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("ping called more times than configured"))
+                .map(Response::new)
         }
     }
 }
@@ -4,18 +4,24 @@ use crate::{
     handler::Handler,
     proto::{CreateUserReq, CreateUserResp, User},
 };
-use common::UuidGenerator;
+use common::{Now, UuidGenerator};
+use setup::validate_email;
 use tonic::{Request, Response, Status};
 
-impl<D, U> Handler<D, U>
+impl<D, U, N> Handler<D, U, N>
 where
     D: DBClient,
     U: UuidGenerator,
+    N: Now,
 {
     /// Creates a new user.
     ///
     /// # Errors
+    /// - invalid argument if the name or email is missing, or the email is malformed
     /// - internal error if the user cannot be inserted into the db
+    ///
+    /// Creating a user whose email is already taken returns the existing
+    /// user, so that a retried request does not fail.
     pub async fn create_user(
         &self,
         req: Request<CreateUserReq>,
@@ -34,17 +40,32 @@ where
         if email.is_empty() {
             return Err(Error::MissingUserEmail.into());
         }
+        validate_email(&email)?;
 
-        self.db
-            .insert_user(id, &name, &email)
+        let now = self.now.now();
+        let inserted = self
+            .db
+            .insert_user(id, &name, &email, now)
             .await
             .map_err(Error::InsertUser)?;
 
+        if !inserted {
+            let user = self
+                .db
+                .get_user_by_email(&email)
+                .await
+                .map_err(Error::GetUser)?;
+            tracing::Span::current().record("user_id", &user.id);
+            return Ok(Response::new(CreateUserResp { user: Some(user) }));
+        }
+
         let response = CreateUserResp {
             user: Some(User {
                 id: id.to_string(),
                 name,
                 email,
+                created_at: now.timestamp(),
+                updated_at: now.timestamp(),
             }),
         };
 
@@ -59,27 +80,32 @@ mod tests {
         error::DBError,
         fixture::{fixture_create_user_req, fixture_user},
         handler::Handler,
-        proto::{CreateUserReq, CreateUserResp},
+        proto::{CreateUserReq, CreateUserResp, User},
     };
-    use common::mock::MockUuidGenerator;
+    use common::mock::{MockNow, MockUuidGenerator};
     use rstest::rstest;
-    use tokio::sync::Mutex;
+    use testutils::assert_status;
     use tonic::{Code, Request};
 
     #[rstest]
     #[case::happy(
         fixture_create_user_req(|_| {}),
-        Ok(()),
+        Ok(true),
         Ok(CreateUserResp { user: Some(fixture_user(|_| {})) })
     )]
     #[case::missing_name(
         fixture_create_user_req(|r| r.name.clear()),
-        Ok(()),
+        Ok(true),
         Err(Code::InvalidArgument)
     )]
     #[case::missing_email(
         fixture_create_user_req(|r| r.email.clear()),
-        Ok(()),
+        Ok(true),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::invalid_email(
+        fixture_create_user_req(|r| r.email = "user@example.com.".to_string()),
+        Ok(true),
         Err(Code::InvalidArgument)
     )]
     #[case::internal_error(
@@ -90,22 +116,70 @@ mod tests {
     #[tokio::test]
     async fn test_create_user(
         #[case] req: CreateUserReq,
-        #[case] insert_res: Result<(), DBError>,
+        #[case] insert_res: Result<bool, DBError>,
         #[case] want: Result<CreateUserResp, Code>,
     ) {
         use testutils::assert_response;
 
-        let db = MockDBClient {
-            insert_user: Mutex::new(Some(insert_res)),
-            ..Default::default()
-        };
+        let mut db = MockDBClient::default();
+        db.expect_insert_user([insert_res]);
 
         let service = Handler {
             db,
             uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
         };
 
         let got = service.create_user(Request::new(req)).await;
         assert_response(got, want);
     }
+
+    #[rstest]
+    #[case::returns_existing_user(
+        Ok(fixture_user(|u| u.name = "existing".to_string())),
+        Ok(CreateUserResp { user: Some(fixture_user(|u| u.name = "existing".to_string())) })
+    )]
+    #[case::lookup_error(Err(DBError::Unknown), Err(Code::Internal))]
+    #[tokio::test]
+    async fn test_create_user_existing_email(
+        #[case] lookup_res: Result<User, DBError>,
+        #[case] want: Result<CreateUserResp, Code>,
+    ) {
+        // given
+        use testutils::assert_response;
+        let mut db = MockDBClient::default();
+        db.expect_insert_user([Ok(false)])
+            .expect_get_user_by_email([lookup_res]);
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = service
+            .create_user(Request::new(fixture_create_user_req(|_| {})))
+            .await;
+
+        // then
+        assert_response(got, want);
+        assert_eq!(service.db.get_user_by_email_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_user_missing_email_message() {
+        // given
+        let service = Handler {
+            db: MockDBClient::default(),
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+        let req = fixture_create_user_req(|r| r.email.clear());
+
+        // when
+        let got = service.create_user(Request::new(req)).await;
+
+        // then
+        assert_status(got, Code::InvalidArgument, "missing user email");
+    }
 }
@@ -1,4 +1,5 @@
 use crate::error::DBError;
+use chrono::{DateTime, Utc};
 use deadpool_postgres::Pool;
 use std::fmt::Debug;
 use tokio_postgres::Row;
@@ -10,9 +11,31 @@ use crate::proto::User;
 #[cfg_attr(test, mock::db_client)]
 #[async_trait]
 pub trait DBClient: Send + Sync + 'static {
-    async fn insert_user(&self, id: Uuid, name: &str, email: &str) -> Result<(), DBError>;
+    async fn insert_user(
+        &self,
+        id: Uuid,
+        name: &str,
+        email: &str,
+        now: DateTime<Utc>,
+    ) -> Result<bool, DBError>;
 
     async fn get_user(&self, id: Uuid) -> Result<User, DBError>;
+
+    async fn get_user_by_email(&self, email: &str) -> Result<User, DBError>;
+
+    async fn get_users(&self, ids: &[Uuid]) -> Result<Vec<User>, DBError>;
+
+    async fn update_user(
+        &self,
+        id: Uuid,
+        name: &str,
+        email: &str,
+        now: DateTime<Utc>,
+    ) -> Result<User, DBError>;
+
+    async fn delete_user(&self, id: Uuid) -> Result<(), DBError>;
+
+    async fn list_users(&self, limit: i64, offset: i64) -> Result<Vec<User>, DBError>;
 }
 
 #[derive(Clone, Debug)]
@@ -29,20 +52,33 @@ impl PostgresDBClient {
 
 #[async_trait]
 impl DBClient for PostgresDBClient {
+    /// Inserts a user that was created and last updated at `now`.
+    ///
+    /// Returns `false` without inserting if a user with the same email,
+    /// ignoring its case, already exists.
+    ///
     /// # Errors
     /// - if the database connection cannot be established
     /// - if the database query fails
-    async fn insert_user(&self, id: Uuid, name: &str, email: &str) -> Result<(), DBError> {
+    async fn insert_user(
+        &self,
+        id: Uuid,
+        name: &str,
+        email: &str,
+        now: DateTime<Utc>,
+    ) -> Result<bool, DBError> {
         let client = self.pool.get().await?;
 
-        client
-            .execute(
-                "INSERT INTO users (id, name, email) VALUES ($1, $2, $3)",
-                &[&id, &name, &email],
+        let row = client
+            .query_opt(
+                "INSERT INTO users (id, name, email, created_at, updated_at) VALUES ($1, $2, $3, $4, $4) \
+                 ON CONFLICT ((LOWER(email))) WHERE deleted_at IS NULL DO NOTHING RETURNING id",
+                &[&id, &name, &email, &now],
             )
-            .await?;
+            .await
+            .map_err(DBError::query("insert_user"))?;
 
-        Ok(())
+        Ok(row.is_some())
     }
 
     /// # Errors
@@ -53,29 +89,168 @@ impl DBClient for PostgresDBClient {
         let client = self.pool.get().await?;
 
         let stmt = client
-            .prepare("SELECT id, name, email FROM users WHERE id = $1")
-            .await?;
-        let row = client.query_opt(&stmt, &[&id]).await?;
+            .prepare(
+                "SELECT id, name, email, created_at, updated_at FROM users WHERE id = $1 AND deleted_at IS NULL",
+            )
+            .await
+            .map_err(DBError::query("get_user"))?;
+        let row = client
+            .query_opt(&stmt, &[&id])
+            .await
+            .map_err(DBError::query("get_user"))?;
         let Some(row) = row else {
             return Err(DBError::NotFound);
         };
 
-        Ok(User::try_from(row)?)
+        User::try_from(row).map_err(DBError::query("get_user"))
+    }
+
+    /// Returns the user with the email, ignoring its ASCII case.
+    ///
+    /// # Errors
+    /// - if the database connection cannot be established
+    /// - if the database query fails
+    /// - If the user is not found
+    async fn get_user_by_email(&self, email: &str) -> Result<User, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare(
+                "SELECT id, name, email, created_at, updated_at FROM users WHERE LOWER(email) = LOWER($1) AND deleted_at IS NULL",
+            )
+            .await
+            .map_err(DBError::query("get_user_by_email"))?;
+        let row = client
+            .query_opt(&stmt, &[&email])
+            .await
+            .map_err(DBError::query("get_user_by_email"))?;
+        let Some(row) = row else {
+            return Err(DBError::NotFound);
+        };
+
+        User::try_from(row).map_err(DBError::query("get_user_by_email"))
+    }
+
+    /// Returns the users with the ids, ordered by id. Ids without a user
+    /// are omitted.
+    ///
+    /// # Errors
+    /// - if the database connection cannot be established
+    /// - if the database query fails
+    async fn get_users(&self, ids: &[Uuid]) -> Result<Vec<User>, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare(
+                "SELECT id, name, email, created_at, updated_at FROM users WHERE id = ANY($1) AND deleted_at IS NULL ORDER BY id",
+            )
+            .await
+            .map_err(DBError::query("get_users"))?;
+        let rows = client
+            .query(&stmt, &[&ids])
+            .await
+            .map_err(DBError::query("get_users"))?;
+
+        rows.into_iter()
+            .map(User::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(DBError::query("get_users"))
+    }
+
+    /// Updates a user and sets `updated_at` to `now`.
+    ///
+    /// # Errors
+    /// - if the database connection cannot be established
+    /// - if the database query fails
+    /// - If the user is not found
+    async fn update_user(
+        &self,
+        id: Uuid,
+        name: &str,
+        email: &str,
+        now: DateTime<Utc>,
+    ) -> Result<User, DBError> {
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_opt(
+                "UPDATE users SET name = $2, email = $3, updated_at = $4 WHERE id = $1 AND deleted_at IS NULL RETURNING id, name, email, created_at, updated_at",
+                &[&id, &name, &email, &now],
+            )
+            .await
+            .map_err(DBError::query("update_user"))?;
+        let Some(row) = row else {
+            return Err(DBError::NotFound);
+        };
+
+        User::try_from(row).map_err(DBError::query("update_user"))
+    }
+
+    /// Soft-deletes a user by setting `deleted_at`.
+    ///
+    /// # Errors
+    /// - if the database connection cannot be established
+    /// - if the database query fails
+    /// - If the user is not found or already deleted
+    async fn delete_user(&self, id: Uuid) -> Result<(), DBError> {
+        let client = self.pool.get().await?;
+
+        let deleted = client
+            .execute(
+                "UPDATE users SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+                &[&id],
+            )
+            .await
+            .map_err(DBError::query("delete_user"))?;
+        if deleted == 0 {
+            return Err(DBError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a page of users that are not deleted, ordered by id.
+    ///
+    /// # Errors
+    /// - if the database connection cannot be established
+    /// - if the database query fails
+    async fn list_users(&self, limit: i64, offset: i64) -> Result<Vec<User>, DBError> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare(
+                "SELECT id, name, email, created_at, updated_at FROM users WHERE deleted_at IS NULL ORDER BY id LIMIT $1 OFFSET $2",
+            )
+            .await
+            .map_err(DBError::query("list_users"))?;
+        let rows = client
+            .query(&stmt, &[&limit, &offset])
+            .await
+            .map_err(DBError::query("list_users"))?;
+
+        rows.into_iter()
+            .map(User::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(DBError::query("list_users"))
     }
 }
 
 impl TryFrom<Row> for User {
-    type Error = DBError;
+    type Error = tokio_postgres::Error;
 
-    fn try_from(value: Row) -> Result<Self, DBError> {
+    fn try_from(value: Row) -> Result<Self, Self::Error> {
         let id: Uuid = value.try_get("id")?;
         let name: String = value.try_get("name")?;
         let email: String = value.try_get("email")?;
+        let created_at: DateTime<Utc> = value.try_get("created_at")?;
+        let updated_at: DateTime<Utc> = value.try_get("updated_at")?;
 
         Ok(User {
             id: id.to_string(),
             name,
             email,
+            created_at: created_at.timestamp(),
+            updated_at: updated_at.timestamp(),
         })
     }
 }
@@ -85,8 +260,9 @@ pub mod test {
     pub(crate) use super::MockDBClient;
     use super::*;
     use crate::error::DBError;
-    use crate::fixture::{DBUser, fixture_db_user, fixture_user, fixture_uuid};
+    use crate::fixture::{DBUser, fixture_db_user, fixture_now, fixture_user, fixture_uuid};
     use crate::proto::User;
+    use chrono::Duration;
     use rstest::rstest;
     use testutils::get_test_db;
     use user::SERVICE_NAME;
@@ -105,7 +281,7 @@ pub mod test {
 
         for user in given_user {
             db_client
-                .insert_user(user.id.clone(), &user.name, &user.email)
+                .insert_user(user.id, user.name, user.email, fixture_now())
                 .await
                 .expect("failed to insert user");
         }
@@ -143,4 +319,200 @@ pub mod test {
         })
         .await;
     }
+
+    #[rstest]
+    #[case::exact(
+        "user@example.com",
+        Ok(fixture_user(|u| u.email = "User@Example.com".to_string()))
+    )]
+    #[case::ignores_case(
+        "USER@example.COM",
+        Ok(fixture_user(|u| u.email = "User@Example.com".to_string()))
+    )]
+    #[case::not_found("other@example.com", Err(DBError::NotFound))]
+    #[tokio::test]
+    async fn test_get_user_by_email(#[case] email: &str, #[case] want: Result<User, DBError>) {
+        let given_users = vec![fixture_db_user(|u| u.email = "User@Example.com")];
+
+        run_db_test(given_users, |db_client| async move {
+            let got = db_client.get_user_by_email(email).await;
+
+            match (got, want) {
+                (Ok(got_user), Ok(want_user)) => assert_eq!(got_user, want_user),
+                (Err(got_err), Err(want_err)) => {
+                    assert_eq!(format!("{got_err}"), format!("{want_err}"))
+                }
+                (got, want) => panic!("expected {want:?}, got {got:?}"),
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_users() {
+        let given_users = [
+            (1, "a@example.com"),
+            (2, "b@example.com"),
+            (3, "c@example.com"),
+        ]
+        .into_iter()
+        .map(|(i, email)| {
+            fixture_db_user(|u| {
+                u.id = Uuid::from_u128(i);
+                u.email = email;
+            })
+        })
+        .collect();
+
+        run_db_test(given_users, |db_client| async move {
+            let ids = [3, 99, 1].map(Uuid::from_u128);
+
+            let got = db_client
+                .get_users(&ids)
+                .await
+                .expect("failed to get users");
+
+            let got_ids: Vec<_> = got.into_iter().map(|u| u.id).collect();
+            let want_ids: Vec<_> = [1, 3]
+                .into_iter()
+                .map(|i| Uuid::from_u128(i).to_string())
+                .collect();
+            assert_eq!(got_ids, want_ids);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_insert_user_with_duplicate_email() {
+        let given_users = vec![fixture_db_user(|u| u.email = "user@example.com")];
+
+        run_db_test(given_users, |db_client| async move {
+            let got = db_client
+                .insert_user(
+                    Uuid::from_u128(1),
+                    "other",
+                    "USER@example.com",
+                    fixture_now(),
+                )
+                .await;
+
+            assert!(!got.unwrap(), "expected the insert to be skipped");
+            let existing = db_client.get_user_by_email("user@example.com").await;
+            assert_eq!(existing.unwrap().id, fixture_uuid().to_string());
+            let duplicate = db_client.get_user(Uuid::from_u128(1)).await;
+            assert!(matches!(duplicate, Err(DBError::NotFound)), "{duplicate:?}");
+        })
+        .await;
+    }
+
+    #[rstest]
+    #[case::happy_path(
+        fixture_uuid(),
+        vec![fixture_db_user(|_| {})],
+        Ok(fixture_user(|u| {
+            u.name = "new name".to_string();
+            u.email = "new email".to_string();
+            u.updated_at = (fixture_now() + Duration::hours(1)).timestamp();
+        }))
+    )]
+    #[case::not_found(
+        Uuid::parse_str("99999999-9999-9999-9999-999999999999").unwrap(),
+        vec![fixture_db_user(|_| {})],
+        Err(DBError::NotFound)
+    )]
+    #[tokio::test]
+    async fn test_update_user(
+        #[case] user_id: Uuid,
+        #[case] given_users: Vec<DBUser>,
+        #[case] want: Result<User, DBError>,
+    ) {
+        run_db_test(given_users, |db_client| async move {
+            let got = db_client
+                .update_user(
+                    user_id,
+                    "new name",
+                    "new email",
+                    fixture_now() + Duration::hours(1),
+                )
+                .await;
+
+            match (got, want) {
+                (Ok(got_user), Ok(want_user)) => assert_eq!(got_user, want_user),
+                (Err(got_err), Err(want_err)) => {
+                    assert_eq!(format!("{got_err}"), format!("{want_err}"))
+                }
+                (got, want) => panic!("expected {want:?}, got {got:?}"),
+            }
+        })
+        .await;
+    }
+
+    #[rstest]
+    #[case::happy_path(fixture_uuid(), vec![fixture_db_user(|_| {})], Ok(()))]
+    #[case::not_found(
+        Uuid::parse_str("99999999-9999-9999-9999-999999999999").unwrap(),
+        vec![fixture_db_user(|_| {})],
+        Err(DBError::NotFound)
+    )]
+    #[tokio::test]
+    async fn test_delete_user(
+        #[case] user_id: Uuid,
+        #[case] given_users: Vec<DBUser>,
+        #[case] want: Result<(), DBError>,
+    ) {
+        run_db_test(given_users, |db_client| async move {
+            let got = db_client.delete_user(user_id).await;
+
+            match (got, want) {
+                (Ok(()), Ok(())) => {
+                    let got_user = db_client.get_user(user_id).await;
+                    assert!(
+                        matches!(got_user, Err(DBError::NotFound)),
+                        "expected deleted user to be not found, got {got_user:?}"
+                    );
+                }
+                (Err(got_err), Err(want_err)) => {
+                    assert_eq!(format!("{got_err}"), format!("{want_err}"))
+                }
+                (got, want) => panic!("expected {want:?}, got {got:?}"),
+            }
+        })
+        .await;
+    }
+
+    #[rstest]
+    #[case::first_page(2, 0, vec![1, 2])]
+    #[case::second_page(2, 2, vec![3])]
+    #[case::past_the_end(2, 4, vec![])]
+    #[tokio::test]
+    async fn test_list_users(#[case] limit: i64, #[case] offset: i64, #[case] want_ids: Vec<u128>) {
+        let given_users = [
+            (3, "c@example.com"),
+            (1, "a@example.com"),
+            (2, "b@example.com"),
+        ]
+        .into_iter()
+        .map(|(i, email)| {
+            fixture_db_user(|u| {
+                u.id = Uuid::from_u128(i);
+                u.email = email;
+            })
+        })
+        .collect();
+
+        run_db_test(given_users, |db_client| async move {
+            let got = db_client
+                .list_users(limit, offset)
+                .await
+                .expect("failed to list users");
+
+            let got_ids: Vec<_> = got.into_iter().map(|u| u.id).collect();
+            let want_ids: Vec<_> = want_ids
+                .into_iter()
+                .map(|i| Uuid::from_u128(i).to_string())
+                .collect();
+            assert_eq!(got_ids, want_ids);
+        })
+        .await;
+    }
 }
@@ -0,0 +1,101 @@
+use crate::{
+    db::DBClient,
+    error::{DBError, Error},
+    handler::Handler,
+    proto::{DeleteUserReq, DeleteUserResp},
+};
+use common::{Now, UuidGenerator};
+use setup::validate_user_id;
+use tonic::{Request, Response, Status};
+
+impl<D, U, N> Handler<D, U, N>
+where
+    D: DBClient,
+    U: UuidGenerator,
+    N: Now,
+{
+    /// Soft-deletes a user. Deleted users are no longer returned by
+    /// `get_user`.
+    ///
+    /// # Errors
+    /// - invalid argument if the id is missing or malformed
+    /// - not found if the user does not exist or is already deleted
+    /// - internal error if the user cannot be deleted in the db
+    pub async fn delete_user(
+        &self,
+        req: Request<DeleteUserReq>,
+    ) -> Result<Response<DeleteUserResp>, Status> {
+        let req = req.into_inner();
+        let user_id = validate_user_id(&req.id)?;
+
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        self.db.delete_user(user_id).await.map_err(|e| match e {
+            DBError::NotFound => Error::UserNotFound(user_id.to_string()),
+            _ => Error::DeleteUser(e),
+        })?;
+
+        Ok(Response::new(DeleteUserResp {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::fixture_uuid,
+        handler::Handler,
+        proto::{DeleteUserReq, DeleteUserResp},
+    };
+    use common::mock::{MockNow, MockUuidGenerator};
+    use rstest::rstest;
+    use tonic::{Code, Request};
+
+    #[rstest]
+    #[case::happy(
+        DeleteUserReq { id: fixture_uuid().to_string() },
+        Ok(()),
+        Ok(DeleteUserResp {})
+    )]
+    #[case::missing_id(
+        DeleteUserReq { id: String::new() },
+        Ok(()),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::invalid_id(
+        DeleteUserReq { id: "invalid".to_string() },
+        Ok(()),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::not_found(
+        DeleteUserReq { id: fixture_uuid().to_string() },
+        Err(DBError::NotFound),
+        Err(Code::NotFound)
+    )]
+    #[case::internal_error(
+        DeleteUserReq { id: fixture_uuid().to_string() },
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_delete_user(
+        #[case] req: DeleteUserReq,
+        #[case] db_result: Result<(), DBError>,
+        #[case] want: Result<DeleteUserResp, Code>,
+    ) {
+        use testutils::assert_response;
+
+        let mut db = MockDBClient::default();
+        db.expect_delete_user([db_result]);
+
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        let got = service.delete_user(Request::new(req)).await;
+        assert_response(got, want);
+    }
+}
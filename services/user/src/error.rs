@@ -16,14 +16,32 @@ pub enum Error {
     #[error("missing user email")]
     MissingUserEmail,
 
+    #[error("invalid limit: {0}")]
+    InvalidLimit(i64),
+
+    #[error("invalid offset: {0}")]
+    InvalidOffset(i64),
+
     #[error("user not found: {0}")]
     UserNotFound(String),
 
     #[error("get user error: {0}")]
     GetUser(DBError),
 
+    #[error("get users error: {0}")]
+    GetUsers(DBError),
+
     #[error("insert user error: {0}")]
     InsertUser(DBError),
+
+    #[error("update user error: {0}")]
+    UpdateUser(DBError),
+
+    #[error("delete user error: {0}")]
+    DeleteUser(DBError),
+
+    #[error("list users error: {0}")]
+    ListUsers(DBError),
 }
 
 impl From<Error> for Status {
@@ -32,9 +50,16 @@ impl From<Error> for Status {
             Error::MissingUserName
             | Error::MissingUserEmail
             | Error::MissingUserId
-            | Error::InvalidUserId(_) => Code::InvalidArgument,
+            | Error::InvalidUserId(_)
+            | Error::InvalidLimit(_)
+            | Error::InvalidOffset(_) => Code::InvalidArgument,
             Error::UserNotFound(_) => Code::NotFound,
-            Error::GetUser(_) | Error::InsertUser(_) => Code::Internal,
+            Error::GetUser(_)
+            | Error::GetUsers(_)
+            | Error::InsertUser(_)
+            | Error::UpdateUser(_)
+            | Error::DeleteUser(_)
+            | Error::ListUsers(_) => Code::Internal,
         };
         Status::new(code, err.to_string())
     }
@@ -46,8 +71,12 @@ pub enum DBError {
     #[error("unknown error occured")]
     Unknown,
 
-    #[error("internal database error: {0}")]
-    Internal(#[from] tokio_postgres::Error),
+    #[error("{op} query failed: {source}")]
+    Query {
+        /// The database operation that failed, e.g. `get_user`.
+        op: &'static str,
+        source: tokio_postgres::Error,
+    },
 
     #[error("connection error: {0}")]
     Connection(#[from] deadpool_postgres::PoolError),
@@ -55,3 +84,11 @@ pub enum DBError {
     #[error("entity not found")]
     NotFound,
 }
+
+impl DBError {
+    /// Wraps a failed query of the given database operation, so that
+    /// the error tells which query failed.
+    pub(crate) fn query(op: &'static str) -> impl FnOnce(tokio_postgres::Error) -> Self {
+        move |source| Self::Query { op, source }
+    }
+}
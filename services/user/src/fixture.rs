@@ -1,13 +1,19 @@
 #![cfg(test)]
 
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::proto::{CreateUserReq, User};
+use crate::proto::{CreateUserReq, UpdateUserReq, User};
 
 pub fn fixture_uuid() -> Uuid {
     Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap()
 }
 
+/// The time of [`common::mock::MockNow`].
+pub fn fixture_now() -> DateTime<Utc> {
+    DateTime::from_timestamp(1577836800, 0).unwrap()
+}
+
 pub fn fixture_user<F>(mut func: F) -> User
 where
     F: FnMut(&mut User),
@@ -15,7 +21,9 @@ where
     let mut user = User {
         id: fixture_uuid().to_string(),
         name: "name".to_string(),
-        email: "email".to_string(),
+        email: "user@example.com".to_string(),
+        created_at: fixture_now().timestamp(),
+        updated_at: fixture_now().timestamp(),
     };
     func(&mut user);
     user
@@ -27,7 +35,20 @@ where
 {
     let mut user = CreateUserReq {
         name: "name".to_string(),
-        email: "email".to_string(),
+        email: "user@example.com".to_string(),
+    };
+    func(&mut user);
+    user
+}
+
+pub fn fixture_update_user_req<F>(mut func: F) -> UpdateUserReq
+where
+    F: FnMut(&mut UpdateUserReq),
+{
+    let mut user = UpdateUserReq {
+        id: fixture_uuid().to_string(),
+        name: "name".to_string(),
+        email: "user@example.com".to_string(),
     };
     func(&mut user);
     user
@@ -4,14 +4,15 @@ use crate::{
     handler::Handler,
     proto::{GetUserReq, GetUserResp},
 };
-use common::UuidGenerator;
+use common::{Now, UuidGenerator};
 use setup::validate_user_id;
 use tonic::{Request, Response, Status};
 
-impl<D, U> Handler<D, U>
+impl<D, U, N> Handler<D, U, N>
 where
     D: DBClient,
     U: UuidGenerator,
+    N: Now,
 {
     /// Gets a user by identifier.
     ///
@@ -36,7 +37,6 @@ where
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
-    use tokio::sync::Mutex;
     use tonic::{Code, Request};
 
     use crate::{
@@ -80,15 +80,14 @@ mod tests {
         #[case] want: Result<GetUserResp, Code>,
     ) {
         // given
-        use common::mock::MockUuidGenerator;
+        use common::mock::{MockNow, MockUuidGenerator};
         use testutils::assert_response;
-        let db = MockDBClient {
-            get_user: Mutex::new(Some(db_result)),
-            ..Default::default()
-        };
+        let mut db = MockDBClient::default();
+        db.expect_get_user([db_result]);
         let service = Handler {
             db,
             uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
         };
 
         // when
@@ -97,4 +96,25 @@ mod tests {
         // then
         assert_response(got, want);
     }
+
+    #[rstest]
+    #[case::missing_id("".to_string(), "missing user id")]
+    #[case::not_a_uuid("not-uuid".to_string(), "invalid user id: not-uuid")]
+    #[tokio::test]
+    async fn test_get_user_invalid_id_message(#[case] id: String, #[case] want_message: &str) {
+        // given
+        use common::mock::{MockNow, MockUuidGenerator};
+        use testutils::assert_status;
+        let service = Handler {
+            db: MockDBClient::default(),
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = service.get_user(Request::new(GetUserReq { id })).await;
+
+        // then
+        assert_status(got, Code::InvalidArgument, want_message);
+    }
 }
@@ -0,0 +1,103 @@
+use crate::{
+    db::DBClient,
+    error::{DBError, Error},
+    handler::Handler,
+    proto::{GetUserByEmailReq, GetUserByEmailResp},
+};
+use common::{Now, UuidGenerator};
+use tonic::{Request, Response, Status};
+
+impl<D, U, N> Handler<D, U, N>
+where
+    D: DBClient,
+    U: UuidGenerator,
+    N: Now,
+{
+    /// Gets a user by email, ignoring its case.
+    ///
+    /// # Errors
+    /// - missing email
+    /// - not found if no user has the email
+    /// - internal error if the user cannot be read from the db
+    pub async fn get_user_by_email(
+        &self,
+        req: Request<GetUserByEmailReq>,
+    ) -> Result<Response<GetUserByEmailResp>, Status> {
+        let req = req.into_inner();
+        if req.email.is_empty() {
+            return Err(Error::MissingUserEmail.into());
+        }
+
+        let user = self
+            .db
+            .get_user_by_email(&req.email)
+            .await
+            .map_err(|e| match e {
+                DBError::NotFound => Error::UserNotFound(req.email.clone()),
+                _ => Error::GetUser(e),
+            })?;
+
+        Ok(Response::new(GetUserByEmailResp { user: Some(user) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::mock::{MockNow, MockUuidGenerator};
+    use rstest::rstest;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::fixture_user,
+        handler::Handler,
+        proto::{GetUserByEmailReq, GetUserByEmailResp, User},
+    };
+
+    #[rstest]
+    #[case::happy_path(
+        "email".to_string(),
+        Ok(fixture_user(|_| {})),
+        Ok(GetUserByEmailResp { user: Some(fixture_user(|_| {})) })
+    )]
+    #[case::missing_email(
+        String::new(),
+        Ok(fixture_user(|_| {})),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::not_found(
+        "email".to_string(),
+        Err(DBError::NotFound),
+        Err(Code::NotFound)
+    )]
+    #[case::internal_error(
+        "email".to_string(),
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_get_user_by_email(
+        #[case] email: String,
+        #[case] db_result: Result<User, DBError>,
+        #[case] want: Result<GetUserByEmailResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_get_user_by_email([db_result]);
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = service
+            .get_user_by_email(Request::new(GetUserByEmailReq { email }))
+            .await;
+
+        // then
+        assert_response(got, want);
+    }
+}
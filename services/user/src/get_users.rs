@@ -0,0 +1,107 @@
+use crate::{
+    db::DBClient,
+    error::Error,
+    handler::Handler,
+    proto::{GetUsersReq, GetUsersResp},
+};
+use common::{Now, UuidGenerator};
+use setup::validate_user_id;
+use tonic::{Request, Response, Status};
+
+impl<D, U, N> Handler<D, U, N>
+where
+    D: DBClient,
+    U: UuidGenerator,
+    N: Now,
+{
+    /// Gets several users by identifier in a single query. Identifiers
+    /// without a user are omitted from the response.
+    ///
+    /// # Errors
+    /// - invalid argument if any id is missing or not a uuid
+    /// - internal error if the users cannot be read from the db
+    pub async fn get_users(
+        &self,
+        req: Request<GetUsersReq>,
+    ) -> Result<Response<GetUsersResp>, Status> {
+        let req = req.into_inner();
+        let ids = req
+            .ids
+            .iter()
+            .map(|id| validate_user_id(id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if ids.is_empty() {
+            return Ok(Response::new(GetUsersResp::default()));
+        }
+
+        let users = self.db.get_users(&ids).await.map_err(Error::GetUsers)?;
+
+        Ok(Response::new(GetUsersResp { users }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::mock::{MockNow, MockUuidGenerator};
+    use rstest::rstest;
+    use testutils::assert_response;
+    use tonic::{Code, Request};
+
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_user, fixture_uuid},
+        handler::Handler,
+        proto::{GetUsersReq, GetUsersResp, User},
+    };
+
+    #[rstest]
+    #[case::happy_path(
+        vec![fixture_uuid().to_string()],
+        vec![Ok(vec![fixture_user(|_| {})])],
+        Ok(GetUsersResp { users: vec![fixture_user(|_| {})] })
+    )]
+    #[case::unknown_ids_are_omitted(
+        vec![fixture_uuid().to_string()],
+        vec![Ok(vec![])],
+        Ok(GetUsersResp { users: vec![] })
+    )]
+    #[case::no_ids(vec![], vec![], Ok(GetUsersResp { users: vec![] }))]
+    #[case::missing_id(
+        vec![fixture_uuid().to_string(), String::new()],
+        vec![],
+        Err(Code::InvalidArgument)
+    )]
+    #[case::not_a_uuid(
+        vec!["not-uuid".to_string()],
+        vec![],
+        Err(Code::InvalidArgument)
+    )]
+    #[case::internal_error(
+        vec![fixture_uuid().to_string()],
+        vec![Err(DBError::Unknown)],
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_get_users(
+        #[case] ids: Vec<String>,
+        #[case] db_results: Vec<Result<Vec<User>, DBError>>,
+        #[case] want: Result<GetUsersResp, Code>,
+    ) {
+        // given
+        let mut db = MockDBClient::default();
+        db.expect_get_users(db_results);
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        // when
+        let got = service.get_users(Request::new(GetUsersReq { ids })).await;
+
+        // then
+        assert_response(got, want);
+    }
+}
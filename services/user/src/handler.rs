@@ -1,24 +1,29 @@
 use crate::{
     db::DBClient,
     proto::{
-        CreateUserReq, CreateUserResp, GetUserReq, GetUserResp, user_service_server::UserService,
+        CreateUserReq, CreateUserResp, DeleteUserReq, DeleteUserResp, GetUserByEmailReq,
+        GetUserByEmailResp, GetUserReq, GetUserResp, GetUsersReq, GetUsersResp, ListUsersReq,
+        ListUsersResp, PingReq, PingResp, UpdateUserReq, UpdateUserResp,
+        user_service_server::UserService,
     },
 };
-use common::UuidGenerator;
+use common::{Now, UuidGenerator};
 use tonic::{Request, Response, Status};
 use tracing::instrument;
 
 #[derive(Clone)]
-pub struct Handler<D, U> {
+pub struct Handler<D, U, N> {
     pub db: D,
     pub uuid: U,
+    pub now: N,
 }
 
 #[tonic::async_trait]
-impl<D, U> UserService for Handler<D, U>
+impl<D, U, N> UserService for Handler<D, U, N>
 where
     D: DBClient,
     U: UuidGenerator,
+    N: Now,
 {
     #[instrument(skip_all, fields(user_id), err)]
     async fn create_user(
@@ -32,4 +37,45 @@ where
     async fn get_user(&self, req: Request<GetUserReq>) -> Result<Response<GetUserResp>, Status> {
         self.get_user(req).await
     }
+
+    #[instrument(skip_all, err)]
+    async fn get_user_by_email(
+        &self,
+        req: Request<GetUserByEmailReq>,
+    ) -> Result<Response<GetUserByEmailResp>, Status> {
+        self.get_user_by_email(req).await
+    }
+
+    #[instrument(skip_all, err)]
+    async fn get_users(&self, req: Request<GetUsersReq>) -> Result<Response<GetUsersResp>, Status> {
+        self.get_users(req).await
+    }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn update_user(
+        &self,
+        req: Request<UpdateUserReq>,
+    ) -> Result<Response<UpdateUserResp>, Status> {
+        self.update_user(req).await
+    }
+
+    #[instrument(skip_all, fields(user_id), err)]
+    async fn delete_user(
+        &self,
+        req: Request<DeleteUserReq>,
+    ) -> Result<Response<DeleteUserResp>, Status> {
+        self.delete_user(req).await
+    }
+
+    #[instrument(skip_all, err)]
+    async fn list_users(
+        &self,
+        req: Request<ListUsersReq>,
+    ) -> Result<Response<ListUsersResp>, Status> {
+        self.list_users(req).await
+    }
+
+    async fn ping(&self, _: Request<PingReq>) -> Result<Response<PingResp>, Status> {
+        Ok(Response::new(PingResp {}))
+    }
 }
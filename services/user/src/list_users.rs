@@ -0,0 +1,118 @@
+use crate::{
+    db::DBClient,
+    error::Error,
+    handler::Handler,
+    proto::{ListUsersReq, ListUsersResp},
+};
+use common::{Now, UuidGenerator};
+use tonic::{Request, Response, Status};
+
+/// The maximum number of users returned per page.
+pub(crate) const MAX_LIST_USERS_LIMIT: i64 = 100;
+
+impl<D, U, N> Handler<D, U, N>
+where
+    D: DBClient,
+    U: UuidGenerator,
+    N: Now,
+{
+    /// Lists users page by page, ordered by id.
+    ///
+    /// # Errors
+    /// - invalid argument if the limit or offset is negative
+    /// - internal error if the users cannot be read from the db
+    pub async fn list_users(
+        &self,
+        req: Request<ListUsersReq>,
+    ) -> Result<Response<ListUsersResp>, Status> {
+        let req = req.into_inner();
+
+        let limit = page_limit(req.limit)?;
+        if req.offset < 0 {
+            return Err(Error::InvalidOffset(req.offset).into());
+        }
+
+        let users = self
+            .db
+            .list_users(limit, req.offset)
+            .await
+            .map_err(Error::ListUsers)?;
+
+        Ok(Response::new(ListUsersResp { users }))
+    }
+}
+
+/// Clamps the requested limit to [`MAX_LIST_USERS_LIMIT`]. An unset
+/// limit returns a full page.
+fn page_limit(limit: i64) -> Result<i64, Error> {
+    match limit {
+        ..0 => Err(Error::InvalidLimit(limit)),
+        0 => Ok(MAX_LIST_USERS_LIMIT),
+        _ => Ok(limit.min(MAX_LIST_USERS_LIMIT)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test::MockDBClient, error::DBError, fixture::fixture_user, proto::User};
+    use common::mock::{MockNow, MockUuidGenerator};
+    use rstest::rstest;
+    use tonic::Code;
+
+    #[rstest]
+    #[case::within_max(10, Some(10))]
+    #[case::max(100, Some(100))]
+    #[case::clamped(1000, Some(100))]
+    #[case::unset(0, Some(100))]
+    #[case::negative(-1, None)]
+    fn test_page_limit(#[case] limit: i64, #[case] want: Option<i64>) {
+        // when
+        let got = page_limit(limit);
+
+        // then
+        assert_eq!(got.ok(), want);
+    }
+
+    #[rstest]
+    #[case::happy(
+        ListUsersReq { limit: 1000, offset: 0 },
+        Ok(vec![fixture_user(|_| {})]),
+        Ok(ListUsersResp { users: vec![fixture_user(|_| {})] })
+    )]
+    #[case::negative_limit(
+        ListUsersReq { limit: -1, offset: 0 },
+        Ok(vec![]),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::negative_offset(
+        ListUsersReq { limit: 10, offset: -1 },
+        Ok(vec![]),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::internal_error(
+        ListUsersReq { limit: 10, offset: 0 },
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_list_users(
+        #[case] req: ListUsersReq,
+        #[case] db_result: Result<Vec<User>, DBError>,
+        #[case] want: Result<ListUsersResp, Code>,
+    ) {
+        use testutils::assert_response;
+
+        let mut db = MockDBClient::default();
+        db.expect_list_users([db_result]);
+
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        let got = service.list_users(Request::new(req)).await;
+        assert_response(got, want);
+    }
+}
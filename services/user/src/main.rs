@@ -1,19 +1,29 @@
 pub mod create_user;
 pub mod db;
+pub mod delete_user;
 pub mod error;
 pub mod get_user;
+pub mod get_user_by_email;
+pub mod get_users;
 pub mod handler;
+pub mod list_users;
 #[allow(clippy::all)]
 pub mod proto;
+pub mod update_user;
 
 #[cfg(test)]
 mod fixture;
 
-use crate::{handler::Handler, proto::user_service_server::UserServiceServer};
-use common::UuidV4Generator;
+use crate::{
+    handler::Handler,
+    proto::user_service_server::{self, UserServiceServer},
+};
+use common::{SystemNow, UuidV4Generator};
 use db::PostgresDBClient;
 use dotenv::dotenv;
-use setup::{middleware::TracingGrpcServiceLayer, tracing::init_tracer};
+use setup::{
+    health::grpc_health_service, middleware::TracingGrpcServiceLayer, tracing::init_tracer,
+};
 use std::error::Error;
 use user::{GRPC_PORT, SERVICE_NAME};
 
@@ -27,9 +37,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let pool = database::connect(&pg_cfg)?;
     database::run_migrations!(pool, "./migrations");
 
+    let health_pool = pool.clone();
+    let health = grpc_health_service(user_service_server::SERVICE_NAME, move || {
+        database::ping(health_pool.clone())
+    })
+    .await;
+
     let handler = Handler {
         db: PostgresDBClient::new(pool),
         uuid: UuidV4Generator,
+        now: SystemNow,
     };
 
     let addr = format!("0.0.0.0:{GRPC_PORT}").parse()?;
@@ -37,7 +54,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     println!("listening on :{GRPC_PORT}");
     let mut server = tonic::transport::Server::builder().layer(TracingGrpcServiceLayer);
-    server.add_service(svc).serve(addr).await.unwrap();
+    server
+        .add_service(health)
+        .add_service(svc)
+        .serve(addr)
+        .await
+        .unwrap();
 
     tracer.shutdown()?;
 
@@ -0,0 +1,123 @@
+use crate::{
+    db::DBClient,
+    error::{DBError, Error},
+    handler::Handler,
+    proto::{UpdateUserReq, UpdateUserResp},
+};
+use common::{Now, UuidGenerator};
+use setup::{validate_email, validate_user_id};
+use tonic::{Request, Response, Status};
+
+impl<D, U, N> Handler<D, U, N>
+where
+    D: DBClient,
+    U: UuidGenerator,
+    N: Now,
+{
+    /// Updates a user's display name and email.
+    ///
+    /// # Errors
+    /// - invalid argument if the id, name or email is missing, or the email is malformed
+    /// - not found if the user does not exist
+    /// - internal error if the user cannot be updated in the db
+    pub async fn update_user(
+        &self,
+        req: Request<UpdateUserReq>,
+    ) -> Result<Response<UpdateUserResp>, Status> {
+        let req = req.into_inner();
+        let user_id = validate_user_id(&req.id)?;
+
+        tracing::Span::current().record("user_id", user_id.to_string());
+
+        if req.name.is_empty() {
+            return Err(Error::MissingUserName.into());
+        }
+
+        if req.email.is_empty() {
+            return Err(Error::MissingUserEmail.into());
+        }
+        validate_email(&req.email)?;
+
+        let user = self
+            .db
+            .update_user(user_id, &req.name, &req.email, self.now.now())
+            .await
+            .map_err(|e| match e {
+                DBError::NotFound => Error::UserNotFound(user_id.to_string()),
+                _ => Error::UpdateUser(e),
+            })?;
+
+        Ok(Response::new(UpdateUserResp { user: Some(user) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db::test::MockDBClient,
+        error::DBError,
+        fixture::{fixture_update_user_req, fixture_user},
+        handler::Handler,
+        proto::{UpdateUserReq, UpdateUserResp, User},
+    };
+    use common::mock::{MockNow, MockUuidGenerator};
+    use rstest::rstest;
+    use tonic::{Code, Request};
+
+    #[rstest]
+    #[case::happy(
+        fixture_update_user_req(|_| {}),
+        Ok(fixture_user(|_| {})),
+        Ok(UpdateUserResp { user: Some(fixture_user(|_| {})) })
+    )]
+    #[case::missing_id(
+        fixture_update_user_req(|r| r.id.clear()),
+        Ok(fixture_user(|_| {})),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::missing_name(
+        fixture_update_user_req(|r| r.name.clear()),
+        Ok(fixture_user(|_| {})),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::missing_email(
+        fixture_update_user_req(|r| r.email.clear()),
+        Ok(fixture_user(|_| {})),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::invalid_email(
+        fixture_update_user_req(|r| r.email = "user.example.com".to_string()),
+        Ok(fixture_user(|_| {})),
+        Err(Code::InvalidArgument)
+    )]
+    #[case::not_found(
+        fixture_update_user_req(|_| {}),
+        Err(DBError::NotFound),
+        Err(Code::NotFound)
+    )]
+    #[case::internal_error(
+        fixture_update_user_req(|_| {}),
+        Err(DBError::Unknown),
+        Err(Code::Internal)
+    )]
+    #[tokio::test]
+    async fn test_update_user(
+        #[case] req: UpdateUserReq,
+        #[case] db_result: Result<User, DBError>,
+        #[case] want: Result<UpdateUserResp, Code>,
+    ) {
+        use testutils::assert_response;
+
+        let mut db = MockDBClient::default();
+        db.expect_update_user([db_result]);
+
+        let service = Handler {
+            db,
+            uuid: MockUuidGenerator::default(),
+            now: MockNow::default_time(),
+        };
+
+        let got = service.update_user(Request::new(req)).await;
+        assert_response(got, want);
+    }
+}
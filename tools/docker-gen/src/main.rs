@@ -1,54 +1,133 @@
 //! Generates optimized Dockerfiles for Rust workspace services.
 //! Analyzes dependencies and creates minimal workspace configs.
 
+use clap::Parser;
 use minijinja::{Environment, context};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use toml::Value;
 
+/// Dependency tables of a manifest, also used under `[target.'cfg(...)']`
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+#[derive(Debug, Parser)]
+#[command(about = "Generates optimized Dockerfiles for Rust workspace services")]
+struct Args {
+    /// Name of the service, defaults to the name of the current directory
+    #[arg(long)]
+    service: Option<String>,
+
+    /// Path of the workspace root, defaults to the current directory if
+    /// `--service` is given and to its parent otherwise
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+}
+
+impl Args {
+    /// Resolves the service name and the workspace root
+    fn resolve(self, current_dir: &Path) -> Result<(String, PathBuf), Box<dyn std::error::Error>> {
+        let Some(service_name) = self.service else {
+            let service_name = current_dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or("Could not determine service name from current directory")?;
+            let workspace_root = self
+                .workspace_root
+                .unwrap_or_else(|| current_dir.join(".."));
+            return Ok((service_name.to_string(), workspace_root));
+        };
+
+        let workspace_root = self
+            .workspace_root
+            .unwrap_or_else(|| current_dir.to_path_buf());
+        Ok((service_name, workspace_root))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct CopyFile {
     src: String,
     dest: String,
 }
 
+/// Workspace members a manifest depends on
+#[derive(Debug, Default, PartialEq)]
+struct MemberDependencies {
+    /// Members needed to build, including build and target dependencies
+    build: Vec<String>,
+    /// Members only needed as dev-dependencies, which may form cycles
+    dev: Vec<String>,
+}
+
 fn get_workspace_dependencies(
+    root: &Path,
     service_name: &str,
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let workspace_members = parse_workspace_members()?;
-    let mut required_members = HashSet::new();
+    let workspace_members = parse_workspace_members(root)?;
+    let mut graph = BTreeMap::new();
     let mut to_process = vec![service_name.to_string()];
 
     // Process dependencies transitively
     while let Some(current) = to_process.pop() {
-        if required_members.contains(&current) {
+        if graph.contains_key(&current) {
             continue;
         }
 
-        required_members.insert(current.clone());
+        let member_deps = parse_service_dependencies_for_path(
+            &root.join(&current).join("Cargo.toml"),
+            &current,
+            &workspace_members,
+        )?;
 
-        let member_deps = if current == service_name {
-            parse_service_dependencies_for_path("Cargo.toml")?
-        } else {
-            parse_service_dependencies_for_path(&format!("../{}/Cargo.toml", current))?
-        };
+        to_process.extend(member_deps.build.iter().chain(&member_deps.dev).cloned());
+        graph.insert(current, member_deps.build);
+    }
 
-        for dep in member_deps {
-            if workspace_members.contains(&dep) && !required_members.contains(&dep) {
-                to_process.push(dep);
-            }
+    Ok(topological_order(&graph)?)
+}
+
+/// Orders the members so that every member comes after the members it needs
+/// to build. Dev-dependencies are not edges, as cargo allows them to be cyclic.
+fn topological_order(graph: &BTreeMap<String, Vec<String>>) -> Result<Vec<String>, String> {
+    fn visit<'a>(
+        member: &'a str,
+        graph: &'a BTreeMap<String, Vec<String>>,
+        path: &mut Vec<&'a str>,
+        order: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if order.iter().any(|m| m == member) {
+            return Ok(());
         }
+
+        if let Some(start) = path.iter().position(|m| *m == member) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(member);
+            return Err(format!("Dependency cycle: {}", cycle.join(" -> ")));
+        }
+
+        path.push(member);
+        for dep in graph.get(member).into_iter().flatten() {
+            visit(dep, graph, path, order)?;
+        }
+        path.pop();
+
+        order.push(member.to_string());
+        Ok(())
     }
 
-    let mut sorted_members: Vec<String> = required_members.into_iter().collect();
-    sorted_members.sort();
-    Ok(sorted_members)
+    let mut order = Vec::new();
+    for member in graph.keys() {
+        visit(member, graph, &mut Vec::new(), &mut order)?;
+    }
+
+    Ok(order)
 }
 
-fn parse_workspace_members() -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let workspace_content = fs::read_to_string("../Cargo.toml")?;
+fn parse_workspace_members(root: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let workspace_content = fs::read_to_string(root.join("Cargo.toml"))?;
     let workspace_toml: Value = toml::from_str(&workspace_content)?;
 
     let members = workspace_toml
@@ -67,12 +146,21 @@ fn parse_workspace_members() -> Result<Vec<String>, Box<dyn std::error::Error>>
 }
 
 fn parse_service_dependencies_for_path(
-    cargo_path: &str,
-) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    cargo_path: &Path,
+    member: &str,
+    workspace_members: &[String],
+) -> Result<MemberDependencies, Box<dyn std::error::Error>> {
     let service_content = fs::read_to_string(cargo_path)?;
-    let service_toml: Value = toml::from_str(&service_content)?;
-    let workspace_members = parse_workspace_members()?;
-    let mut dependencies = Vec::new();
+    parse_service_dependencies(&service_content, member, workspace_members)
+}
+
+fn parse_service_dependencies(
+    service_content: &str,
+    member: &str,
+    workspace_members: &[String],
+) -> Result<MemberDependencies, Box<dyn std::error::Error>> {
+    let service_toml: Value = toml::from_str(service_content)?;
+    let mut dependencies = MemberDependencies::default();
 
     let extract_path_deps = |deps: &toml::map::Map<String, Value>| {
         let mut path_deps = Vec::new();
@@ -86,10 +174,9 @@ fn parse_service_dependencies_for_path(
             // Check if dependency is a workspace member
             if let Some(dep_table) = dep_value.as_table() {
                 if let Some(path) = dep_table.get("path").and_then(|p| p.as_str()) {
-                    if let Some(member_path) = path.strip_prefix("../") {
-                        if workspace_members.contains(&member_path.to_string()) {
-                            path_deps.push(member_path.to_string());
-                        }
+                    let member_path = resolve_member_path(member, path);
+                    if workspace_members.contains(&member_path) {
+                        path_deps.push(member_path);
                     }
                 }
             }
@@ -98,27 +185,105 @@ fn parse_service_dependencies_for_path(
         path_deps
     };
 
-    // Check dependencies
-    if let Some(deps) = service_toml.get("dependencies").and_then(|d| d.as_table()) {
-        dependencies.extend(extract_path_deps(deps));
+    for (table, deps) in dependency_tables(&service_toml) {
+        let path_deps = extract_path_deps(deps);
+        if table == "dev-dependencies" {
+            dependencies.dev.extend(path_deps);
+        } else {
+            dependencies.build.extend(path_deps);
+        }
     }
 
-    // Check dev-dependencies
-    if let Some(dev_deps) = service_toml
-        .get("dev-dependencies")
-        .and_then(|d| d.as_table())
-    {
-        dependencies.extend(extract_path_deps(dev_deps));
+    for deps in [&mut dependencies.build, &mut dependencies.dev] {
+        deps.sort();
+        deps.dedup();
     }
 
     Ok(dependencies)
 }
 
+/// Returns the dependency tables of a manifest, both the top-level ones and
+/// the ones of every target
+fn dependency_tables(manifest: &Value) -> Vec<(&'static str, &toml::map::Map<String, Value>)> {
+    let targets = manifest
+        .get("target")
+        .and_then(|t| t.as_table())
+        .into_iter()
+        .flat_map(|t| t.values());
+
+    std::iter::once(manifest)
+        .chain(targets)
+        .flat_map(|m| {
+            DEPENDENCY_TABLES
+                .into_iter()
+                .filter_map(move |table| Some((table, m.get(table)?.as_table()?)))
+        })
+        .collect()
+}
+
+/// Checks that the path dependencies of every required member are required
+/// members too, so that the minimal workspace builds in isolation
+fn verify_path_dependencies(
+    root: &Path,
+    required_members: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for member in required_members {
+        let content = fs::read_to_string(root.join(member).join("Cargo.toml"))?;
+        check_path_dependencies(&content, member, required_members)?;
+    }
+
+    Ok(())
+}
+
+fn check_path_dependencies(
+    member_content: &str,
+    member: &str,
+    required_members: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let member_toml: Value = toml::from_str(member_content)?;
+
+    for (_, deps) in dependency_tables(&member_toml) {
+        for (dep_name, dep_value) in deps {
+            let Some(path) = dep_value.get("path").and_then(|p| p.as_str()) else {
+                continue;
+            };
+
+            let dep_path = resolve_member_path(member, path);
+            if !required_members.contains(&dep_path) {
+                return Err(format!(
+                    "Member '{}' has path dependency '{}' on '{}', which is not in the minimal workspace",
+                    member, dep_name, dep_path
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves a dependency path relative to a member, e.g. "../common" of
+/// "pkg/database" to "pkg/common"
+fn resolve_member_path(member: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = member.split('/').collect();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
 fn create_minimal_workspace(
+    root: &Path,
     service_name: &str,
     required_members: &[String],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let workspace_content = fs::read_to_string("../Cargo.toml")?;
+    let workspace_content = fs::read_to_string(root.join("Cargo.toml"))?;
     let mut workspace_toml: toml::Value = toml::from_str(&workspace_content)?;
 
     if let Some(workspace) = workspace_toml.get_mut("workspace") {
@@ -137,9 +302,10 @@ fn create_minimal_workspace(
 
     let minimal_workspace_toml = toml::to_string_pretty(&workspace_toml)?;
 
-    fs::create_dir_all("../.docker-gen")?;
+    let out_dir = root.join(".docker-gen");
+    fs::create_dir_all(&out_dir)?;
     fs::write(
-        &format!("../.docker-gen/Cargo.toml.{}", service_name),
+        out_dir.join(format!("Cargo.toml.{}", service_name)),
         minimal_workspace_toml,
     )?;
 
@@ -147,6 +313,7 @@ fn create_minimal_workspace(
 }
 
 fn generate_dockerfile(
+    root: &Path,
     service_name: &str,
     required_members: &[String],
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -162,7 +329,7 @@ fn generate_dockerfile(
         copy_files => copy_files
     })?;
 
-    fs::write("Dockerfile", rendered)?;
+    fs::write(root.join(service_name).join("Dockerfile"), rendered)?;
     Ok(())
 }
 
@@ -189,21 +356,191 @@ fn build_copy_files(service_name: &str, required_members: &[String]) -> Vec<Copy
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let current_dir = env::current_dir()?;
-    let service_name = current_dir
-        .file_name()
-        .and_then(|name| name.to_str())
-        .ok_or("Could not determine service name from current directory")?;
+    let (service_name, root) = Args::parse().resolve(&env::current_dir()?)?;
 
     // Resolve member dependencies
-    let required_members = get_workspace_dependencies(service_name)?;
+    let required_members = get_workspace_dependencies(&root, &service_name)?;
     println!("Workspace dependencies: {:?}", required_members);
+    verify_path_dependencies(&root, &required_members)?;
 
     // Create minimal workspace config
-    create_minimal_workspace(service_name, &required_members)?;
+    create_minimal_workspace(&root, &service_name, &required_members)?;
 
     // Generate Dockerfile
-    generate_dockerfile(service_name, &required_members)?;
+    generate_dockerfile(&root, &service_name, &required_members)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(member, deps)| {
+                let deps = deps.iter().map(|d| d.to_string()).collect();
+                (member.to_string(), deps)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_service_dependencies() {
+        // given
+        let members = vec![
+            String::from("auth"),
+            String::from("pkg/common"),
+            String::from("pkg/proto"),
+            String::from("pkg/testutils"),
+            String::from("pkg/unix"),
+        ];
+        let content = r#"
+            [package]
+            name = "auth"
+
+            [dependencies]
+            common = { path = "../pkg/common" }
+            tokio = "1"
+
+            [dev-dependencies]
+            common = { path = "../pkg/common", features = ["mock"] }
+            testutils = { path = "../pkg/testutils" }
+
+            [build-dependencies]
+            proto = { path = "../pkg/proto" }
+
+            [target.'cfg(unix)'.dependencies]
+            unix = { path = "../pkg/unix" }
+        "#;
+
+        // when
+        let got = parse_service_dependencies(content, "auth", &members).unwrap();
+
+        // then
+        assert_eq!(
+            got,
+            MemberDependencies {
+                build: vec![
+                    String::from("pkg/common"),
+                    String::from("pkg/proto"),
+                    String::from("pkg/unix"),
+                ],
+                dev: vec![String::from("pkg/common"), String::from("pkg/testutils")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_member_dependencies() {
+        // given
+        let members = vec![String::from("pkg/common"), String::from("pkg/database")];
+        let content = r#"
+            [dependencies]
+            common = { path = "../common" }
+        "#;
+
+        // when
+        let got = parse_service_dependencies(content, "pkg/database", &members).unwrap();
+
+        // then
+        assert_eq!(got.build, vec![String::from("pkg/common")]);
+    }
+
+    #[test]
+    fn test_topological_order_diamond() {
+        // given
+        let graph = graph(&[
+            ("auth", &["pkg/database", "pkg/setup"]),
+            ("pkg/database", &["pkg/common"]),
+            ("pkg/setup", &["pkg/common"]),
+            ("pkg/common", &[]),
+        ]);
+
+        // when
+        let got = topological_order(&graph).unwrap();
+
+        // then
+        assert_eq!(got, vec!["pkg/common", "pkg/database", "pkg/setup", "auth"]);
+    }
+
+    #[test]
+    fn test_topological_order_cycle() {
+        // given
+        let graph = graph(&[
+            ("auth", &["pkg/database"]),
+            ("pkg/database", &["pkg/testutils"]),
+            ("pkg/testutils", &["pkg/database"]),
+        ]);
+
+        // when
+        let got = topological_order(&graph);
+
+        // then
+        assert_eq!(
+            got,
+            Err(String::from(
+                "Dependency cycle: pkg/database -> pkg/testutils -> pkg/database"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_check_path_dependencies() {
+        // given
+        let required = vec![String::from("pkg/common"), String::from("pkg/database")];
+        let content = r#"
+            [dependencies]
+            common = { path = "../common" }
+            tokio = "1"
+        "#;
+
+        // when
+        let got = check_path_dependencies(content, "pkg/database", &required);
+
+        // then
+        assert!(got.is_ok());
+    }
+
+    #[test]
+    fn test_check_path_dependencies_dangling() {
+        // given
+        let required = vec![String::from("pkg/common"), String::from("pkg/database")];
+        let content = r#"
+            [dependencies]
+            common = { path = "../common" }
+
+            [target.'cfg(unix)'.dependencies]
+            unix = { path = "../unix" }
+        "#;
+
+        // when
+        let got = check_path_dependencies(content, "pkg/database", &required);
+
+        // then
+        assert_eq!(
+            got.unwrap_err().to_string(),
+            "Member 'pkg/database' has path dependency 'unix' on 'pkg/unix', which is not in the minimal workspace"
+        );
+    }
+
+    #[test]
+    fn test_service_flag_resolves_same_members() {
+        // given
+        let services = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../services");
+        let from_flag = Args::parse_from(["docker-gen", "--service", "auth"]);
+        let from_cwd = Args::parse_from(["docker-gen"]);
+
+        // when
+        let (flag_service, flag_root) = from_flag.resolve(&services).unwrap();
+        let (cwd_service, cwd_root) = from_cwd.resolve(&services.join("auth")).unwrap();
+
+        // then
+        assert_eq!(flag_service, cwd_service);
+        assert_eq!(
+            get_workspace_dependencies(&flag_root, &flag_service).unwrap(),
+            get_workspace_dependencies(&cwd_root, &cwd_service).unwrap()
+        );
+    }
+}
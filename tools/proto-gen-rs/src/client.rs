@@ -1,44 +1,86 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use heck::{ToSnakeCase, ToUpperCamelCase};
 use prost_types::{FileDescriptorSet, ServiceDescriptorProto};
 use std::{fs, path::Path};
 
+/// Package prefix of the protobuf well-known types
+const WELL_KNOWN_PACKAGE: &str = "google.protobuf.";
+
 /// Generates wrapper clients for every service found
+///
+/// A single service is written to `client.rs` and named after the proto
+/// directory. Multiple services are written to `client/<service>.rs`, named
+/// after each service, and re-exported from `client.rs`.
 pub(crate) fn generate_client<P: AsRef<Path>>(
     src_dir: &P,
     proto_dir: &P,
     fds: &FileDescriptorSet,
 ) -> Result<()> {
     let file = find_target_file(&fds);
-
-    if file.service.len() != 1 {
-        panic!(
-            "Proto file '{}' contains {} services, but exactly 1 is required.",
-            file.name.as_ref().unwrap_or(&String::new()),
-            file.service.len()
-        );
-    }
-
-    if file.service.len() != 1 {
-        panic!(
-            "Proto file '{}' contains {} services, but exactly 1 is required.",
-            file.name.as_ref().unwrap_or(&String::new()),
-            file.service.len()
-        );
+    let src_dir = src_dir.as_ref();
+
+    match file.service.as_slice() {
+        [] => bail!(
+            "Proto file '{}' contains no service.",
+            file.name.as_deref().unwrap_or_default()
+        ),
+        [service] => {
+            let service_name = proto_dir
+                .as_ref()
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+
+            let code = generate_client_code(service, &service_name)?;
+            fs::write(src_dir.join("client.rs"), code)?;
+        }
+        services => {
+            let client_dir = src_dir.join("client");
+            fs::create_dir_all(&client_dir)?;
+
+            let mut names = Vec::new();
+            for service in services {
+                let name = client_name(service);
+                let code = generate_client_code(service, &name)?;
+                fs::write(
+                    client_dir.join(format!("{}.rs", name.to_snake_case())),
+                    code,
+                )?;
+                names.push(name);
+            }
+
+            fs::write(src_dir.join("client.rs"), generate_client_mod(&names))?;
+        }
     }
 
-    let service_name = std::path::Path::new(proto_dir.as_ref())
-        .file_name()
-        .unwrap()
-        .to_string_lossy()
-        .to_string();
+    Ok(())
+}
 
-    let service = &file.service[0];
-    let code = generate_client_code(service, &service_name)?;
-    let fname = format!("{}/client.rs", src_dir.as_ref().to_string_lossy());
-    fs::write(fname, code)?;
+/// Name of the client of a service, e.g. "Admin" for "AdminService"
+fn client_name(svc: &ServiceDescriptorProto) -> String {
+    let name = svc.name.as_deref().unwrap();
+    match name.strip_suffix("Service") {
+        Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+        _ => name.to_string(),
+    }
+}
 
-    Ok(())
+/// Generate the module that declares and re-exports the per-service clients
+fn generate_client_mod(names: &[String]) -> String {
+    let mut code = String::from("// This file is generated.\n");
+    for name in names {
+        code.push_str(&format!("pub mod {};\n", name.to_snake_case()));
+    }
+    code.push('\n');
+    for name in names {
+        let name = name.to_upper_camel_case();
+        code.push_str(&format!(
+            "pub use {}::{{I{name}Client, {name}Client}};\n",
+            name.to_snake_case()
+        ));
+    }
+    code
 }
 
 /// Generate a client module for a single service
@@ -49,33 +91,69 @@ fn generate_client_code(svc: &ServiceDescriptorProto, svc_name: &str) -> Result<
     let proto_service_name_snake = proto_service_name.to_snake_case();
     let proto_service_client = format!("{}Client", proto_service_name);
 
-    let (trait_methods, impl_methods, mock_field_decls, mock_field_inits, mock_impl) =
+    let (trait_methods, impl_methods, mock_field_decls, mock_field_inits, mock_calls, mock_impl) =
         generate_methods(svc)?;
 
     let imports = generate_imports(svc, &proto_service_name_snake, &proto_service_client);
+    let (stream_imports, mock_stream_imports) = if has_server_streaming(svc) {
+        (
+            "use tonic::codegen::BoxStream;\n",
+            "    use tokio::sync::mpsc;\n    use tonic::codegen::tokio_stream::wrappers::ReceiverStream;\n",
+        )
+    } else {
+        ("", "")
+    };
 
     Ok(format!(
         r#"// This file is generated.
 use crate::GRPC_PORT;
 use crate::SERVICE_NAME;
 {imports}
-use setup::{{middleware::tracing::TracingServiceClient, patched_host}};
-use std::{{error::Error, str::FromStr as _}};
-use tonic::transport::{{Channel, Endpoint}};
+use setup::endpoint::{{
+    ConnectRetry, DEFAULT_REQUEST_TIMEOUT, connect_with_retry, deadline_exceeded, service_endpoint,
+}};
+use setup::middleware::tracing::TracingServiceClient;
+use std::error::Error;
+use std::time::Duration;
+{stream_imports}use tonic::transport::Channel;
 use tonic::{{Request, Response, Status, async_trait}};
 
 #[derive(Clone)]
 pub struct {svc_name}Client({proto_service_client}<TracingServiceClient<Channel>>);
 
 impl {svc_name}Client {{
+    /// Creates a client with the [`DEFAULT_REQUEST_TIMEOUT`].
     pub async fn new() -> Result<Self, Box<dyn Error>> {{
-        let host = patched_host(String::from(SERVICE_NAME));
-        let endpoint = Endpoint::from_str(&format!("http://{{host}}:{{GRPC_PORT}}"))?;
-        let channel = endpoint.connect().await?;
-        let client = TracingServiceClient::new(channel);
-        let client = {proto_service_client}::new(client);
+        Self::new_with_timeout(DEFAULT_REQUEST_TIMEOUT).await
+    }}
+
+    /// Creates a client whose requests fail with `DeadlineExceeded`
+    /// if they take longer than `timeout`.
+    ///
+    /// Connecting is retried as configured by [`ConnectRetry::from_env`].
+    pub async fn new_with_timeout(timeout: Duration) -> Result<Self, Box<dyn Error>> {{
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(timeout);
+        let channel = connect_with_retry(&endpoint, ConnectRetry::from_env()?).await?;
 
-        Ok(Self(client))
+        Ok(Self::from_channel(channel))
+    }}
+
+    /// Creates a client that connects on its first request.
+    ///
+    /// Unlike [`Self::new`], this does not wait for the service to be up,
+    /// so callers can start in any order and recover once it becomes
+    /// reachable. The tradeoff is that a misconfigured or unreachable
+    /// service is only noticed when the first request fails.
+    pub fn lazy() -> Result<Self, Box<dyn Error>> {{
+        let endpoint = service_endpoint(SERVICE_NAME, GRPC_PORT)?.timeout(DEFAULT_REQUEST_TIMEOUT);
+        let channel = endpoint.connect_lazy();
+
+        Ok(Self::from_channel(channel))
+    }}
+
+    fn from_channel(channel: Channel) -> Self {{
+        let client = TracingServiceClient::new(channel);
+        Self({proto_service_client}::new(client))
     }}
 }}
 
@@ -94,8 +172,10 @@ impl I{svc_name}Client for {svc_name}Client {{
 #[cfg(feature = "testutils")]
 pub mod testutils {{
     use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{{AtomicUsize, Ordering}};
     use tokio::sync::Mutex;
-    use tonic::{{Request, Response, Status}};
+{mock_stream_imports}    use tonic::{{Request, Response, Status}};
 
     #[rustfmt::skip]
     pub struct Mock{svc_name}Client {{
@@ -110,6 +190,11 @@ pub mod testutils {{
         }}
     }}
 
+    #[rustfmt::skip]
+    impl Mock{svc_name}Client {{
+{mock_calls}
+    }}
+
     #[rustfmt::skip]
     #[async_trait]
     impl I{svc_name}Client for Mock{svc_name}Client {{
@@ -118,74 +203,133 @@ pub mod testutils {{
 }}
 "#,
         imports = imports,
+        stream_imports = stream_imports,
+        mock_stream_imports = mock_stream_imports,
         svc_name = svc_name,
         trait_methods = trait_methods,
         impl_methods = impl_methods,
         mock_field_decls = mock_field_decls,
         mock_field_inits = mock_field_inits,
+        mock_calls = mock_calls,
         mock_impl = mock_impl,
         proto_service_client = proto_service_client,
     ))
 }
 
-/// Generates all RPC method blocks, plus separate mock decls and inits
+/// Generates all RPC method blocks, plus separate mock decls, inits,
+/// response setters and call counters
 fn generate_methods(
     svc: &ServiceDescriptorProto,
-) -> Result<(String, String, String, String, String)> {
+) -> Result<(String, String, String, String, String, String)> {
     let mut trait_methods_vec = Vec::new();
     let mut impl_methods_vec = Vec::new();
     let mut mock_field_decls_vec = Vec::new();
     let mut mock_field_inits_vec = Vec::new();
+    let mut mock_calls_vec = Vec::new();
     let mut mock_impl_vec = Vec::new();
 
     for m in &svc.method {
         let method_name = m.name.as_ref().unwrap();
         let method_snake = method_name.to_snake_case();
 
+        if m.client_streaming() {
+            eprintln!(
+                "warning: skipping client streaming method '{}.{}', which is not supported",
+                svc.name(),
+                method_name
+            );
+            continue;
+        }
+
         let input = rust_type(m.input_type());
         let output = rust_type(m.output_type());
 
+        // server streaming responses are boxed so that the mock can back
+        // them with a channel
+        let (response, call, mock_resp, into_response) = if m.server_streaming() {
+            (
+                format!("BoxStream<{output}>"),
+                format!(
+                    "let resp = self.0.clone().{method_snake}(req).await.map_err(deadline_exceeded)?;\n        Ok(resp.map(|stream| Box::pin(stream) as BoxStream<{output}>))"
+                ),
+                format!("mpsc::Receiver<Result<{output}, Status>>"),
+                format!(
+                    "|rx| Response::new(Box::pin(ReceiverStream::new(rx)) as BoxStream<{output}>)"
+                ),
+            )
+        } else {
+            (
+                output.clone(),
+                format!("self.0.clone().{method_snake}(req).await.map_err(deadline_exceeded)"),
+                output.clone(),
+                String::from("Response::new"),
+            )
+        };
+
         // trait signature
         trait_methods_vec.push(format!(
-        "    async fn {method_snake}(&self, req: Request<{input}>) -> Result<Response<{output}>, Status>;",
+        "    async fn {method_snake}(&self, req: Request<{input}>) -> Result<Response<{response}>, Status>;",
         method_snake = method_snake,
         input = input,
-        output = output
+        response = response
     ));
 
         // impl calling tonic client
         impl_methods_vec.push(format!(
-            r#"    async fn {method_snake}(&self, req: Request<{input}>) -> Result<Response<{output}>, Status> {{
-        self.0.clone().{method_snake}(req).await
+            r#"    async fn {method_snake}(&self, req: Request<{input}>) -> Result<Response<{response}>, Status> {{
+        {call}
     }}"#,
             method_snake = method_snake,
             input = input,
-            output = output
+            response = response,
+            call = call
         ));
 
         // mock struct field declarations
         mock_field_decls_vec.push(format!(
-        "        pub {method_snake}_req: Mutex<Option<{input}>>,\n        pub {method_snake}_resp: Mutex<Option<Result<{output}, Status>>>,",
+        "        pub {method_snake}_req: Mutex<Option<{input}>>,\n        pub {method_snake}_resp: Mutex<VecDeque<Result<{mock_resp}, Status>>>,\n        {method_snake}_call_count: AtomicUsize,",
         method_snake = method_snake,
         input = input,
-        output = output
+        mock_resp = mock_resp
     ));
 
         // mock initializers
         mock_field_inits_vec.push(format!(
-        "                {method_snake}_req: Mutex::new(None),\n                {method_snake}_resp: Mutex::new(None),",
+        "                {method_snake}_req: Mutex::new(None),\n                {method_snake}_resp: Mutex::new(VecDeque::new()),\n                {method_snake}_call_count: AtomicUsize::new(0),",
         method_snake = method_snake
     ));
 
+        // mock response setter and call counter
+        mock_calls_vec.push(format!(
+            r#"        /// Queues the result of the next unanswered `{method_snake}` call.
+        pub fn push_{method_snake}_resp(&mut self, resp: Result<{mock_resp}, Status>) -> &mut Self {{
+            self.{method_snake}_resp.get_mut().push_back(resp);
+            self
+        }}
+        /// Returns how often `{method_snake}` was called.
+        pub fn {method_snake}_calls(&self) -> usize {{
+            self.{method_snake}_call_count.load(Ordering::SeqCst)
+        }}"#,
+            method_snake = method_snake,
+            mock_resp = mock_resp
+        ));
+
         // mock method impl
         mock_impl_vec.push(format!(
-            r#"        async fn {method_snake}(&self, req: Request<{input}>) -> Result<Response<{output}>, Status> {{
+            r#"        async fn {method_snake}(&self, req: Request<{input}>) -> Result<Response<{response}>, Status> {{
+            self.{method_snake}_call_count.fetch_add(1, Ordering::SeqCst);
             *self.{method_snake}_req.lock().await = Some(req.into_inner());
-            self.{method_snake}_resp.lock().await.take().unwrap().map(Response::new)
+            self.{method_snake}_resp
+                .lock()
+                .await
+                .pop_front()
+                .unwrap_or_else(|| panic!("{method_snake} called more times than configured"))
+                .map({into_response})
         }}"#,
             method_snake = method_snake,
             input = input,
-            output = output
+            response = response,
+            into_response = into_response
         ));
     }
 
@@ -193,6 +337,7 @@ fn generate_methods(
     let impl_methods = impl_methods_vec.join("\n");
     let mock_field_decls = mock_field_decls_vec.join("\n");
     let mock_field_inits = mock_field_inits_vec.join("\n");
+    let mock_calls = mock_calls_vec.join("\n");
     let mock_impl = mock_impl_vec.join("\n");
 
     Ok((
@@ -200,18 +345,40 @@ fn generate_methods(
         impl_methods,
         mock_field_decls,
         mock_field_inits,
+        mock_calls,
         mock_impl,
     ))
 }
 
+/// Whether the service has a supported server streaming method
+fn has_server_streaming(svc: &ServiceDescriptorProto) -> bool {
+    svc.method
+        .iter()
+        .any(|m| m.server_streaming() && !m.client_streaming())
+}
+
 /// Extract "MyMessage" from ".mypackage.MyMessage" (or "MyMessage")
+///
+/// Well-known types map to the paths prost generates for them, which must
+/// match the `extern_path`s in `generate_protos`.
 fn rust_type(proto_type: &str) -> String {
-    proto_type
+    let proto_type = proto_type.trim_start_matches('.');
+    if let Some(wkt) = proto_type.strip_prefix(WELL_KNOWN_PACKAGE) {
+        return match wkt {
+            "Empty" => String::from("()"),
+            "Timestamp" => String::from("::prost_wkt_types::Timestamp"),
+            _ => format!("::prost_types::{wkt}"),
+        };
+    }
+
+    proto_type.split('.').next_back().unwrap().to_string()
+}
+
+/// Whether the type is generated into `crate::proto`
+fn is_local_type(proto_type: &str) -> bool {
+    !proto_type
         .trim_start_matches('.')
-        .split('.')
-        .next_back()
-        .unwrap()
-        .to_string()
+        .starts_with(WELL_KNOWN_PACKAGE)
 }
 
 /// Find all messages used by the service
@@ -223,9 +390,12 @@ fn generate_imports(
     use std::collections::BTreeSet;
     let mut imports: BTreeSet<String> = BTreeSet::new();
 
-    for m in &svc.method {
-        imports.insert(rust_type(m.input_type()));
-        imports.insert(rust_type(m.output_type()));
+    for m in svc.method.iter().filter(|m| !m.client_streaming()) {
+        for ty in [m.input_type(), m.output_type()] {
+            if is_local_type(ty) {
+                imports.insert(rust_type(ty));
+            }
+        }
     }
 
     imports.insert(format!(
@@ -262,3 +432,51 @@ fn find_target_file<'a>(fds: &'a FileDescriptorSet) -> &'a prost_types::FileDesc
 
     candidates[0]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::compile_proto;
+
+    #[test]
+    fn test_well_known_types() {
+        // given
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/well_known/api.proto");
+        let fds = compile_proto(&path).unwrap();
+        let file = find_target_file(&fds);
+
+        // when
+        let code = generate_client_code(&file.service[0], "well_known").unwrap();
+
+        // then
+        assert!(code.contains("Request<()>) -> Result<Response<GetItemResp>, Status>"));
+        assert!(code.contains("Request<GetItemReq>) -> Result<Response<()>, Status>"));
+        assert!(code.contains("Result<Response<::prost_wkt_types::Timestamp>, Status>"));
+        assert!(code.contains("Result<Response<::prost_types::Duration>, Status>"));
+        assert!(!code.contains("use crate::proto::Empty;"));
+        assert!(!code.contains("use crate::proto::Timestamp;"));
+        assert!(!code.contains("use crate::proto::Duration;"));
+    }
+
+    #[test]
+    fn test_streaming_methods() {
+        // given
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/streaming/api.proto");
+        let fds = compile_proto(&path).unwrap();
+        let file = find_target_file(&fds);
+
+        // when
+        let code = generate_client_code(&file.service[0], "streaming").unwrap();
+
+        // then
+        assert!(code.contains(
+            "fn get_item(&self, req: Request<GetItemReq>) -> Result<Response<Item>, Status>"
+        ));
+        assert!(code.contains(
+            "fn watch_items(&self, req: Request<GetItemReq>) -> Result<Response<BoxStream<Item>>, Status>"
+        ));
+        assert!(code.contains("pub watch_items_resp: Mutex<VecDeque<Result<mpsc::Receiver<Result<Item, Status>>, Status>>>"));
+        assert!(code.contains("use tonic::codegen::BoxStream;"));
+        assert!(!code.contains("upload_items"));
+    }
+}
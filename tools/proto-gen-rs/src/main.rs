@@ -38,7 +38,7 @@ fn main() -> anyhow::Result<()> {
             &package_name,
         );
 
-        // Generate custom client code into src/client.rs
+        // Generate custom client code into src/client.rs (and src/client/)
         for proto_path in proto_files {
             let fds = compile_proto(&proto_path)?;
             generate_client(&src_dir, &current_dir, &fds)?;